@@ -0,0 +1,41 @@
+//! compares diff modes across input sizes and similarity levels
+//!
+//! run with `cargo bench --features bench --bench modes`. the linear-space
+//! and myers variants aren't implemented yet; add them to `DiffMode` when
+//! they are and they'll be measured on the same grid.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ctdiff::attack::AttackScenario;
+use ctdiff::bench::{bench_config, workloads, DiffMode, DEFAULT_SIZES};
+use ctdiff::ConstantTimeDiff;
+
+fn benchmark_modes(c: &mut Criterion, group_name: &str, scenarios: &[AttackScenario]) {
+    let differ = ConstantTimeDiff::new(bench_config(usize::MAX));
+    let mut group = c.benchmark_group(group_name);
+
+    for workload in workloads(scenarios, DEFAULT_SIZES) {
+        group.throughput(Throughput::Bytes(workload.bytes()));
+        for mode in DiffMode::ALL {
+            group.bench_with_input(
+                BenchmarkId::new(mode.name(), &workload.name),
+                &workload,
+                |b, workload| {
+                    b.iter(|| mode.run(&differ, black_box(&workload.left), black_box(&workload.right)))
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+fn benchmark_similarity(c: &mut Criterion) {
+    benchmark_modes(c, "similarity", &[AttackScenario::SimilarityGradient]);
+}
+
+fn benchmark_realistic(c: &mut Criterion) {
+    benchmark_modes(c, "realistic", &[AttackScenario::VersionControl, AttackScenario::CodeReview]);
+}
+
+criterion_group!(benches, benchmark_similarity, benchmark_realistic);
+criterion_main!(benches);
@@ -129,7 +129,10 @@ fn automated_testing_example() -> Result<(), Box<dyn std::error::Error>> {
     
     for test_case in test_cases {
         let result = run_similarity_test(test_case)?;
-        println!("   Test Result: {:?}", result);
+        println!(
+            "   Test Result: {} passed={} similarity={:.2} edit_distance={}",
+            result.name, result.passed, result.similarity, result.edit_distance
+        );
     }
     println!();
     
@@ -2,8 +2,8 @@
 //! 
 //! demonstrates security features and best practices
 
-use ctdiff::{DiffBuilder, SecurityLevel, OutputFormat};
-use ctdiff::security::{SecurityConfig, TimingProtection};
+use ctdiff::{DiffBuilder, SecurityLevel};
+use ctdiff::security::{AffixTrim, SecurityConfig, TimingProtection, PaddingStrategy};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Security Configuration Examples ===\n");
@@ -88,8 +88,14 @@ fn custom_security_config() -> Result<(), Box<dyn std::error::Error>> {
         padding_size: Some(4096), // fixed padding size
         validate_inputs: true,
         max_edit_distance: Some(512), // limit computation
+        padding_byte: PaddingStrategy::default(),
         memory_protection: true,
         timing_protection: TimingProtection::Strict,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     
     println!("   Custom Configuration:");
@@ -148,7 +154,7 @@ fn secure_file_handling() -> Result<(), Box<dyn std::error::Error>> {
     let large_file = "/tmp/large_secure.txt";
     
     std::fs::write(small_file, b"small secure content")?;
-    std::fs::write(large_file, &vec![b'x'; 10000])?; // 10KB file
+    std::fs::write(large_file, vec![b'x'; 10000])?; // 10KB file
     
     println!("   Testing file size limits:");
     
@@ -2,10 +2,25 @@
 //! 
 //! implements a modified myers algorithm that resists timing attacks by
 //! ensuring execution time depends only on input sizes, not content patterns.
+//!
+//! with the `tracing` feature, matrix fill, script extraction and padding
+//! run inside debug-level spans. spans and events record lengths, padding
+//! size and edit distance only; input bytes are never recorded, so enabling
+//! logs can't leak the data being compared.
 
 use crate::primitives::{ct_bytes_eq, ct_min};
-use crate::types::{DiffOperation, DiffResult, DiffError, SecurityConfig};
-use subtle::{Choice, ConditionallySelectable};
+use crate::granularity::{Granularity, Tokenizer};
+use crate::types::{split_lines, AffixTrim, DiffOperation, DiffResult, DiffError, LineDiffResult, LineOperation, PaddingStrategy, SecurityConfig, TokenDiffResult, TokenOperation};
+use crate::security::TimingProtection;
+use rand::RngCore;
+use std::borrow::Cow;
+use std::ops::{Index, IndexMut};
+use std::sync::Mutex;
+use std::time::Instant;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater};
+
+/// rows of the matrix fill between deadline checks in `diff_with_deadline`
+const DEADLINE_CHECK_ROWS: usize = 64;
 
 /// constant-time myers diff algorithm implementation
 /// 
@@ -15,12 +30,24 @@ use subtle::{Choice, ConditionallySelectable};
 #[derive(Clone, Debug)]
 pub struct ConstantTimeDiff {
     config: SecurityConfig,
+    scratch: Option<ScratchPool>,
 }
 
 impl ConstantTimeDiff {
     /// create new diff computer with given security configuration
     pub fn new(config: SecurityConfig) -> Self {
-        Self { config }
+        Self { config, scratch: None }
+    }
+
+    /// create a diff computer that keeps its matrix buffer between calls
+    /// 
+    /// the buffer grows to the largest matrix seen and is reused after
+    /// that, so diffing many similar-sized pairs allocates it only once.
+    /// results are the same as with `new`. the buffer is held until the
+    /// instance is dropped; calls that overlap on another thread allocate
+    /// their own instead of waiting. clones start with an empty buffer.
+    pub fn with_scratch_pool(config: SecurityConfig) -> Self {
+        Self { config, scratch: Some(ScratchPool::default()) }
     }
     
     /// get the security configuration
@@ -33,108 +60,437 @@ impl ConstantTimeDiff {
     /// returns edit script and metadata. execution time depends only on
     /// input lengths, not content differences or similarity patterns.
     pub fn diff(&self, a: &[u8], b: &[u8]) -> Result<DiffResult, DiffError> {
-        // validate input sizes against security limits
+        self.diff_inner(a, b, None, None)
+    }
+
+    /// compute diff, reporting progress as a fraction from 0.0 to 1.0
+    /// 
+    /// `progress` is called once per matrix row and a final time with 1.0
+    /// when the diff succeeds. the number of calls depends only on the
+    /// (padded) input lengths, so the cadence reveals nothing about content,
+    /// but the callback's own run time adds to the diff's: keep it cheap
+    /// and independent of the inputs, or it becomes a timing signal.
+    pub fn diff_with_progress(&self, a: &[u8], b: &[u8], mut progress: impl FnMut(f32)) -> Result<DiffResult, DiffError> {
+        self.diff_inner(a, b, None, Some(RowReport::Progress(&mut progress)))
+    }
+
+    /// compute diff, reporting progress and a running edit distance estimate
+    /// 
+    /// `preview` gets the fraction done, as for `diff_with_progress`, and a
+    /// lower bound on the edit distance: the best cell of the row just
+    /// filled plus the difference in the lengths left to align. the bound
+    /// never decreases, and the final call with 1.0 gets the exact
+    /// distance. the cadence again depends only on the (padded) lengths;
+    /// each row is scanned with constant-time selects to compute the bound,
+    /// and the callback's own cost adds to the diff's, so keep it cheap.
+    pub fn diff_with_preview(&self, a: &[u8], b: &[u8], mut preview: impl FnMut(f32, usize)) -> Result<DiffResult, DiffError> {
+        self.diff_inner(a, b, None, Some(RowReport::Preview { callback: &mut preview, len_a: a.len(), len_b: b.len() }))
+    }
+
+    /// compute diff but give up once `deadline` has passed
+    /// 
+    /// the clock is checked every few rows of the matrix fill and the call
+    /// fails with `ComputationLimitExceeded` when time runs out. stopping
+    /// early makes run time depend on progress, a deliberate timing
+    /// dependency, so this refuses to run under `TimingProtection::Strict`.
+    pub fn diff_with_deadline(&self, a: &[u8], b: &[u8], deadline: Instant) -> Result<DiffResult, DiffError> {
+        if self.config.timing_protection == TimingProtection::Strict {
+            return Err(DiffError::TimingProtectionViolation(
+                "deadlines are not allowed under strict timing protection".to_string()
+            ));
+        }
+        self.diff_inner(a, b, Some(deadline), None)
+    }
+
+    /// shared diff implementation with an optional deadline and progress callback
+    fn diff_inner(
+        &self,
+        a: &[u8],
+        b: &[u8],
+        deadline: Option<Instant>,
+        mut progress: Option<RowReport<'_>>,
+    ) -> Result<DiffResult, DiffError> {
+        self.check_lengths(a.len(), b.len())?;
+        self.validate_padding_byte(a, b)?;
+
+        if let Some(identical) = self.identical_shortcut(a, b) {
+            let result = self.check_operation_limit(identical)?;
+            if let Some(progress) = progress {
+                progress.finish(&result);
+            }
+            return Ok(result);
+        }
+
+        // trimmed windows are kept as is; only the middle goes through the matrix
+        let (prefix, suffix) = self.affix_trim_lengths(a, b);
+        let mut operations = vec![DiffOperation::Keep; prefix];
+        operations.extend(self.diff_untrimmed(
+            &a[prefix..a.len() - suffix],
+            &b[prefix..b.len() - suffix],
+            deadline,
+            progress.as_mut(),
+        )?);
+        operations.extend(std::iter::repeat_n(DiffOperation::Keep, suffix));
+        
+        // compute actual edit distance from the operations
+        let edit_distance = operations.iter()
+            .filter(|op| op.is_modification())
+            .count();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(len_a = a.len(), len_b = b.len(), edit_distance, "diff complete");
+
+        let result = self.check_operation_limit(DiffResult::new(operations, edit_distance, a.len(), b.len()))?;
+        if let Some(progress) = progress {
+            progress.finish(&result);
+        }
+        Ok(result)
+    }
+
+    /// true if `a` and `b` differ, without building a matrix or script
+    /// 
+    /// inputs are compared with `ct_bytes_eq`, which touches every byte.
+    /// with padding on they are padded as for `diff` first, so the time
+    /// depends on the padded size alone; otherwise unequal lengths answer
+    /// at once and equal ones take time linear in the length. only the
+    /// input size limit applies, since nothing is diffed.
+    pub fn differs(&self, a: &[u8], b: &[u8]) -> Result<bool, DiffError> {
         self.config.validate_input_sizes(a.len(), b.len())?;
+        
+        let Some(pad_size) = self.config.effective_padding_size(a.len(), b.len()) else {
+            return Ok(!ct_bytes_eq(a, b));
+        };
+        // both sides get the same fill, so the lengths have to be compared too
+        let (padded_a, padded_b) = self.pad_inputs(a, b, pad_size)?;
+        let same_length = (a.len() as u64).ct_eq(&(b.len() as u64));
+        let same_bytes = Choice::from(ct_bytes_eq(&padded_a, &padded_b) as u8);
+        Ok(!bool::from(same_length & same_bytes))
+    }
 
-        // pad inputs if required by security config
-        let (padded_a, padded_b) = if let Some(pad_size) = self.config.effective_padding_size(a.len(), b.len()) {
-            self.pad_inputs(a, b, pad_size)?
-        } else {
-            (a.to_vec(), b.to_vec())
+    /// edit distance between `a` and `b`, without building the edit script
+    /// 
+    /// runs the same checks, trimming and padded matrix fill as `diff` but
+    /// reads the cell for the real input lengths instead of backtracking, so
+    /// it equals `diff(a, b)?.edit_distance`. with no script to count,
+    /// `max_operations` doesn't apply.
+    pub fn distance(&self, a: &[u8], b: &[u8]) -> Result<usize, DiffError> {
+        self.check_lengths(a.len(), b.len())?;
+        self.validate_padding_byte(a, b)?;
+        
+        if self.takes_identical_shortcut(a, b) {
+            return Ok(0);
+        }
+        
+        let (prefix, suffix) = self.affix_trim_lengths(a, b);
+        let (a, b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+        
+        // padding only extends the matrix; the cell for the real lengths
+        // depends on the real bytes alone
+        let (_, _, matrix) = self.padded_matrix(a, b, None, None)?;
+        let distance = matrix[(a.len(), b.len())] as usize;
+        self.recycle(matrix);
+        Ok(distance)
+    }
+
+    /// checks input lengths against the size and edit distance limits
+    /// 
+    /// these are the checks `diff` runs before touching any content, so a
+    /// caller can reject inputs by length alone, e.g. before reading files.
+    /// the edit distance limit applies to the real inputs; padding adds a
+    /// fixed amount of work that doesn't count toward it.
+    pub fn check_lengths(&self, len_a: usize, len_b: usize) -> Result<(), DiffError> {
+        self.config.validate_input_sizes(len_a, len_b)?;
+        self.check_edit_distance(len_a, len_b)
+    }
+
+    /// rejects inputs of `units_a` and `units_b` units whose potential edit
+    /// distance, the two counts summed, exceeds `max_edit_distance`
+    /// 
+    /// units are bytes for `diff` and lines or tokens for the coarser diffs.
+    fn check_edit_distance(&self, units_a: usize, units_b: usize) -> Result<(), DiffError> {
+        match self.config.max_edit_distance {
+            Some(max_dist) if units_a + units_b > max_dist => Err(DiffError::ComputationLimitExceeded(
+                format!("potential edit distance {} exceeds limit {}", units_a + units_b, max_dist)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// rejects an `(m + 1) x (n + 1)` matrix larger than `max_matrix_bytes`
+    /// 
+    /// runs before allocating, so an oversized diff is an error rather than
+    /// an out-of-memory abort.
+    fn check_matrix_size(&self, m: usize, n: usize) -> Result<(), DiffError> {
+        let Some(limit) = self.config.max_matrix_bytes else {
+            return Ok(());
+        };
+        
+        let bytes = (m + 1).checked_mul(n + 1)
+            .and_then(|cells| cells.checked_mul(std::mem::size_of::<u32>()));
+        match bytes {
+            Some(bytes) if bytes <= limit => Ok(()),
+            Some(bytes) => Err(DiffError::ComputationLimitExceeded(
+                format!("edit matrix of {}x{} cells needs {} bytes, over the {} byte limit", m + 1, n + 1, bytes, limit)
+            )),
+            None => Err(DiffError::ComputationLimitExceeded(
+                format!("edit matrix of {}x{} cells overflows usize", m + 1, n + 1)
+            )),
+        }
+    }
+
+    /// lengths of the prefix and suffix windows `affix_trim` lets the diff skip
+    /// 
+    /// windows are only considered when both inputs are at least twice the
+    /// window size, so they never overlap; that check uses lengths alone.
+    /// each window is compared in full with `ct_bytes_eq` and trimmed only if
+    /// every byte matched.
+    fn affix_trim_lengths(&self, a: &[u8], b: &[u8]) -> (usize, usize) {
+        let window = match self.config.affix_trim {
+            AffixTrim::LengthBounded(k) if k > 0 && a.len().min(b.len()) >= 2 * k => k,
+            _ => return (0, 0),
         };
+        
+        let prefix_matches = ct_bytes_eq(&a[..window], &b[..window]);
+        let suffix_matches = ct_bytes_eq(&a[a.len() - window..], &b[b.len() - window..]);
+        (
+            if prefix_matches { window } else { 0 },
+            if suffix_matches { window } else { 0 },
+        )
+    }
 
-        // compute edit distance matrix in constant time
-        let matrix = self.compute_edit_matrix(&padded_a, &padded_b)?;
+    /// pads, fills the matrix and extracts the script for the whole of `a` and `b`
+    fn diff_untrimmed(
+        &self,
+        a: &[u8],
+        b: &[u8],
+        deadline: Option<Instant>,
+        progress: Option<&mut RowReport<'_>>,
+    ) -> Result<Vec<DiffOperation>, DiffError> {
+        // previews bound the distance of this window, which is the whole
+        // distance since trimmed bytes are kept
+        let mut progress = progress.map(|report| report.for_window(a.len(), b.len()));
+        
+        let (padded_a, padded_b, matrix) = self.padded_matrix(a, b, deadline, progress.as_mut())?;
         
         // extract edit script from matrix using constant-time backtracking
         let operations = if let Some(_pad_size) = self.config.effective_padding_size(a.len(), b.len()) {
             // for padded inputs, generate operations but limit to original lengths
-            self.extract_edit_script_with_limits(&padded_a, &padded_b, &matrix, a.len(), b.len())?
+            self.extract_edit_script_with_limits(&padded_a, &padded_b, &matrix, a.len(), b.len())
         } else {
             // for unpadded inputs, use normal extraction
-            self.extract_edit_script_constant_time(&padded_a, &padded_b, &matrix)?
+            self.extract_edit_script_constant_time(&padded_a, &padded_b, &matrix)
+        };
+        self.recycle(matrix);
+        operations
+    }
+
+    /// pads `a` and `b` if the config asks for it and fills their edit matrix
+    /// in constant time
+    /// 
+    /// returns the inputs the matrix was computed over, the padded copies or
+    /// else `a` and `b` themselves, along with the matrix.
+    fn padded_matrix<'a>(
+        &self,
+        a: &'a [u8],
+        b: &'a [u8],
+        deadline: Option<Instant>,
+        progress: Option<&mut RowReport<'_>>,
+    ) -> Result<PaddedMatrix<'a>, DiffError> {
+        let (padded_a, padded_b) = match self.config.effective_padding_size(a.len(), b.len()) {
+            Some(pad_size) => {
+                let (padded_a, padded_b) = self.pad_inputs(a, b, pad_size)?;
+                (Cow::Owned(padded_a), Cow::Owned(padded_b))
+            }
+            None => (Cow::Borrowed(a), Cow::Borrowed(b)),
+        };
+        
+        let matrix = self.compute_edit_matrix(&padded_a, &padded_b, deadline, progress)?;
+        Ok((padded_a, padded_b, matrix))
+    }
+
+    /// rejects inputs containing the fixed padding byte when `validate_inputs`
+    /// and padding are both on
+    /// 
+    /// such a byte can't be told apart from padding. the scan visits every
+    /// byte of both inputs and only looks at the accumulated flag at the end,
+    /// so its timing doesn't depend on whether or where the byte occurs.
+    fn validate_padding_byte(&self, a: &[u8], b: &[u8]) -> Result<(), DiffError> {
+        let reserved = match self.config.padding_byte {
+            PaddingStrategy::Fixed(byte) if self.config.validate_inputs && self.config.pad_inputs => byte,
+            _ => return Ok(()),
         };
         
-        // no need to filter if we generated correctly
-        let filtered_ops = operations;
+        let mut found = Choice::from(0);
+        for byte in a.iter().chain(b) {
+            found |= byte.ct_eq(&reserved);
+        }
         
-        // compute actual edit distance from filtered operations
-        let edit_distance = filtered_ops.iter()
-            .filter(|op| op.is_modification())
-            .count();
+        if bool::from(found) {
+            return Err(DiffError::InvalidInput(format!(
+                "input contains the padding byte 0x{:02x}; disable validate_inputs or choose another padding strategy",
+                reserved
+            )));
+        }
+        Ok(())
+    }
 
-        Ok(DiffResult::new(filtered_ops, edit_distance, a.len(), b.len()))
+    /// all-keep result for byte-identical inputs when `fast_identical_check` is on
+    /// 
+    /// `ct_bytes_eq` touches every byte whatever the content, so the check
+    /// itself reveals nothing about where inputs differ; skipping the matrix
+    /// afterwards does reveal that they were identical.
+    fn identical_shortcut(&self, a: &[u8], b: &[u8]) -> Option<DiffResult> {
+        if !self.takes_identical_shortcut(a, b) {
+            return None;
+        }
+        Some(DiffResult::new(vec![DiffOperation::Keep; a.len()], 0, a.len(), b.len()))
+    }
+
+    /// true if `fast_identical_check` is on and `a` and `b` are identical
+    fn takes_identical_shortcut(&self, a: &[u8], b: &[u8]) -> bool {
+        self.config.fast_identical_check && ct_bytes_eq(a, b)
+    }
+
+    /// rejects results with more operations than `max_operations` allows
+    fn check_operation_limit(&self, result: DiffResult) -> Result<DiffResult, DiffError> {
+        self.check_operation_count(result.operations.len())?;
+        Ok(result)
+    }
+
+    /// rejects an edit script of `count` operations if `max_operations` is lower
+    fn check_operation_count(&self, count: usize) -> Result<(), DiffError> {
+        match self.config.max_operations {
+            Some(limit) if count > limit => Err(DiffError::ComputationLimitExceeded(
+                format!("{} operations exceeds limit {}", count, limit)
+            )),
+            _ => Ok(()),
+        }
     }
 
     /// pad inputs to uniform size for stronger timing protection
     /// 
-    /// pads with distinctive byte pattern that can be filtered out later.
-    /// padding pattern chosen to be unlikely in real content.
+    /// fills according to the configured padding strategy. both inputs take
+    /// their padding from one position-aligned buffer, so every padded
+    /// position holds the same byte in `a` and `b`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len_a = a.len(), len_b = b.len(), pad_size = pad_size)))]
     fn pad_inputs(&self, a: &[u8], b: &[u8], pad_size: usize) -> Result<(Vec<u8>, Vec<u8>), DiffError> {
         if a.len() > pad_size || b.len() > pad_size {
-            return Err(DiffError::InputTooLarge {
-                size: a.len().max(b.len()),
-                limit: pad_size,
+            return Err(DiffError::PaddingTooSmall {
+                input_size: a.len().max(b.len()),
+                padding_size: pad_size,
             });
         }
 
-        const PAD_BYTE: u8 = 0xFF; // distinctive padding byte
+        let pad = match self.config.padding_byte {
+            PaddingStrategy::Fixed(byte) => vec![byte; pad_size],
+            PaddingStrategy::Random => {
+                let mut pad = vec![0u8; pad_size];
+                rand::rngs::OsRng.try_fill_bytes(&mut pad)
+                    .map_err(|e| DiffError::AlgorithmError(format!("failed to generate random padding: {}", e)))?;
+                pad
+            }
+            PaddingStrategy::OutOfBand => vec![0u8; pad_size],
+        };
         
         let mut padded_a = a.to_vec();
         let mut padded_b = b.to_vec();
         
-        padded_a.resize(pad_size, PAD_BYTE);
-        padded_b.resize(pad_size, PAD_BYTE);
+        padded_a.extend_from_slice(&pad[a.len()..]);
+        padded_b.extend_from_slice(&pad[b.len()..]);
         
         Ok((padded_a, padded_b))
     }
 
+    /// the pooled matrix buffer, or an empty one without a pool or while
+    /// another call holds it
+    fn take_scratch(&self) -> Vec<u32> {
+        self.scratch.as_ref()
+            .and_then(|pool| pool.0.try_lock().ok())
+            .map(|mut buffer| std::mem::take(&mut *buffer))
+            .unwrap_or_default()
+    }
+
+    /// hands a finished matrix's buffer back to the pool, if there is one
+    /// and the buffer is at least as large as the one it holds
+    /// 
+    /// the buffer is zeroed first, so the distances of one diff don't sit in
+    /// the pool until the next. every cell is written whatever it holds, so
+    /// the cost depends only on the matrix size.
+    fn recycle(&self, mut matrix: EditMatrix) {
+        if let Some(mut buffer) = self.scratch.as_ref().and_then(|pool| pool.0.try_lock().ok()) {
+            if matrix.cells.capacity() >= buffer.capacity() {
+                matrix.cells.fill(0);
+                *buffer = std::hint::black_box(matrix.cells);
+            }
+        }
+    }
+
+    /// cost of a diagonal step over different symbols
+    /// 
+    /// `u32::MAX` when substitutions are disabled, so the step never wins.
+    fn mismatch_cost(&self) -> u32 {
+        if self.config.allow_substitution { 1 } else { u32::MAX }
+    }
+
     /// compute edit distance matrix using constant-time operations
     /// 
     /// implements myers algorithm with oblivious memory access patterns.
-    /// always computes full matrix regardless of early solution availability.
-    fn compute_edit_matrix(&self, a: &[u8], b: &[u8]) -> Result<Vec<Vec<u32>>, DiffError> {
+    /// always computes full matrix regardless of early solution availability,
+    /// unless a deadline is given and passes. `progress` gets `i / (m + 1)`
+    /// after row `i`, leaving 1.0 for the caller once the script is built.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len_a = a.len(), len_b = b.len())))]
+    fn compute_edit_matrix<T: Symbol>(
+        &self,
+        a: &[T],
+        b: &[T],
+        deadline: Option<Instant>,
+        mut progress: Option<&mut RowReport<'_>>,
+    ) -> Result<EditMatrix, DiffError> {
         let m = a.len();
         let n = b.len();
-        
-        // check edit distance limit if configured
-        if let Some(max_dist) = self.config.max_edit_distance {
-            if m + n > max_dist {
-                return Err(DiffError::ComputationLimitExceeded(
-                    format!("potential edit distance {} exceeds limit {}", m + n, max_dist)
-                ));
-            }
-        }
+        self.check_matrix_size(m, n)?;
 
         // initialize matrix with maximum possible values
-        let mut matrix = vec![vec![u32::MAX; n + 1]; m + 1];
+        let mut matrix = EditMatrix::filled(self.take_scratch(), m + 1, n + 1, u32::MAX);
         
         // initialize first row and column in constant time
         for i in 0..=m {
-            matrix[i][0] = i as u32;
+            matrix[(i, 0)] = i as u32;
         }
         for j in 0..=n {
-            matrix[0][j] = j as u32;
+            matrix[(0, j)] = j as u32;
         }
 
         // fill matrix using constant-time operations
         // always processes every cell regardless of optimal path
+        let mismatch_cost = self.mismatch_cost();
         for i in 1..=m {
+            if let Some(deadline) = deadline {
+                if i % DEADLINE_CHECK_ROWS == 0 && Instant::now() >= deadline {
+                    return Err(DiffError::ComputationLimitExceeded(
+                        format!("deadline passed after {} of {} rows", i, m)
+                    ));
+                }
+            }
+            
             for j in 1..=n {
                 // constant-time equality check for current characters
-                let chars_equal = ct_bytes_eq(&[a[i-1]], &[b[j-1]]);
+                let chars_equal = a[i-1].ct_equals(&b[j-1]);
                 
                 // compute three possible transitions in constant time
-                let diagonal_cost = if chars_equal { 0 } else { 1 };
-                let diagonal = matrix[i-1][j-1] + diagonal_cost;
-                let insert = matrix[i][j-1] + 1;
-                let delete = matrix[i-1][j] + 1;
+                let diagonal_cost = if chars_equal { 0 } else { mismatch_cost };
+                let diagonal = matrix[(i-1, j-1)].saturating_add(diagonal_cost);
+                let insert = matrix[(i, j-1)] + 1;
+                let delete = matrix[(i-1, j)] + 1;
                 
                 // find minimum using constant-time operations
                 let min_insert_delete = ct_min(insert, delete);
                 let minimum = ct_min(diagonal, min_insert_delete);
                 
-                matrix[i][j] = minimum;
+                matrix[(i, j)] = minimum;
+            }
+            
+            if let Some(progress) = progress.as_deref_mut() {
+                progress.row(&matrix, i, i as f32 / (m + 1) as f32);
             }
         }
 
@@ -145,10 +501,57 @@ impl ConstantTimeDiff {
     /// 
     /// processes all possible paths simultaneously and selects optimal one
     /// without content-dependent branching. maintains uniform execution time.
-    fn extract_edit_script_constant_time(&self, a: &[u8], b: &[u8], matrix: &[Vec<u32>]) -> Result<Vec<DiffOperation>, DiffError> {
-        let mut operations = Vec::new();
-        let mut i = a.len();
-        let mut j = b.len();
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len_a = a.len(), len_b = b.len())))]
+    fn extract_edit_script_constant_time(&self, a: &[u8], b: &[u8], matrix: &EditMatrix) -> Result<Vec<DiffOperation>, DiffError> {
+        self.extract_edit_script_with_limits(a, b, matrix, a.len(), b.len())
+    }
+
+    /// extract edit script with limits for padded inputs
+    /// 
+    /// generates edit script that only operates on original data lengths,
+    /// avoiding the need for post-processing filtering.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(orig_len_a = orig_len_a, orig_len_b = orig_len_b)))]
+    fn extract_edit_script_with_limits(&self, a: &[u8], b: &[u8], matrix: &EditMatrix, orig_len_a: usize, orig_len_b: usize) -> Result<Vec<DiffOperation>, DiffError> {
+        let steps = self.backtrack(a, b, matrix, orig_len_a, orig_len_b)?;
+        
+        let mut j = 0;
+        let operations = steps.into_iter()
+            .map(|step| {
+                let op = match step {
+                    Step::Match => DiffOperation::Keep,
+                    Step::Mismatch => DiffOperation::Substitute(b[j]),
+                    Step::Delete => DiffOperation::Delete,
+                    Step::Insert => DiffOperation::Insert(b[j]),
+                };
+                j += step.advances_b() as usize;
+                op
+            })
+            .collect();
+        
+        Ok(operations)
+    }
+
+    /// checks the edit distance limit for `a` and `b`, then fills their
+    /// matrix and backtracks it into steps
+    /// 
+    /// shared by the diffs over lines and tokens, which apply no padding.
+    fn align<T: Symbol>(&self, a: &[T], b: &[T]) -> Result<Vec<Step>, DiffError> {
+        self.check_edit_distance(a.len(), b.len())?;
+        
+        let matrix = self.compute_edit_matrix(a, b, None, None)?;
+        let steps = self.backtrack(a, b, &matrix, a.len(), b.len());
+        self.recycle(matrix);
+        steps
+    }
+
+    /// backtrack through the matrix from `(len_a, len_b)` to the origin
+    /// 
+    /// returns the alignment in forward order. transitions are selected with
+    /// constant-time comparisons; diagonal moves win ties, then deletes.
+    fn backtrack<T: Symbol>(&self, a: &[T], b: &[T], matrix: &EditMatrix, len_a: usize, len_b: usize) -> Result<Vec<Step>, DiffError> {
+        let mut steps = Vec::new();
+        let mut i = len_a;
+        let mut j = len_b;
 
         // backtrack through matrix using constant-time path selection
         while i > 0 || j > 0 {
@@ -158,19 +561,19 @@ impl ConstantTimeDiff {
             let in_interior = Choice::from(((i > 0) && (j > 0)) as u8);
 
             // get matrix values with bounds checking
-            let current = matrix[i][j];
-            let diagonal = if i == 0 || j == 0 { u32::MAX } else { matrix[i-1][j-1] };
-            let delete_pred = if i == 0 { u32::MAX } else { matrix[i-1][j] };
-            let insert_pred = if j == 0 { u32::MAX } else { matrix[i][j-1] };
+            let current = matrix[(i, j)];
+            let diagonal = if i == 0 || j == 0 { u32::MAX } else { matrix[(i-1, j-1)] };
+            let delete_pred = if i == 0 { u32::MAX } else { matrix[(i-1, j)] };
+            let insert_pred = if j == 0 { u32::MAX } else { matrix[(i, j-1)] };
 
             // compute costs for each possible transition
             let chars_equal = if i == 0 || j == 0 {
                 Choice::from(0)
             } else {
-                Choice::from(ct_bytes_eq(&[a[i-1]], &[b[j-1]]) as u8)
+                Choice::from(a[i-1].ct_equals(&b[j-1]) as u8)
             };
             
-            let diagonal_cost = u32::conditional_select(&1, &0, chars_equal);
+            let diagonal_cost = u32::conditional_select(&self.mismatch_cost(), &0, chars_equal);
             let expected_diagonal = diagonal.saturating_add(diagonal_cost);
             let expected_delete = delete_pred.saturating_add(1);
             let expected_insert = insert_pred.saturating_add(1);
@@ -188,22 +591,22 @@ impl ConstantTimeDiff {
             let final_delete = came_from_delete | force_delete;
             let final_diagonal = came_from_diagonal;
 
-            // select operation type using constant-time conditional
-            let op = if final_diagonal.into() {
+            // select step type using constant-time conditional
+            let step = if final_diagonal.into() {
                 if chars_equal.into() {
-                    DiffOperation::Keep
+                    Step::Match
                 } else {
-                    DiffOperation::Substitute(if j > 0 { b[j-1] } else { 0 })
+                    Step::Mismatch
                 }
             } else if final_delete.into() {
-                DiffOperation::Delete
+                Step::Delete
             } else if final_insert.into() {
-                DiffOperation::Insert(if j > 0 { b[j-1] } else { 0 })
+                Step::Insert
             } else {
                 return Err(DiffError::AlgorithmError("no valid transition found".to_string()));
             };
 
-            operations.push(op);
+            steps.push(step);
 
             // update positions using constant-time conditional arithmetic
             let move_i = final_diagonal | final_delete;
@@ -217,92 +620,435 @@ impl ConstantTimeDiff {
             j = j.saturating_sub(j_delta);
         }
 
-        // reverse operations to get forward edit script
-        operations.reverse();
-        Ok(operations)
+        // reverse steps to get forward alignment
+        steps.reverse();
+        Ok(steps)
     }
 
-    /// extract edit script with limits for padded inputs
+    /// compute diff over tokens of the chosen granularity
     /// 
-    /// generates edit script that only operates on original data lengths,
-    /// avoiding the need for post-processing filtering.
-    fn extract_edit_script_with_limits(&self, a: &[u8], b: &[u8], matrix: &[Vec<u32>], orig_len_a: usize, orig_len_b: usize) -> Result<Vec<DiffOperation>, DiffError> {
-        let mut operations = Vec::new();
-        let mut i = orig_len_a; // start from original lengths, not padded lengths
-        let mut j = orig_len_b;
+    /// the matrix is filled over tokens, compared with `ct_bytes_eq`, and the
+    /// token alignment is expanded back into byte operations so the result
+    /// works with `apply_to` and every output format. a kept token becomes
+    /// `Keep`s, a deleted or inserted token becomes `Delete`s or `Insert`s,
+    /// and a replaced token is substituted byte by byte with the length
+    /// difference deleted or inserted, so the whole token is marked changed.
+    /// 
+    /// timing depends on token counts rather than byte lengths, and
+    /// comparing two tokens of different lengths returns early. padding is
+    /// not applied to token sequences.
+    pub fn diff_with_granularity(&self, a: &[u8], b: &[u8], granularity: Granularity) -> Result<DiffResult, DiffError> {
+        self.diff_units(a, b, granularity, None).map(|(result, _)| result)
+    }
 
-        // backtrack through matrix using constant-time path selection
-        while i > 0 || j > 0 {
-            // handle boundary conditions with constant-time checks
-            let at_top_boundary = Choice::from((i == 0) as u8);
-            let at_left_boundary = Choice::from((j == 0) as u8);
-            let in_interior = Choice::from(((i > 0) && (j > 0)) as u8);
+    /// compute diff over tokens chosen by a caller-supplied tokenizer
+    /// 
+    /// works like `diff_with_granularity`, with each input split by
+    /// `tokenizer` instead, so a replaced token is likewise marked changed as
+    /// a whole. fails with `DiffError::InvalidInput` if the tokenizer's
+    /// ranges don't cover an input in order.
+    pub fn diff_with_tokenizer(&self, a: &[u8], b: &[u8], tokenizer: &Tokenizer) -> Result<DiffResult, DiffError> {
+        self.diff_units(a, b, Granularity::Byte, Some(tokenizer)).map(|(result, _)| result)
+    }
 
-            // get matrix values with bounds checking
-            let current = matrix[i][j];
-            let diagonal = if i == 0 || j == 0 { u32::MAX } else { matrix[i-1][j-1] };
-            let delete_pred = if i == 0 { u32::MAX } else { matrix[i-1][j] };
-            let insert_pred = if j == 0 { u32::MAX } else { matrix[i][j-1] };
+    /// diffs with `tokenizer` if given, else at `granularity`, also
+    /// returning how many tokens were changed
+    /// 
+    /// the count is what `edit_distance` would be if it were measured in
+    /// tokens: each replaced, deleted or inserted token counts once, however
+    /// many bytes it has. for byte diffs it equals `edit_distance`.
+    pub(crate) fn diff_units(
+        &self,
+        a: &[u8],
+        b: &[u8],
+        granularity: Granularity,
+        tokenizer: Option<&Tokenizer>,
+    ) -> Result<(DiffResult, usize), DiffError> {
+        if granularity == Granularity::Byte && tokenizer.is_none() {
+            let result = self.diff(a, b)?;
+            let edits = result.edit_distance;
+            return Ok((result, edits));
+        }
+        
+        self.config.validate_input_sizes(a.len(), b.len())?;
+        
+        if let Some(identical) = self.identical_shortcut(a, b) {
+            return Ok((self.check_operation_limit(identical)?, 0));
+        }
+        
+        let (result, token_edits) = match tokenizer {
+            Some(tokenizer) => self.diff_token_slices(a, b, &tokenizer.tokenize(a)?, &tokenizer.tokenize(b)?)?,
+            None => self.diff_token_slices(a, b, &granularity.tokenize(a), &granularity.tokenize(b))?,
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(len_a = a.len(), len_b = b.len(), %granularity, custom_tokenizer = tokenizer.is_some(), edit_distance = result.edit_distance, token_edits, "diff complete");
+        
+        Ok((self.check_operation_limit(result)?, token_edits))
+    }
 
-            // compute costs for each possible transition
-            let chars_equal = if i == 0 || j == 0 {
-                Choice::from(0)
-            } else {
-                Choice::from(ct_bytes_eq(&[a[i-1]], &[b[j-1]]) as u8)
-            };
-            
-            let diagonal_cost = u32::conditional_select(&1, &0, chars_equal);
-            let expected_diagonal = diagonal.saturating_add(diagonal_cost);
-            let expected_delete = delete_pred.saturating_add(1);
-            let expected_insert = insert_pred.saturating_add(1);
+    /// compute a line-granularity diff of two prepared inputs
+    /// 
+    /// gives the same result as `diff_with_granularity` with
+    /// `Granularity::Line` on their bytes, but the lines are compared
+    /// through the hashes computed by `PreparedInput::new`, with the same
+    /// collision check as `diff_lines`, so an input prepared once can be
+    /// diffed against many others without splitting or hashing it again.
+    pub fn diff_prepared(&self, a: &PreparedInput, b: &PreparedInput) -> Result<DiffResult, DiffError> {
+        self.diff_prepared_units(a, b).map(|(result, _)| result)
+    }
 
-            // determine which transition to take using constant-time comparison
-            let came_from_diagonal = Choice::from((expected_diagonal == current) as u8) & in_interior;
-            let came_from_delete = Choice::from((expected_delete == current) as u8) & Choice::from((i > 0) as u8) & !came_from_diagonal;
-            let came_from_insert = Choice::from((expected_insert == current) as u8) & Choice::from((j > 0) as u8) & !came_from_diagonal & !came_from_delete;
+    /// `diff_prepared`, also returning how many lines were changed
+    pub(crate) fn diff_prepared_units(&self, a: &PreparedInput, b: &PreparedInput) -> Result<(DiffResult, usize), DiffError> {
+        self.config.validate_input_sizes(a.data.len(), b.data.len())?;
+        
+        if let Some(identical) = self.identical_shortcut(&a.data, &b.data) {
+            return Ok((self.check_operation_limit(identical)?, 0));
+        }
+        
+        let (result, token_edits) = self.diff_token_slices(&a.data, &b.data, &a.lines, &b.lines)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(len_a = a.data.len(), len_b = b.data.len(), edit_distance = result.edit_distance, token_edits, "diff complete");
+        
+        Ok((self.check_operation_limit(result)?, token_edits))
+    }
+
+    /// aligns the tokens of `a` and `b` and expands the alignment into byte
+    /// operations over the whole inputs, counting the changed tokens
+    fn diff_token_slices<T: Symbol + AsRef<[u8]>>(&self, a: &[u8], b: &[u8], tokens_a: &[T], tokens_b: &[T]) -> Result<(DiffResult, usize), DiffError> {
+        let steps = self.align(tokens_a, tokens_b)?;
+        
+        let token_edits = steps.iter().filter(|&&step| step != Step::Match).count();
+        let mut operations = Vec::with_capacity(a.len().max(b.len()));
+        let (mut i, mut j) = (0, 0);
+        for step in steps {
+            let removed: &[u8] = if step.advances_a() { tokens_a[i].as_ref() } else { &[] };
+            let added: &[u8] = if step.advances_b() { tokens_b[j].as_ref() } else { &[] };
             
-            // at boundaries, force appropriate operations
-            let force_insert = at_top_boundary & Choice::from((j > 0) as u8);
-            let force_delete = at_left_boundary & Choice::from((i > 0) as u8);
+            if step == Step::Match {
+                operations.extend(removed.iter().map(|_| DiffOperation::Keep));
+            } else {
+                let common = removed.len().min(added.len());
+                operations.extend(added[..common].iter().map(|&byte| DiffOperation::Substitute(byte)));
+                operations.extend(removed[common..].iter().map(|_| DiffOperation::Delete));
+                operations.extend(added[common..].iter().map(|&byte| DiffOperation::Insert(byte)));
+            }
             
-            let final_insert = came_from_insert | force_insert;
-            let final_delete = came_from_delete | force_delete;
-            let final_diagonal = came_from_diagonal;
+            i += step.advances_a() as usize;
+            j += step.advances_b() as usize;
+        }
+        
+        let edit_distance = operations.iter()
+            .filter(|op| op.is_modification())
+            .count();
+        
+        Ok((DiffResult::new(operations, edit_distance, a.len(), b.len()), token_edits))
+    }
 
-            // select operation type using constant-time conditional
-            let op = if final_diagonal.into() {
-                if chars_equal.into() {
-                    DiffOperation::Keep
-                } else {
-                    DiffOperation::Substitute(if j > 0 { b[j-1] } else { 0 })
-                }
-            } else if final_delete.into() {
-                DiffOperation::Delete
-            } else if final_insert.into() {
-                DiffOperation::Insert(if j > 0 { b[j-1] } else { 0 })
-            } else {
-                return Err(DiffError::AlgorithmError("no valid transition found".to_string()));
-            };
+    /// compute a line-level diff over hashed lines
+    /// 
+    /// both inputs are split after each `\n` and every line is hashed up
+    /// front, so each matrix cell compares two fixed-size hashes in constant
+    /// time instead of whole lines; the matrix has one row per line, which
+    /// keeps large source files tractable. every cell also compares its two
+    /// lines in constant time, whether or not their hashes match, which
+    /// guards against collisions without revealing how many line pairs are
+    /// equal. timing depends on line counts and lengths, since lines of
+    /// different lengths compare faster. padding is not applied.
+    pub fn diff_lines(&self, a: &[u8], b: &[u8]) -> Result<LineDiffResult, DiffError> {
+        self.config.validate_input_sizes(a.len(), b.len())?;
+        
+        let lines_a: Vec<HashedLine<&[u8]>> = split_lines(a).into_iter().map(HashedLine::new).collect();
+        let lines_b: Vec<HashedLine<&[u8]>> = split_lines(b).into_iter().map(HashedLine::new).collect();
+        let steps = self.align(&lines_a, &lines_b)?;
+        
+        let mut operations = Vec::with_capacity(steps.len());
+        let mut j = 0;
+        for step in steps {
+            let added = || lines_b[j].line.to_vec();
+            operations.push(match step {
+                Step::Match => LineOperation::Keep,
+                Step::Mismatch => LineOperation::Replace(added()),
+                Step::Delete => LineOperation::Delete,
+                Step::Insert => LineOperation::Insert(added()),
+            });
+            j += step.advances_b() as usize;
+        }
+        
+        let edit_distance = operations.iter()
+            .filter(|op| op.is_modification())
+            .count();
+        self.check_operation_count(operations.len())?;
+        
+        Ok(LineDiffResult {
+            operations,
+            edit_distance,
+            original_lines_a: lines_a.len(),
+            original_lines_b: lines_b.len(),
+        })
+    }
 
-            operations.push(op);
+    /// compute a diff over caller-tokenized input
+    /// 
+    /// runs the same matrix and backtracking as the byte-level diff, with
+    /// each token compared through `ct_bytes_eq` on its byte representation.
+    /// operations refer to tokens by index rather than copying them. the
+    /// size limit applies to the tokens' total byte length, the edit
+    /// distance limit to their count; no padding is applied. as with
+    /// `Granularity::Word`, comparing tokens of different lengths returns
+    /// early, so run time depends on token lengths.
+    pub fn diff_tokens<T: AsRef<[u8]> + PartialEq>(&self, a: &[T], b: &[T]) -> Result<TokenDiffResult<T>, DiffError> {
+        let bytes = |tokens: &[T]| tokens.iter().map(|token| token.as_ref().len()).sum::<usize>();
+        self.config.validate_input_sizes(bytes(a), bytes(b))?;
+        
+        let tokens_a: Vec<&[u8]> = a.iter().map(AsRef::as_ref).collect();
+        let tokens_b: Vec<&[u8]> = b.iter().map(AsRef::as_ref).collect();
+        let steps = self.align(&tokens_a, &tokens_b)?;
+        
+        let (mut left, mut right) = (0, 0);
+        let operations: Vec<TokenOperation> = steps.into_iter()
+            .map(|step| {
+                let op = match step {
+                    Step::Match => TokenOperation::Keep { left, right },
+                    Step::Mismatch => TokenOperation::Substitute { left, right },
+                    Step::Delete => TokenOperation::Delete { left },
+                    Step::Insert => TokenOperation::Insert { right },
+                };
+                left += step.advances_a() as usize;
+                right += step.advances_b() as usize;
+                op
+            })
+            .collect();
+        self.check_operation_count(operations.len())?;
+        
+        Ok(TokenDiffResult::new(operations, a.len(), b.len()))
+    }
 
-            // update positions using constant-time conditional arithmetic
-            let move_i = final_diagonal | final_delete;
-            let move_j = final_diagonal | final_insert;
-            
-            // constant-time position updates
-            let i_delta = u8::conditional_select(&0, &1, move_i) as usize;
-            let j_delta = u8::conditional_select(&0, &1, move_j) as usize;
-            
-            i = i.saturating_sub(i_delta);
-            j = j.saturating_sub(j_delta);
+}
+
+/// the padded inputs an edit matrix was computed over, with the matrix
+type PaddedMatrix<'a> = (Cow<'a, [u8]>, Cow<'a, [u8]>, EditMatrix);
+
+/// edit distances of an `(m + 1) x (n + 1)` matrix in one allocation
+/// 
+/// cells are stored row by row: `(i, j)` is at `i * (n + 1) + j`, where row
+/// `i` covers the first `i` symbols of `a` and column `j` the first `j` of
+/// `b`. a row is contiguous, so the fill walks memory in order and finds the
+/// cell above at a fixed `n + 1` behind, with no per-row pointer to follow.
+struct EditMatrix {
+    cells: Vec<u32>,
+    columns: usize,
+}
+
+impl EditMatrix {
+    /// a `rows x columns` matrix with every cell set to `value`, reusing
+    /// `cells`' allocation
+    fn filled(mut cells: Vec<u32>, rows: usize, columns: usize, value: u32) -> Self {
+        cells.clear();
+        cells.resize(rows * columns, value);
+        Self { cells, columns }
+    }
+}
+
+impl Index<(usize, usize)> for EditMatrix {
+    type Output = u32;
+
+    fn index(&self, (i, j): (usize, usize)) -> &u32 {
+        &self.cells[i * self.columns + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for EditMatrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut u32 {
+        &mut self.cells[i * self.columns + j]
+    }
+}
+
+/// matrix buffer shared by the calls of a `with_scratch_pool` instance
+#[derive(Default)]
+struct ScratchPool(Mutex<Vec<u32>>);
+
+impl Clone for ScratchPool {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for ScratchPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ScratchPool")
+    }
+}
+
+/// one move through the edit matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// diagonal move over equal symbols
+    Match,
+    /// diagonal move over different symbols
+    Mismatch,
+    /// symbol only in `a`
+    Delete,
+    /// symbol only in `b`
+    Insert,
+}
+
+impl Step {
+    /// consumes a symbol of `a`
+    fn advances_a(self) -> bool {
+        !matches!(self, Step::Insert)
+    }
+    
+    /// consumes a symbol of `b`
+    fn advances_b(self) -> bool {
+        !matches!(self, Step::Delete)
+    }
+}
+
+/// element the edit matrix is computed over
+trait Symbol {
+    /// constant-time equality
+    fn ct_equals(&self, other: &Self) -> bool;
+}
+
+impl Symbol for u8 {
+    fn ct_equals(&self, other: &Self) -> bool {
+        ct_bytes_eq(&[*self], &[*other])
+    }
+}
+
+impl Symbol for &[u8] {
+    fn ct_equals(&self, other: &Self) -> bool {
+        ct_bytes_eq(self, other)
+    }
+}
+
+/// what the matrix fill reports after each row
+enum RowReport<'a> {
+    /// the fraction of rows filled, for `diff_with_progress`
+    Progress(&'a mut dyn FnMut(f32)),
+    /// the fraction filled and a lower bound on the edit distance of the
+    /// first `len_a` and `len_b` symbols, for `diff_with_preview`
+    Preview {
+        callback: &'a mut dyn FnMut(f32, usize),
+        len_a: usize,
+        len_b: usize,
+    },
+}
+
+impl RowReport<'_> {
+    /// the same report, with a preview bounding a window of `len_a` and `len_b`
+    fn for_window(&mut self, len_a: usize, len_b: usize) -> RowReport<'_> {
+        match self {
+            RowReport::Progress(callback) => RowReport::Progress(&mut **callback),
+            RowReport::Preview { callback, .. } => RowReport::Preview { callback: &mut **callback, len_a, len_b },
         }
+    }
+    
+    /// reports row `i` of `matrix`
+    /// 
+    /// a preview's bound is the least `matrix[(i, j)]` plus the difference
+    /// in lengths left after `i` and `j`, over every column up to `len_b`.
+    /// all columns are visited, those past `len_b` masked out with
+    /// constant-time selects; padding rows past `len_a` rescan row `len_a`.
+    fn row(&mut self, matrix: &EditMatrix, i: usize, fraction: f32) {
+        match self {
+            RowReport::Progress(callback) => callback(fraction),
+            RowReport::Preview { callback, len_a, len_b } => {
+                let row_a = i.min(*len_a);
+                let mut bound = u32::MAX;
+                for j in 0..matrix.columns {
+                    let rest = (*len_a - row_a).abs_diff(len_b.saturating_sub(j)) as u32;
+                    let cell = matrix[(row_a, j)].saturating_add(rest);
+                    let past_end = (j as u64).ct_gt(&(*len_b as u64));
+                    bound = ct_min(bound, u32::conditional_select(&cell, &u32::MAX, past_end));
+                }
+                callback(fraction, bound as usize);
+            }
+        }
+    }
+    
+    /// the final report, once `result` is built
+    fn finish(self, result: &DiffResult) {
+        match self {
+            RowReport::Progress(callback) => callback(1.0),
+            RowReport::Preview { callback, .. } => callback(1.0, result.edit_distance),
+        }
+    }
+}
 
-        // reverse operations to get forward edit script
-        operations.reverse();
-        Ok(operations)
+/// a line with its precomputed hash, for `diff_lines` and `diff_prepared`
+/// 
+/// `diff_lines` borrows its lines from the input, while `PreparedInput`
+/// owns them so they can be kept between diffs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HashedLine<L> {
+    hash: u64,
+    line: L,
+}
+
+impl<L: AsRef<[u8]>> HashedLine<L> {
+    fn new(line: L) -> Self {
+        Self { hash: line_hash(line.as_ref()), line }
     }
+}
 
+impl<L: AsRef<[u8]>> AsRef<[u8]> for HashedLine<L> {
+    fn as_ref(&self) -> &[u8] {
+        self.line.as_ref()
+    }
+}
+
+/// fnv hash of a line, as compared by `HashedLine`
+fn line_hash(line: &[u8]) -> u64 {
+    use std::hash::Hasher;
+    
+    let mut hasher = fnv::FnvHasher::default();
+    hasher.write(line);
+    hasher.finish()
+}
+
+/// an input split into lines and hashed once, for `diff_prepared`
+/// 
+/// lines end after each `\n`, as for `Granularity::Line`. the lines are
+/// kept as hashed copies next to the whole input, which the non-line
+/// granularities of `Diff::compare_prepared` still compare.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedInput {
+    data: Vec<u8>,
+    lines: Vec<HashedLine<Box<[u8]>>>,
+    /// the input before normalization, kept by `Diff::prepare` when
+    /// `normalize_newlines` needs it to report line ending changes
+    pub(crate) original: Option<Vec<u8>>,
+}
+
+impl PreparedInput {
+    /// splits and hashes `data`
+    pub fn new(data: Vec<u8>) -> Self {
+        let lines = split_lines(&data).into_iter()
+            .map(|line| HashedLine::new(Box::from(line)))
+            .collect();
+        Self { data, lines, original: None }
+    }
+    
+    /// the prepared bytes
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    
+    /// number of lines
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+impl<L: AsRef<[u8]>> Symbol for HashedLine<L> {
+    /// hashes and lines are both compared in constant time and combined
+    /// without branching, so collisions can't merge lines and the lines are
+    /// compared whatever the hashes say
+    fn ct_equals(&self, other: &Self) -> bool {
+        let lines_equal = Choice::from(ct_bytes_eq(self.line.as_ref(), other.line.as_ref()) as u8);
+        (self.hash.ct_eq(&other.hash) & lines_equal).into()
+    }
 }
 
 /// simplified constant-time diff function for common use cases
@@ -335,6 +1081,7 @@ pub fn balanced_diff(a: &[u8], b: &[u8]) -> Result<DiffResult, DiffError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security::TimingProtection;
 
     #[test]
     fn test_simple_diff() {
@@ -345,6 +1092,13 @@ mod tests {
             padding_size: None,
             validate_inputs: true,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: None,
+            allow_substitution: true,
         };
         let differ = ConstantTimeDiff::new(config);
         let result = differ.diff(b"abc", b"abd").unwrap();
@@ -361,6 +1115,13 @@ mod tests {
             padding_size: None,
             validate_inputs: true,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: None,
+            allow_substitution: true,
         };
         let differ = ConstantTimeDiff::new(config);
         let result = differ.diff(b"hello", b"hello").unwrap();
@@ -377,6 +1138,13 @@ mod tests {
             padding_size: None,
             validate_inputs: true,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: None,
+            allow_substitution: true,
         };
         let differ = ConstantTimeDiff::new(config);
         let result = differ.diff(b"", b"").unwrap();
@@ -393,10 +1161,97 @@ mod tests {
             padding_size: Some(16),
             validate_inputs: true,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: None,
+            allow_substitution: true,
         };
         
         let differ = ConstantTimeDiff::new(config);
         let result = differ.diff(b"abc", b"def").unwrap();
         assert!(result.is_valid());
     }
+
+    #[test]
+    fn test_hashed_line_collision_falls_back_to_bytes() {
+        // force a collision: equal hashes, different lines
+        let a = HashedLine { hash: 42, line: &b"left\n"[..] };
+        let b = HashedLine { hash: 42, line: &b"right\n"[..] };
+        let c = HashedLine { hash: 42, line: &b"left\n"[..] };
+        
+        assert!(!a.ct_equals(&b));
+        assert!(a.ct_equals(&c));
+        assert!(HashedLine::new(&b"x\n"[..]).ct_equals(&HashedLine::new(&b"x\n"[..])));
+        assert!(!HashedLine::new(&b"x\n"[..]).ct_equals(&HashedLine::new(&b"x"[..])));
+    }
+
+    #[test]
+    fn test_hashed_line_compares_bytes_when_hashes_differ() {
+        use std::cell::Cell;
+        
+        /// a line that counts how often its bytes are read
+        struct CountedLine<'a>(&'a [u8], &'a Cell<usize>);
+        
+        impl AsRef<[u8]> for CountedLine<'_> {
+            fn as_ref(&self) -> &[u8] {
+                self.1.set(self.1.get() + 1);
+                self.0
+            }
+        }
+        
+        let reads = Cell::new(0);
+        let a = HashedLine { hash: 1, line: CountedLine(b"same\n", &reads) };
+        let b = HashedLine { hash: 2, line: CountedLine(b"same\n", &reads) };
+        
+        // the hash mismatch decides the result, but must not skip the bytes
+        assert!(!a.ct_equals(&b));
+        assert_eq!(reads.get(), 2);
+    }
+
+    #[test]
+    fn test_flat_matrix_matches_nested_reference() {
+        // the row-per-vec levenshtein table the flat layout replaced
+        fn nested(a: &[u8], b: &[u8]) -> Vec<Vec<u32>> {
+            let mut matrix = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+            for (i, row) in matrix.iter_mut().enumerate() {
+                row[0] = i as u32;
+            }
+            for (j, cell) in matrix[0].iter_mut().enumerate() {
+                *cell = j as u32;
+            }
+            for i in 1..=a.len() {
+                for j in 1..=b.len() {
+                    let diagonal = matrix[i-1][j-1] + u32::from(a[i-1] != b[j-1]);
+                    matrix[i][j] = diagonal.min(matrix[i][j-1] + 1).min(matrix[i-1][j] + 1);
+                }
+            }
+            matrix
+        }
+
+        let differ = ConstantTimeDiff::with_scratch_pool(SecurityConfig::default());
+        let inputs: Vec<Vec<u8>> = (0..12)
+            .map(|len| (0..len).map(|i| b"abcab"[(i * 7 + len) % 5]).collect())
+            .collect();
+        for a in &inputs {
+            for b in &inputs {
+                let matrix = differ.compute_edit_matrix(a, b, None, None).unwrap();
+                let expected = nested(a, b);
+                for (i, row) in expected.iter().enumerate() {
+                    for (j, &cell) in row.iter().enumerate() {
+                        assert_eq!(matrix[(i, j)], cell, "cell ({}, {}) of {:?} vs {:?}", i, j, a, b);
+                    }
+                }
+                assert_eq!(matrix.cells.len(), expected.len() * expected[0].len());
+                differ.recycle(matrix);
+            }
+        }
+        
+        // the pooled buffer holds no distances between calls
+        let pooled = differ.scratch.as_ref().unwrap().0.lock().unwrap();
+        assert_eq!(pooled.len(), 12 * 12);
+        assert!(pooled.iter().all(|&cell| cell == 0));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+//! workloads for comparing diff modes (`cargo bench --features bench`)
+//!
+//! inputs are the attack scenarios' test pairs, each side repeated until it
+//! reaches a target size, so every mode is measured on the same realistic
+//! content across a size grid and the scenarios' similarity levels. the
+//! harness itself lives in `benches/modes.rs`; this module only builds the
+//! inputs and runs a mode once, so it can be smoke-tested without criterion.
+
+use crate::algorithm::ConstantTimeDiff;
+use crate::attack::AttackScenario;
+use crate::granularity::Granularity;
+use crate::types::{DiffError, SecurityConfig};
+
+/// target input sizes, in bytes, of the default grid
+pub const DEFAULT_SIZES: &[usize] = &[64, 256, 1024];
+
+/// diff entry point being measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMode {
+    /// `diff`, one symbol per byte
+    Bytes,
+    /// `diff_with_granularity` with word tokens
+    Words,
+    /// `diff_with_granularity` with line tokens
+    Lines,
+    /// `diff_lines`, one hashed symbol per line
+    HashedLines,
+}
+
+impl DiffMode {
+    /// every mode, in report order
+    pub const ALL: [DiffMode; 4] = [DiffMode::Bytes, DiffMode::Words, DiffMode::Lines, DiffMode::HashedLines];
+
+    /// short name used in benchmark ids
+    pub fn name(self) -> &'static str {
+        match self {
+            DiffMode::Bytes => "bytes",
+            DiffMode::Words => "words",
+            DiffMode::Lines => "lines",
+            DiffMode::HashedLines => "hashed_lines",
+        }
+    }
+
+    /// diffs `a` against `b` once, returning the edit distance
+    pub fn run(self, differ: &ConstantTimeDiff, a: &[u8], b: &[u8]) -> Result<usize, DiffError> {
+        match self {
+            DiffMode::Bytes => differ.diff(a, b).map(|result| result.edit_distance),
+            DiffMode::Words => differ.diff_with_granularity(a, b, Granularity::Word)
+                .map(|result| result.edit_distance),
+            DiffMode::Lines => differ.diff_with_granularity(a, b, Granularity::Line)
+                .map(|result| result.edit_distance),
+            DiffMode::HashedLines => differ.diff_lines(a, b).map(|result| result.edit_distance),
+        }
+    }
+}
+
+/// one input pair of the grid
+#[derive(Debug, Clone)]
+pub struct Workload {
+    /// `<pair name>/<target size>`
+    pub name: String,
+    /// the size the pair was grown to
+    pub size: usize,
+    /// left input
+    pub left: Vec<u8>,
+    /// right input
+    pub right: Vec<u8>,
+}
+
+impl Workload {
+    /// bytes read per diff, for throughput reporting
+    pub fn bytes(&self) -> u64 {
+        (self.left.len() + self.right.len()) as u64
+    }
+}
+
+/// every test pair of `scenarios`, grown to each of `sizes`
+///
+/// both sides are repeated the same number of times, enough for the longer
+/// one to reach the size, so the pair keeps its similarity ratio.
+pub fn workloads(scenarios: &[AttackScenario], sizes: &[usize]) -> Vec<Workload> {
+    let mut workloads = Vec::new();
+    for scenario in scenarios {
+        for (left, right, name) in scenario.generate_test_pairs() {
+            let longest = left.len().max(right.len()).max(1);
+            for &size in sizes {
+                let repeats = size.div_ceil(longest).max(1);
+                workloads.push(Workload {
+                    name: format!("{}/{}", name, size),
+                    size,
+                    left: left.repeat(repeats),
+                    right: right.repeat(repeats),
+                });
+            }
+        }
+    }
+    workloads
+}
+
+/// configuration the modes are measured with
+///
+/// padding and validation are off so only the diff itself is timed, and
+/// the size limit is raised to fit `max_input_size`.
+pub fn bench_config(max_input_size: usize) -> SecurityConfig {
+    SecurityConfig {
+        max_input_size,
+        pad_inputs: false,
+        validate_inputs: false,
+        max_edit_distance: None,
+        ..SecurityConfig::default()
+    }
+}
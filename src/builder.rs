@@ -4,11 +4,16 @@
 
 use crate::{
     error::{Error, Result}, 
-    security::{SecurityLevel, SecurityConfig}, 
-    formats::{OutputFormat, FormatOptions},
-    result::DiffResult,
-    algorithm::ConstantTimeDiff,
+    security::{SecurityLevel, SecurityConfig, TimingProtection}, 
+    formats::{ColorChoice, OutputFormat, FormatOptions},
+    granularity::{Granularity, Tokenizer},
+    normalize::{line_ending_only_changes, Normalization},
+    result::{similarity_ratio, DiffResult, ScoreReport},
+    similarity::symmetric_matrix,
+    algorithm::{ConstantTimeDiff, PreparedInput},
+    types::{AffixTrim, DiffError, DiffOperation, DiffResult as LegacyDiffResult},
 };
+use std::ops::Range;
 use std::path::Path;
 
 /// builder for configuring diff operations with fluent api
@@ -18,8 +23,13 @@ pub struct DiffBuilder {
     output_format: OutputFormat,
     format_options: FormatOptions,
     context_lines: usize,
-    enable_color: bool,
+    color_choice: ColorChoice,
+    granularity: Granularity,
+    tokenizer: Option<Tokenizer>,
+    normalization: Normalization,
+    show_original_case: bool,
     max_file_size: Option<usize>,
+    reuse_matrix: bool,
 }
 
 impl DiffBuilder {
@@ -30,11 +40,40 @@ impl DiffBuilder {
             output_format: OutputFormat::Unified,
             format_options: FormatOptions::default(),
             context_lines: 3,
-            enable_color: false,
+            color_choice: ColorChoice::Never,
+            granularity: Granularity::Byte,
+            tokenizer: None,
+            normalization: Normalization::default(),
+            show_original_case: false,
             max_file_size: None,
+            reuse_matrix: false,
         }
     }
     
+    /// preset for reviewing source changes
+    /// 
+    /// git format with 3 context lines, word diff on for formats that
+    /// support it, balanced security. whitespace is left significant.
+    pub fn code_review() -> Self {
+        Self::new()
+            .security_level(SecurityLevel::Balanced)
+            .output_format(OutputFormat::Git)
+            .context_lines(3)
+            .format_options(FormatOptions {
+                word_diff: true,
+                ..FormatOptions::default()
+            })
+    }
+    
+    /// preset for comparing configuration files from tooling
+    /// 
+    /// json format with default options and balanced security.
+    pub fn config_change() -> Self {
+        Self::new()
+            .security_level(SecurityLevel::Balanced)
+            .output_format(OutputFormat::Json)
+    }
+    
     /// sets the security level (convenience method)
     pub fn security_level(mut self, level: SecurityLevel) -> Self {
         self.security_config = level.to_config(self.max_file_size);
@@ -66,8 +105,107 @@ impl DiffBuilder {
     }
     
     /// enables or disables colored output
+    /// 
+    /// shorthand for `color_choice(ColorChoice::Always)` or `ColorChoice::Never`
     pub fn color(mut self, enable: bool) -> Self {
-        self.enable_color = enable;
+        self.color_choice = ColorChoice::from(enable);
+        self
+    }
+    
+    /// sets when colored output is produced
+    pub fn color_choice(mut self, choice: ColorChoice) -> Self {
+        self.color_choice = choice;
+        self
+    }
+    
+    /// sets the unit inputs are diffed over
+    /// 
+    /// `Word`, `Line` and `Grapheme` tokenize both inputs and diff the token sequences;
+    /// the result is still expressed as byte operations, with every changed
+    /// token marked as a whole.
+    pub fn granularity(mut self, granularity: Granularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+    
+    /// diffs over tokens chosen by `split` instead of a built-in granularity
+    /// 
+    /// `split` returns the byte ranges of an input's tokens, which must
+    /// cover it in order (see `Tokenizer`); comparisons fail with
+    /// `DiffError::InvalidInput` when they don't. tokens are compared in
+    /// constant time and the result is byte operations, as with
+    /// `granularity`, which this overrides.
+    pub fn tokenizer(mut self, split: impl Fn(&[u8]) -> Vec<Range<usize>> + Send + Sync + 'static) -> Self {
+        self.tokenizer = Some(Tokenizer::new(split));
+        self
+    }
+    
+    /// expands tabs to spaces, up to the next multiple of `width` columns,
+    /// before diffing
+    /// 
+    /// like the other whitespace options this rewrites both inputs, so the
+    /// result (operations, sizes, formatted output) describes the normalized
+    /// text rather than the original bytes.
+    pub fn expand_tabs(mut self, width: usize) -> Self {
+        self.normalization.expand_tabs = Some(width);
+        self
+    }
+    
+    /// ignores spaces and tabs at the end of lines; see `expand_tabs`
+    pub fn ignore_trailing_whitespace(mut self, ignore: bool) -> Self {
+        self.normalization.ignore_trailing_whitespace = ignore;
+        self
+    }
+    
+    /// ignores all whitespace except line breaks; see `expand_tabs`
+    pub fn ignore_all_whitespace(mut self, ignore: bool) -> Self {
+        self.normalization.ignore_all_whitespace = ignore;
+        self
+    }
+    
+    /// converts `\r\n` and lone `\r` line endings to `\n` on both sides
+    /// before diffing
+    /// 
+    /// a CRLF file then compares equal to its LF copy. as with `expand_tabs`
+    /// the script describes the normalized bytes, so it can't be applied to
+    /// the original CRLF input. the summary format notes how many lines
+    /// differed only in their line endings.
+    pub fn normalize_newlines(mut self, normalize: bool) -> Self {
+        self.normalization.normalize_newlines = normalize;
+        self
+    }
+    
+    /// lowercases both inputs before diffing, so `Hello` and `hello`
+    /// compare equal
+    /// 
+    /// only ASCII letters are folded unless `unicode_case` is on. as with
+    /// `expand_tabs` the script then describes the lowercased bytes; see
+    /// `show_original_case` to display changes in their original casing.
+    pub fn ignore_case(mut self, ignore: bool) -> Self {
+        self.normalization.ignore_case = ignore;
+        self
+    }
+    
+    /// with `ignore_case`, lowercases non-ASCII letters too
+    /// 
+    /// characters whose lowercase form has a different UTF-8 length, such
+    /// as `İ` or the Kelvin sign, are compared as they are, so folding never
+    /// moves a byte.
+    pub fn unicode_case(mut self, unicode: bool) -> Self {
+        self.normalization.unicode_case = unicode;
+        self
+    }
+    
+    /// with `ignore_case`, maps the script back to the original casing
+    /// 
+    /// folding keeps every byte in place, so `compare` and the methods built
+    /// on it can rewrite inserted and substituted bytes with the right
+    /// input's own and keep the left input as given. unchanged text still
+    /// shows the left side's casing, since a kept byte comes from the left,
+    /// and the result's right side is the script applied to the left, not
+    /// the raw right input. the other compare methods ignore this.
+    pub fn show_original_case(mut self, show: bool) -> Self {
+        self.show_original_case = show;
         self
     }
     
@@ -78,19 +216,38 @@ impl DiffBuilder {
         self
     }
     
+    /// keeps the edit matrix buffer between comparisons
+    /// 
+    /// worth turning on when one `Diff` compares many pairs: the buffer is
+    /// allocated for the largest pair so far instead of once per call, and
+    /// stays allocated for the life of the `Diff`. results are unchanged.
+    pub fn reuse_matrix(mut self, reuse: bool) -> Self {
+        self.reuse_matrix = reuse;
+        self
+    }
+    
     /// builds the configured diff instance
     pub fn build(self) -> Result<Diff> {
         // validate configuration
         self.security_config.validate()?;
         
-        let differ = ConstantTimeDiff::new(self.security_config.to_legacy());
+        let config = self.security_config.to_legacy();
+        let differ = if self.reuse_matrix {
+            ConstantTimeDiff::with_scratch_pool(config)
+        } else {
+            ConstantTimeDiff::new(config)
+        };
         
         Ok(Diff {
             differ,
             output_format: self.output_format,
             format_options: self.format_options,
             context_lines: self.context_lines,
-            enable_color: self.enable_color,
+            color_choice: self.color_choice,
+            granularity: self.granularity,
+            tokenizer: self.tokenizer,
+            normalization: self.normalization,
+            show_original_case: self.show_original_case,
         })
     }
 }
@@ -102,101 +259,485 @@ impl Default for DiffBuilder {
 }
 
 /// configured diff instance for performing comparisons
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Diff {
     differ: ConstantTimeDiff,
     output_format: OutputFormat,
     format_options: FormatOptions,
     context_lines: usize,
-    enable_color: bool,
+    color_choice: ColorChoice,
+    granularity: Granularity,
+    tokenizer: Option<Tokenizer>,
+    normalization: Normalization,
+    show_original_case: bool,
 }
 
 impl Diff {
     /// compares two byte sequences
     pub fn compare(&self, left: &[u8], right: &[u8]) -> Result<DiffResult> {
-        let result = self.differ.diff(left, right)?;
+        self.compare_named(left, right, "left", "right")
+    }
+    
+    /// true if the inputs differ once normalized
+    /// 
+    /// answers what `compare(left, right)?.is_identical()` would, negated,
+    /// with a constant-time byte comparison instead of a diff: no matrix,
+    /// script or result is built. see `ConstantTimeDiff::differs`.
+    pub fn differs(&self, left: &[u8], right: &[u8]) -> Result<bool> {
+        let left = self.normalization.apply(left);
+        let right = self.normalization.apply(right);
+        Ok(self.differ.differs(&left, &right)?)
+    }
+    
+    /// checks whether inputs of these lengths pass the configured limits
+    /// 
+    /// runs the same length checks as `compare` (input size, then potential
+    /// edit distance) without diffing anything, so files can be vetted by
+    /// their metadata before being read. lengths are of the inputs as given;
+    /// `expand_tabs` can grow them past the limit, and word, line and
+    /// grapheme granularity count tokens instead, so they may accept more.
+    pub fn can_compare(&self, left_len: usize, right_len: usize) -> std::result::Result<(), DiffError> {
+        self.differ.check_lengths(left_len, right_len)
+    }
+    
+    /// largest input, in bytes, that `compare` accepts
+    pub fn max_input_size(&self) -> usize {
+        self.differ.config().max_input_size
+    }
+    
+    /// compares two byte sequences, labelling each side for formatted output
+    /// 
+    /// the names appear wherever a format shows file names, such as the
+    /// `---`/`+++` headers of unified and git output.
+    pub fn compare_named(&self, left: &[u8], right: &[u8], left_name: &str, right_name: &str) -> Result<DiffResult> {
+        if self.show_original_case && self.normalization.ignore_case {
+            return self.compare_original_case(left, right, left_name, right_name);
+        }
+        
+        let normalized_left = self.normalization.apply(left);
+        let normalized_right = self.normalization.apply(right);
+        let result = self.compare_normalized(&self.differ, &normalized_left, &normalized_right, left_name, right_name)?;
+        Ok(self.with_line_ending_changes(result, left, right))
+    }
+    
+    /// `compare_named` diffing the lowercased inputs but reporting the
+    /// original casing, for `show_original_case`
+    fn compare_original_case(&self, left: &[u8], right: &[u8], left_name: &str, right_name: &str) -> Result<DiffResult> {
+        let cased_left = self.normalization.apply_layout(left);
+        let cased_right = self.normalization.apply_layout(right);
+        let (mut result, token_edits) = diff_units(
+            &self.differ,
+            &self.normalization.fold_case(&cased_left),
+            &self.normalization.fold_case(&cased_right),
+            self.granularity,
+            self.tokenizer.as_ref(),
+        )?;
+        restore_case(&mut result, &cased_right);
         
-        Ok(DiffResult::new(
+        let displayed_right = result.apply_to(&cased_left)?;
+        let result = self.rich_result(result, token_edits, &cased_left, &displayed_right, left_name, right_name);
+        Ok(self.with_line_ending_changes(result, left, right))
+    }
+    
+    /// normalizes `data` and splits and hashes its lines for `compare_prepared`
+    pub fn prepare(&self, data: &[u8]) -> PreparedInput {
+        let mut prepared = PreparedInput::new(self.normalization.apply(data).into_owned());
+        if self.normalization.normalize_newlines {
+            prepared.original = Some(data.to_vec());
+        }
+        prepared
+    }
+    
+    /// compares two inputs from `prepare`
+    /// 
+    /// with `Granularity::Line` and no custom tokenizer, the lines hashed by
+    /// `prepare` are reused, so a base prepared once can be compared with
+    /// many others without splitting or hashing it again; the result is the
+    /// same as `compare` on the raw inputs. other settings compare the
+    /// prepared bytes as `compare` would. inputs must come from a `Diff`
+    /// with the same normalization.
+    pub fn compare_prepared(&self, left: &PreparedInput, right: &PreparedInput) -> Result<DiffResult> {
+        let (result, token_edits) = if self.granularity == Granularity::Line && self.tokenizer.is_none() {
+            self.differ.diff_prepared_units(left, right)?
+        } else {
+            diff_units(&self.differ, left.data(), right.data(), self.granularity, self.tokenizer.as_ref())?
+        };
+        let result = self.rich_result(result, token_edits, left.data(), right.data(), "left", "right");
+        
+        match (&left.original, &right.original) {
+            (Some(left_original), Some(right_original)) => {
+                Ok(self.with_line_ending_changes(result, left_original, right_original))
+            }
+            _ => Ok(result),
+        }
+    }
+    
+    /// `compare_named` for inputs that are already normalized
+    fn compare_normalized(&self, differ: &ConstantTimeDiff, left: &[u8], right: &[u8], left_name: &str, right_name: &str) -> Result<DiffResult> {
+        let (result, token_edits) = diff_units(differ, left, right, self.granularity, self.tokenizer.as_ref())?;
+        Ok(self.rich_result(result, token_edits, left, right, left_name, right_name))
+    }
+    
+    /// wraps a diff of normalized inputs with this instance's format settings
+    fn rich_result(&self, result: LegacyDiffResult, token_edits: usize, left: &[u8], right: &[u8], left_name: &str, right_name: &str) -> DiffResult {
+        DiffResult::new_with_names(
             result,
             left.to_vec(),
             right.to_vec(),
+            left_name.to_string(),
+            right_name.to_string(),
             self.output_format.clone(),
             self.format_options.clone(),
             self.context_lines,
-            self.enable_color,
-        ))
+            self.color_choice,
+        )
+        .with_token_edit_distance(token_edits)
     }
     
-    /// compares two text strings
-    pub fn compare_text(&self, left: &str, right: &str) -> Result<DiffResult> {
-        self.compare(left.as_bytes(), right.as_bytes())
+    /// records line-ending-only changes between the raw inputs when
+    /// `normalize_newlines` hid them from the diff
+    fn with_line_ending_changes(&self, result: DiffResult, left: &[u8], right: &[u8]) -> DiffResult {
+        if self.normalization.normalize_newlines {
+            result.with_line_ending_changes(line_ending_only_changes(left, right))
+        } else {
+            result
+        }
     }
     
-    /// compares two files by path
-    pub fn compare_files<P: AsRef<Path>>(&self, left_path: P, right_path: P) -> Result<DiffResult> {
-        let left_data = std::fs::read(left_path.as_ref())
-            .map_err(|e| Error::Io(e))?;
-        let right_data = std::fs::read(right_path.as_ref())
-            .map_err(|e| Error::Io(e))?;
+    /// compares every pair of a batch with all inputs padded to `fixed_pad`
+    /// 
+    /// per-call padding grows with each pair's longer side, so comparisons
+    /// of differently sized records take visibly different times. here every
+    /// input is padded to the same size instead, and the identical-input
+    /// shortcut and affix trimming, which skip work based on content, are
+    /// off, so each comparison fills the same `fixed_pad`-sized matrix. all
+    /// inputs are checked before anything is diffed; one longer than
+    /// `fixed_pad` once normalized fails the whole batch with
+    /// `DiffError::PaddingTooSmall`. the size limits still apply. padding
+    /// covers bytes, so the guarantee is for byte granularity; word and line
+    /// tokens are counted per input.
+    pub fn compare_batch_fixed(&self, pairs: &[(&[u8], &[u8])], fixed_pad: usize) -> Result<Vec<DiffResult>> {
+        let normalized: Vec<_> = pairs.iter()
+            .map(|(left, right)| (self.normalization.apply(left), self.normalization.apply(right)))
+            .collect();
+        if let Some(size) = normalized.iter()
+            .map(|(left, right)| left.len().max(right.len()))
+            .find(|&size| size > fixed_pad)
+        {
+            return Err(DiffError::PaddingTooSmall { input_size: size, padding_size: fixed_pad }.into());
+        }
+        
+        let differ = ConstantTimeDiff::with_scratch_pool(crate::types::SecurityConfig {
+            pad_inputs: true,
+            padding_size: Some(fixed_pad),
+            fast_identical_check: false,
+            affix_trim: AffixTrim::None,
+            ..self.differ.config().clone()
+        });
+        pairs.iter()
+            .zip(&normalized)
+            .map(|((left, right), (normalized_left, normalized_right))| {
+                let result = self.compare_normalized(&differ, normalized_left, normalized_right, "left", "right")?;
+                Ok(self.with_line_ending_changes(result, left, right))
+            })
+            .collect()
+    }
+    
+    /// compares two byte sequences and also returns the right side rebuilt
+    /// from the edit script
+    /// 
+    /// the result keeps the right side the script rebuilds, so neither this
+    /// nor formatting the result afterwards applies the script.
+    pub fn compare_materialized(&self, left: &[u8], right: &[u8]) -> Result<(DiffResult, Vec<u8>)> {
+        let result = self.compare(left, right)?;
+        let reconstructed = result.right_data().to_vec();
+        Ok((result, reconstructed))
+    }
+    
+    /// compares two byte sequences, previewing their similarity as the diff runs
+    /// 
+    /// `on_progress` gets the fraction done and an estimate of the
+    /// similarity so far, from `ConstantTimeDiff::diff_with_preview`'s
+    /// distance bound, so it starts optimistic and only goes down; the final
+    /// call with 1.0 gets the result's `similarity()`. the calls come once
+    /// per matrix row, so their cadence depends only on the lengths, but
+    /// their cost adds to the diff's: keep the callback cheap. word, line
+    /// and custom token diffs only get the final call.
+    pub fn compare_with_preview(&self, left: &[u8], right: &[u8], mut on_progress: impl FnMut(f32, f32)) -> Result<DiffResult> {
+        let normalized_left = self.normalization.apply(left);
+        let normalized_right = self.normalization.apply(right);
+        let similarity = |distance| similarity_ratio(distance, normalized_left.len(), normalized_right.len()) as f32;
+        
+        let (result, token_edits) = if self.granularity == Granularity::Byte && self.tokenizer.is_none() {
+            let result = self.differ.diff_with_preview(&normalized_left, &normalized_right, |fraction, distance| {
+                on_progress(fraction, similarity(distance));
+            })?;
+            let edits = result.edit_distance;
+            (result, edits)
+        } else {
+            let (result, token_edits) = diff_units(&self.differ, &normalized_left, &normalized_right, self.granularity, self.tokenizer.as_ref())?;
+            on_progress(1.0, similarity(result.edit_distance));
+            (result, token_edits)
+        };
+        
+        let result = self.rich_result(result, token_edits, &normalized_left, &normalized_right, "left", "right");
+        Ok(self.with_line_ending_changes(result, left, right))
+    }
+    
+    /// compares two byte sequences and returns only the edit distance
+    ///
+    /// runs the same constant-time algorithm as `compare` but skips building
+    /// the rich result, so neither input is copied and no format state is kept.
+    pub fn compare_distance_only(&self, left: &[u8], right: &[u8]) -> Result<usize> {
+        let left = self.normalization.apply(left);
+        let right = self.normalization.apply(right);
+        let (result, _) = diff_units(&self.differ, &left, &right, self.granularity, self.tokenizer.as_ref())?;
+        Ok(result.edit_distance)
+    }
+    
+    /// scores two byte sequences without building the rich result
+    /// 
+    /// same constant-time diff as `compare`, but only the distance,
+    /// similarity and (normalized) sizes are kept; inputs aren't copied.
+    pub fn score(&self, left: &[u8], right: &[u8]) -> Result<ScoreReport> {
+        let left = self.normalization.apply(left);
+        let right = self.normalization.apply(right);
+        let (result, _) = diff_units(&self.differ, &left, &right, self.granularity, self.tokenizer.as_ref())?;
         
-        // check file size limits
-        let max_size = self.differ.config().max_input_size;
-        if max_size > 0 {
-            if left_data.len() > max_size || right_data.len() > max_size {
-                return Err(Error::resource_limit(format!(
-                    "file size {} exceeds limit {}",
-                    left_data.len().max(right_data.len()),
-                    max_size
-                )));
+        Ok(ScoreReport {
+            edit_distance: result.edit_distance,
+            similarity: similarity_ratio(result.edit_distance, left.len(), right.len()),
+            left_size: left.len(),
+            right_size: right.len(),
+        })
+    }
+    
+    /// computes edit distances for many pairs with one configured instance
+    ///
+    /// lazily yields one result per pair; each pair is handled like
+    /// `compare_distance_only`.
+    pub fn compare_many<'a, I>(&'a self, pairs: I) -> impl Iterator<Item = Result<usize>> + 'a
+    where
+        I: IntoIterator<Item = (&'a [u8], &'a [u8])>,
+        I::IntoIter: 'a,
+    {
+        pairs.into_iter()
+            .map(move |(left, right)| self.compare_distance_only(left, right))
+    }
+    
+    /// similarity of every pair of `docs`, as an n×n matrix
+    /// 
+    /// each pair is scored like `score` with this instance's configuration;
+    /// only the upper triangle is diffed and the diagonal is 1.0. see
+    /// `similarity::pairwise_matrix` for the n-gram alternative.
+    pub fn pairwise_similarity(&self, docs: &[&[u8]]) -> Result<Vec<Vec<f64>>> {
+        symmetric_matrix(docs, |left, right| Ok(self.score(left, right)?.similarity))
+    }
+    
+    /// compares two byte sequences only if they could reach `min_similarity`
+    /// 
+    /// returns `None` without running the full matrix when a cheap lower bound
+    /// on the edit distance (length difference, then byte-frequency
+    /// difference) already rules the pair out. the bounds are exact, so a
+    /// pair that would meet the threshold is never skipped.
+    /// 
+    /// the early exit makes run time depend on content, so this is only
+    /// available under `Basic` or `None` timing protection and errors
+    /// otherwise.
+    pub fn compare_if_similar(&self, left: &[u8], right: &[u8], min_similarity: f64) -> Result<Option<DiffResult>> {
+        match self.differ.config().timing_protection {
+            TimingProtection::Basic | TimingProtection::None => {}
+            TimingProtection::Strict | TimingProtection::Moderate => {
+                return Err(Error::security(
+                    "compare_if_similar exits early and requires basic or no timing protection"
+                ));
             }
         }
         
-        self.compare(&left_data, &right_data)
+        let left = &*self.normalization.apply(left);
+        let right = &*self.normalization.apply(right);
+        
+        let max_len = left.len().max(right.len());
+        if max_len > 0 {
+            let best_similarity = |min_distance: usize| 1.0 - min_distance as f64 / max_len as f64;
+            
+            // every length difference costs at least one insert or delete
+            if best_similarity(left.len().abs_diff(right.len())) < min_similarity {
+                return Ok(None);
+            }
+            
+            // each byte present more often on one side needs its own edit
+            let mut counts = [0i64; 256];
+            for &byte in left {
+                counts[byte as usize] += 1;
+            }
+            for &byte in right {
+                counts[byte as usize] -= 1;
+            }
+            let surplus_left: i64 = counts.iter().filter(|&&c| c > 0).sum();
+            let surplus_right: i64 = -counts.iter().filter(|&&c| c < 0).sum::<i64>();
+            if best_similarity(surplus_left.max(surplus_right) as usize) < min_similarity {
+                return Ok(None);
+            }
+        }
+        
+        let result = self.compare_normalized(&self.differ, left, right, "left", "right")?;
+        Ok((result.similarity() >= min_similarity).then_some(result))
+    }
+    
+    /// compares `left[left_range]` with `right[right_range]`
+    /// 
+    /// only the ranges are diffed and the result describes just them: its
+    /// inputs, sizes and every offset or line number in formatted output
+    /// (json `position`s included) count from the range start, not from the
+    /// start of the whole input. a range past the end of its input, or one
+    /// whose start is after its end, fails with `DiffError::InvalidInput`.
+    pub fn compare_range(
+        &self,
+        left: &[u8],
+        right: &[u8],
+        left_range: Range<usize>,
+        right_range: Range<usize>,
+    ) -> Result<DiffResult> {
+        let left = slice_range(left, left_range, "left")?;
+        let right = slice_range(right, right_range, "right")?;
+        self.compare(left, right)
+    }
+    
+    /// compares two text strings
+    pub fn compare_text(&self, left: &str, right: &str) -> Result<DiffResult> {
+        self.compare(left.as_bytes(), right.as_bytes())
+    }
+    
+    /// compares a new right side against the left input of `previous`
+    /// 
+    /// for repeated diffs against a fixed base, e.g. an editor re-diffing on
+    /// every save. keeps the previous names; currently a full recompute with
+    /// this instance's settings.
+    pub fn rediff(&self, previous: &DiffResult, new_right: &[u8]) -> Result<DiffResult> {
+        self.compare_named(previous.left_data(), new_right, previous.left_name(), previous.right_name())
+    }
+    
+    /// compares two files by path, using the paths as display names
+    /// 
+    /// files are read incrementally and reading stops as soon as one passes
+    /// `max_input_size`, so pipes, fifos and devices like `/dev/zero` work
+    /// without knowing their size up front and can't exhaust memory.
+    pub fn compare_files<P: AsRef<Path>>(&self, left_path: P, right_path: P) -> Result<DiffResult> {
+        let (left_path, right_path) = (left_path.as_ref(), right_path.as_ref());
+        self.compare_paths(
+            left_path,
+            right_path,
+            &left_path.display().to_string(),
+            &right_path.display().to_string(),
+        )
     }
     
     /// compares two files with string names (for display)
+    /// 
+    /// the contents are passed in already read; see `compare_named`.
     pub fn compare_files_named(&self, left_path: &str, right_path: &str, left_data: &[u8], right_data: &[u8]) -> Result<DiffResult> {
-        let result = self.differ.diff(left_data, right_data)?;
-        
-        Ok(DiffResult::new_with_names(
-            result,
-            left_data.to_vec(),
-            right_data.to_vec(),
-            left_path.to_string(),
-            right_path.to_string(),
-            self.output_format.clone(),
-            self.format_options.clone(),
-            self.context_lines,
-            self.enable_color,
-        ))
+        self.compare_named(left_data, right_data, left_path, right_path)
     }
     
     /// async file comparison (requires async feature)
+    /// 
+    /// reads and compares like `compare_files` on the blocking pool, with
+    /// the sides named `left` and `right`.
     #[cfg(feature = "async")]
     pub async fn compare_files_async<P: AsRef<Path>>(&self, left_path: P, right_path: P) -> Result<DiffResult> {
-        let left_data = tokio::fs::read(left_path.as_ref()).await?;
-        let right_data = tokio::fs::read(right_path.as_ref()).await?;
-        
-        // perform diff in blocking task to avoid blocking async runtime
-        let differ = self.differ.clone();
-        let output_format = self.output_format.clone();
-        let format_options = self.format_options.clone();
-        let context_lines = self.context_lines;
-        let enable_color = self.enable_color;
-        
-        let result = tokio::task::spawn_blocking(move || {
-            let result = differ.diff(&left_data, &right_data)?;
-            Ok(DiffResult::new(
-                result,
-                left_data,
-                right_data,
-                output_format,
-                format_options,
-                context_lines,
-                enable_color,
-            ))
-        }).await??;
+        self.compare_paths_async(left_path.as_ref(), right_path.as_ref(), "left".to_string(), "right".to_string()).await
+    }
+    
+    /// async file comparison that labels the result with the file paths
+    #[cfg(feature = "async")]
+    pub async fn compare_files_async_named<P: AsRef<Path>>(&self, left_path: P, right_path: P) -> Result<DiffResult> {
+        let (left_path, right_path) = (left_path.as_ref(), right_path.as_ref());
+        let left_name = left_path.display().to_string();
+        let right_name = right_path.display().to_string();
+        self.compare_paths_async(left_path, right_path, left_name, right_name).await
+    }
+    
+    /// reads both files with `read_capped` and compares them under the
+    /// given names
+    fn compare_paths(&self, left_path: &Path, right_path: &Path, left_name: &str, right_name: &str) -> Result<DiffResult> {
+        let max_size = self.max_input_size();
+        let left_data = read_capped(left_path, max_size)?;
+        let right_data = read_capped(right_path, max_size)?;
         
-        Ok(result)
+        self.compare_files_named(left_name, right_name, &left_data, &right_data)
+    }
+    
+    /// `compare_paths` on the blocking pool, so neither the reads nor the
+    /// diff block the async runtime
+    #[cfg(feature = "async")]
+    async fn compare_paths_async(&self, left_path: &Path, right_path: &Path, left_name: String, right_name: String) -> Result<DiffResult> {
+        let diff = self.clone();
+        let (left_path, right_path) = (left_path.to_path_buf(), right_path.to_path_buf());
+        tokio::task::spawn_blocking(move || {
+            diff.compare_paths(&left_path, &right_path, &left_name, &right_name)
+        }).await?
+    }
+}
+
+/// reads a file, failing with `InputTooLarge` once it passes `limit` bytes
+/// 
+/// reads at most one byte past the limit whatever the file is, so the size
+/// in the error is a lower bound. a limit of 0 reads without a cap.
+fn read_capped(path: &Path, limit: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+    
+    let file = std::fs::File::open(path)?;
+    let mut data = Vec::new();
+    if limit == 0 {
+        (&file).read_to_end(&mut data)?;
+        return Ok(data);
     }
-}
\ No newline at end of file
+    
+    file.take(limit as u64 + 1).read_to_end(&mut data)?;
+    if data.len() > limit {
+        return Err(DiffError::InputTooLarge { size: data.len(), limit }.into());
+    }
+    Ok(data)
+}
+
+/// `data[range]`, or `InvalidInput` naming `side` if the range doesn't fit
+fn slice_range<'a>(data: &'a [u8], range: Range<usize>, side: &str) -> Result<&'a [u8]> {
+    data.get(range.clone()).ok_or_else(|| {
+        DiffError::InvalidInput(format!(
+            "{} range {}..{} is out of bounds for {} bytes", side, range.start, range.end, data.len()
+        )).into()
+    })
+}
+
+/// replaces the bytes inserted or substituted by `result` with the bytes
+/// at the same positions of `right`
+/// 
+/// `right` must have the length the script was computed for, as with
+/// case folding, which keeps every byte in place.
+fn restore_case(result: &mut LegacyDiffResult, right: &[u8]) {
+    let mut position = 0;
+    for operation in &mut result.operations {
+        match operation {
+            DiffOperation::Insert(byte) | DiffOperation::Substitute(byte) => {
+                *byte = right[position];
+                position += 1;
+            }
+            DiffOperation::Keep => position += 1,
+            DiffOperation::KeepRun(run) => position += *run as usize,
+            DiffOperation::Delete => {}
+        }
+    }
+}
+
+/// diffs with the custom tokenizer if there is one, else at `granularity`,
+/// also returning the number of changed tokens
+fn diff_units(
+    differ: &ConstantTimeDiff,
+    left: &[u8],
+    right: &[u8],
+    granularity: Granularity,
+    tokenizer: Option<&Tokenizer>,
+) -> std::result::Result<(LegacyDiffResult, usize), DiffError> {
+    differ.diff_units(left, right, granularity, tokenizer)
+}
@@ -2,6 +2,7 @@
 //! 
 //! provides detailed error information with security considerations
 
+use crate::types::DiffError;
 use thiserror::Error;
 
 /// main error type for all ctdiff operations
@@ -24,8 +25,32 @@ pub enum Error {
     Configuration { message: String },
     
     /// diff algorithm internal errors
-    #[error("diff algorithm error: {0}")]
-    Algorithm(#[from] crate::types::DiffError),
+    #[error("diff algorithm error: {message}")]
+    Algorithm { message: String },
+    
+    /// an input is larger than the configured `max_input_size`
+    #[error("resource limit exceeded: input size {size} exceeds limit {limit}")]
+    InputTooLarge { size: usize, limit: usize },
+    
+    /// an input is longer than the `padding_size` it should be padded to
+    #[error("invalid input: input size {input_size} exceeds padding size {padding_size}")]
+    PaddingTooSmall { input_size: usize, padding_size: usize },
+    
+    /// edit script is malformed or inconsistent
+    #[error("invalid script: {message}")]
+    InvalidScript { message: String },
+    
+    /// input given to a script is not the length it was built for
+    #[error("invalid input: input length mismatch: expected {expected}, got {got}")]
+    InputLengthMismatch { expected: usize, got: usize },
+    
+    /// edit script consumes more bytes than the input has
+    #[error("invalid script: script extends beyond input")]
+    ScriptOverrunsInput,
+    
+    /// edit script finishes with input bytes left over
+    #[error("invalid script: script does not consume entire input: {remaining} bytes remaining")]
+    ScriptUnderconsumesInput { remaining: usize },
     
     /// format conversion errors
     #[error("format error: {message}")]
@@ -97,7 +122,14 @@ impl Error {
             Self::Security { .. } => false,
             Self::InvalidInput { .. } => true,
             Self::Configuration { .. } => true,
-            Self::Algorithm(_) => false,
+            Self::Algorithm { .. } => false,
+            Self::InputTooLarge { .. } => false,
+            // bad padding or scripts can be fixed by the caller
+            Self::PaddingTooSmall { .. } => true,
+            Self::InvalidScript { .. } => true,
+            Self::InputLengthMismatch { .. } => true,
+            Self::ScriptOverrunsInput => true,
+            Self::ScriptUnderconsumesInput { .. } => true,
             Self::Format { .. } => true,
             Self::ResourceLimit { .. } => false,
             Self::Encoding(_) => true,
@@ -108,5 +140,28 @@ impl Error {
     }
 }
 
+/// maps each algorithm error onto the `Error` variant for its kind, keeping
+/// its fields
+/// 
+/// computation limits become `ResourceLimit` and timing protection
+/// violations `Security`; variants with structured fields have their own
+/// `Error` variant, so nothing is flattened to a message.
+impl From<DiffError> for Error {
+    fn from(error: DiffError) -> Self {
+        match error {
+            DiffError::InputTooLarge { size, limit } => Self::InputTooLarge { size, limit },
+            DiffError::PaddingTooSmall { input_size, padding_size } => Self::PaddingTooSmall { input_size, padding_size },
+            DiffError::InvalidInput(message) => Self::InvalidInput { message },
+            DiffError::InvalidScript(message) => Self::InvalidScript { message },
+            DiffError::InputLengthMismatch { expected, got } => Self::InputLengthMismatch { expected, got },
+            DiffError::ScriptOverrunsInput => Self::ScriptOverrunsInput,
+            DiffError::ScriptUnderconsumesInput { remaining } => Self::ScriptUnderconsumesInput { remaining },
+            DiffError::ComputationLimitExceeded(message) => Self::ResourceLimit { message },
+            DiffError::AlgorithmError(message) => Self::Algorithm { message },
+            DiffError::TimingProtectionViolation(message) => Self::Security { message },
+        }
+    }
+}
+
 /// result type alias for ctdiff operations
 pub type Result<T> = std::result::Result<T, Error>;
\ No newline at end of file
@@ -0,0 +1,222 @@
+//! c interface behind the `capi` feature
+//!
+//! build a shared library with
+//!
+//! ```text
+//! cargo rustc --lib --release --features capi --crate-type cdylib
+//! ```
+//!
+//! and declare the functions on the c side:
+//!
+//! ```text
+//! typedef struct CtdiffResult CtdiffResult;
+//! int32_t ctdiff_compare(const uint8_t *a, size_t a_len, const uint8_t *b, size_t b_len, size_t *out_distance);
+//! int32_t ctdiff_compare_with_level(const uint8_t *a, size_t a_len, const uint8_t *b, size_t b_len, uint32_t level, size_t *out_distance);
+//! int32_t ctdiff_diff(const uint8_t *a, size_t a_len, const uint8_t *b, size_t b_len, CtdiffResult **out_result);
+//! size_t ctdiff_result_edit_distance(const CtdiffResult *result);
+//! void ctdiff_free_result(CtdiffResult *result);
+//! ```
+//!
+//! every function returns `CTDIFF_OK` (0) on success or a negative error code.
+
+use crate::{
+    algorithm::ConstantTimeDiff,
+    security::SecurityLevel,
+    types::{DiffError, DiffResult, SecurityConfig},
+};
+
+/// success
+pub const CTDIFF_OK: i32 = 0;
+/// a required pointer was null
+pub const CTDIFF_ERR_NULL_POINTER: i32 = -1;
+/// input exceeds the configured size limit
+pub const CTDIFF_ERR_INPUT_TOO_LARGE: i32 = -2;
+/// input was rejected as invalid
+pub const CTDIFF_ERR_INVALID_INPUT: i32 = -3;
+/// edit script was malformed
+pub const CTDIFF_ERR_INVALID_SCRIPT: i32 = -4;
+/// computation exceeded configured limits
+pub const CTDIFF_ERR_COMPUTATION_LIMIT: i32 = -5;
+/// internal algorithm error
+pub const CTDIFF_ERR_ALGORITHM: i32 = -6;
+/// operation not allowed under the configured timing protection
+pub const CTDIFF_ERR_TIMING_PROTECTION: i32 = -7;
+/// unknown security level
+pub const CTDIFF_ERR_INVALID_LEVEL: i32 = -8;
+
+/// security levels accepted by `ctdiff_compare_with_level`
+pub const CTDIFF_LEVEL_MAXIMUM: u32 = 0;
+/// balanced security level
+pub const CTDIFF_LEVEL_BALANCED: u32 = 1;
+/// fast security level
+pub const CTDIFF_LEVEL_FAST: u32 = 2;
+
+/// opaque diff result handed out by `ctdiff_diff`
+pub struct CtdiffResult {
+    inner: DiffResult,
+}
+
+/// maps a diff error onto its c error code
+fn error_code(error: &DiffError) -> i32 {
+    match error {
+        DiffError::InputTooLarge { .. } | DiffError::PaddingTooSmall { .. } => CTDIFF_ERR_INPUT_TOO_LARGE,
+        DiffError::InvalidInput(_) | DiffError::InputLengthMismatch { .. } => CTDIFF_ERR_INVALID_INPUT,
+        DiffError::InvalidScript(_)
+        | DiffError::ScriptOverrunsInput
+        | DiffError::ScriptUnderconsumesInput { .. } => CTDIFF_ERR_INVALID_SCRIPT,
+        DiffError::ComputationLimitExceeded(_) => CTDIFF_ERR_COMPUTATION_LIMIT,
+        DiffError::AlgorithmError(_) => CTDIFF_ERR_ALGORITHM,
+        DiffError::TimingProtectionViolation(_) => CTDIFF_ERR_TIMING_PROTECTION,
+    }
+}
+
+/// builds a slice from a c pointer, allowing null only for empty input
+///
+/// # Safety
+///
+/// a non-null `ptr` must be valid for reads of `len` bytes.
+unsafe fn input_slice<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if ptr.is_null() {
+        return (len == 0).then_some(&[]);
+    }
+    Some(std::slice::from_raw_parts(ptr, len))
+}
+
+/// runs the diff with the given config over raw c inputs
+///
+/// # Safety
+///
+/// see `ctdiff_compare`.
+unsafe fn diff_raw(
+    config: SecurityConfig,
+    a: *const u8,
+    a_len: usize,
+    b: *const u8,
+    b_len: usize,
+) -> Result<DiffResult, i32> {
+    let (Some(a), Some(b)) = (input_slice(a, a_len), input_slice(b, b_len)) else {
+        return Err(CTDIFF_ERR_NULL_POINTER);
+    };
+    ConstantTimeDiff::new(config).diff(a, b).map_err(|e| error_code(&e))
+}
+
+/// security config for a c level constant
+fn level_config(level: u32) -> Option<SecurityConfig> {
+    let level = match level {
+        CTDIFF_LEVEL_MAXIMUM => SecurityLevel::Maximum,
+        CTDIFF_LEVEL_BALANCED => SecurityLevel::Balanced,
+        CTDIFF_LEVEL_FAST => SecurityLevel::Fast,
+        _ => return None,
+    };
+    Some(level.to_config(None).to_legacy())
+}
+
+/// computes the edit distance between two buffers with balanced security
+///
+/// writes the distance to `out_distance` and returns `CTDIFF_OK`, or a
+/// negative error code leaving `out_distance` untouched.
+///
+/// # Safety
+///
+/// `a` and `b` must each be null with a zero length or valid for reads of
+/// their length; `out_distance` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn ctdiff_compare(
+    a: *const u8,
+    a_len: usize,
+    b: *const u8,
+    b_len: usize,
+    out_distance: *mut usize,
+) -> i32 {
+    if out_distance.is_null() {
+        return CTDIFF_ERR_NULL_POINTER;
+    }
+    match diff_raw(SecurityConfig::balanced(), a, a_len, b, b_len) {
+        Ok(result) => {
+            *out_distance = result.edit_distance;
+            CTDIFF_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// like `ctdiff_compare` but with an explicit `CTDIFF_LEVEL_*` security level
+///
+/// # Safety
+///
+/// same requirements as `ctdiff_compare`.
+#[no_mangle]
+pub unsafe extern "C" fn ctdiff_compare_with_level(
+    a: *const u8,
+    a_len: usize,
+    b: *const u8,
+    b_len: usize,
+    level: u32,
+    out_distance: *mut usize,
+) -> i32 {
+    if out_distance.is_null() {
+        return CTDIFF_ERR_NULL_POINTER;
+    }
+    let Some(config) = level_config(level) else {
+        return CTDIFF_ERR_INVALID_LEVEL;
+    };
+    match diff_raw(config, a, a_len, b, b_len) {
+        Ok(result) => {
+            *out_distance = result.edit_distance;
+            CTDIFF_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// computes a full diff with balanced security and hands out a result handle
+///
+/// on success `*out_result` owns a result that must be released with
+/// `ctdiff_free_result`.
+///
+/// # Safety
+///
+/// same input requirements as `ctdiff_compare`; `out_result` must be valid
+/// for a write.
+#[no_mangle]
+pub unsafe extern "C" fn ctdiff_diff(
+    a: *const u8,
+    a_len: usize,
+    b: *const u8,
+    b_len: usize,
+    out_result: *mut *mut CtdiffResult,
+) -> i32 {
+    if out_result.is_null() {
+        return CTDIFF_ERR_NULL_POINTER;
+    }
+    match diff_raw(SecurityConfig::balanced(), a, a_len, b, b_len) {
+        Ok(inner) => {
+            *out_result = Box::into_raw(Box::new(CtdiffResult { inner }));
+            CTDIFF_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// edit distance stored in a result handle (0 for a null handle)
+///
+/// # Safety
+///
+/// `result` must be null or a live handle from `ctdiff_diff`.
+#[no_mangle]
+pub unsafe extern "C" fn ctdiff_result_edit_distance(result: *const CtdiffResult) -> usize {
+    result.as_ref().map_or(0, |r| r.inner.edit_distance)
+}
+
+/// releases a result handle; null is ignored
+///
+/// # Safety
+///
+/// `result` must be null or a handle from `ctdiff_diff` that hasn't been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ctdiff_free_result(result: *mut CtdiffResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
@@ -0,0 +1,114 @@
+//! color selection and ansi styling for text formats
+//!
+//! formats write escape codes themselves once `ColorChoice` has decided to
+//! color, so an explicit `Always` isn't undone by a terminal check further down.
+
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+
+/// when to emit ansi colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorChoice {
+    /// always color, even when writing to a pipe (`NO_COLOR` still wins)
+    Always,
+    /// never color
+    #[default]
+    Never,
+    /// color only when stdout is a terminal, following `NO_COLOR` and
+    /// then `CLICOLOR_FORCE`
+    Auto,
+}
+
+impl ColorChoice {
+    /// decides whether to color output written to this process's stdout
+    pub fn should_color(self) -> bool {
+        self.resolve(std::io::stdout().is_terminal())
+    }
+
+    /// decides whether to color, given whether the destination is a terminal
+    ///
+    /// `NO_COLOR` (https://no-color.org) with any non-empty value disables
+    /// color, whatever else is set; otherwise `CLICOLOR_FORCE` set to
+    /// anything but `0` turns `Auto` on even without a terminal.
+    pub fn resolve(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Never => false,
+            ColorChoice::Always => !no_color(),
+            ColorChoice::Auto => !no_color() && (clicolor_force() || is_terminal),
+        }
+    }
+}
+
+impl From<bool> for ColorChoice {
+    fn from(enable: bool) -> Self {
+        if enable { ColorChoice::Always } else { ColorChoice::Never }
+    }
+}
+
+impl std::fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorChoice::Always => write!(f, "always"),
+            ColorChoice::Never => write!(f, "never"),
+            ColorChoice::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            "auto" => Ok(ColorChoice::Auto),
+            _ => Err(crate::Error::invalid_input(format!("unknown color choice: {}", s))),
+        }
+    }
+}
+
+/// `NO_COLOR` is set to a non-empty value
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// `CLICOLOR_FORCE` is set to something other than `0`
+fn clicolor_force() -> bool {
+    std::env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// text to wrap in ansi foreground colors, once it has been chosen to be
+/// colored
+///
+/// a wrapper rather than a trait on `str`, so the color methods don't show
+/// up on every string of a crate using ctdiff.
+#[derive(Debug, Clone, Copy)]
+pub struct Ansi<'a>(pub &'a str);
+
+impl Ansi<'_> {
+    /// red text, used for deletions
+    pub fn red(self) -> String {
+        paint(self.0, 31)
+    }
+
+    /// green text, used for insertions
+    pub fn green(self) -> String {
+        paint(self.0, 32)
+    }
+
+    /// yellow text, used for substitutions
+    pub fn yellow(self) -> String {
+        paint(self.0, 33)
+    }
+
+    /// cyan text, used for hunk headers
+    pub fn cyan(self) -> String {
+        paint(self.0, 36)
+    }
+}
+
+/// wraps text in an sgr color code and a reset
+fn paint(text: &str, code: u8) -> String {
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
@@ -3,9 +3,13 @@
 //! git-compatible patch format for version control integration
 
 use crate::{error::Result, types::DiffResult};
-use crate::formats::FormatOptions;
+use crate::formats::{contains_binary, render, FormatOptions};
+use crate::formats::Ansi;
+use crate::formats::hunks::{build_hunks, omitted_hunks_notice, Hunk, LineKind};
+use std::io::Write;
 
 /// formats diff result as git patch
+#[allow(clippy::too_many_arguments)]
 pub fn format(
     left_name: &str,
     right_name: &str,
@@ -13,42 +17,109 @@ pub fn format(
     right_data: &[u8],
     result: &DiffResult,
     context_lines: usize,
+    enable_color: bool,
     options: &FormatOptions,
 ) -> Result<String> {
-    let mut output = String::new();
+    render(|writer| write(writer, left_name, right_name, left_data, right_data, result, context_lines, enable_color, options))
+}
+
+/// writes diff result as git patch, one hunk at a time
+/// 
+/// produces the same bytes as `format` without collecting them in memory.
+#[allow(clippy::too_many_arguments)]
+pub fn write(
+    writer: &mut dyn Write,
+    left_name: &str,
+    right_name: &str,
+    left_data: &[u8],
+    right_data: &[u8],
+    result: &DiffResult,
+    context_lines: usize,
+    enable_color: bool,
+    options: &FormatOptions,
+) -> Result<()> {
+    write_entry(writer, left_name, right_name, left_data, right_data, result, context_lines, enable_color, options, options.include_metadata)
+}
+
+/// one file in a patch series: `(left_name, right_name, left_data, right_data, result)`
+pub type SeriesEntry = (String, String, Vec<u8>, Vec<u8>, DiffResult);
+
+/// formats several file diffs as one patch stream suitable for `git apply`
+/// 
+/// each entry gets its own `diff --git` header and hunks. identical entries
+/// are skipped, as git does. output is never colored since it's meant to be
+/// applied.
+pub fn format_series(
+    files: &[SeriesEntry],
+    context_lines: usize,
+    options: &FormatOptions,
+) -> Result<String> {
+    // numbered lines would stop the stream from applying
+    let options = FormatOptions { git_line_numbers: false, ..options.clone() };
     
+    render(|writer| {
+        for (left_name, right_name, left_data, right_data, result) in files {
+            if result.edit_distance == 0 {
+                continue;
+            }
+            
+            // the per-file header is what lets git split the stream, so it's always on
+            write_entry(writer, left_name, right_name, left_data, right_data, result, context_lines, false, &options, true)?;
+        }
+        Ok(())
+    })
+}
+
+/// writes a single file's patch, optionally preceded by its git header
+#[allow(clippy::too_many_arguments)]
+fn write_entry(
+    writer: &mut dyn Write,
+    left_name: &str,
+    right_name: &str,
+    left_data: &[u8],
+    right_data: &[u8],
+    result: &DiffResult,
+    context_lines: usize,
+    enable_color: bool,
+    options: &FormatOptions,
+    include_header: bool,
+) -> Result<()> {
     // git patch header
-    if options.include_metadata {
-        output.push_str(&format_git_header(left_name, right_name, left_data, right_data, result));
+    if include_header {
+        writer.write_all(format_git_header(left_name, right_name, left_data, right_data).as_bytes())?;
     }
     
     // if files are identical, return early
     if result.edit_distance == 0 {
-        return Ok(output);
+        return Ok(());
     }
     
-    // reconstruct right side
-    let right_reconstructed = result.apply_to(left_data)
-        .map_err(|e| crate::Error::format(format!("failed to reconstruct: {}", e)))?;
-    
-    let left_text = String::from_utf8_lossy(left_data);
-    let right_text = String::from_utf8_lossy(&right_reconstructed);
-    
-    let left_lines: Vec<&str> = left_text.lines().collect();
-    let right_lines: Vec<&str> = right_text.lines().collect();
+    // git shows no hunks for binary files either
+    if contains_binary(left_data, right_data) {
+        writeln!(writer, "Binary files a/{} and b/{} differ", left_name, right_name)?;
+        return Ok(());
+    }
     
-    // add standard diff header
-    output.push_str(&format!("--- {}\n", left_name));
-    output.push_str(&format!("+++ {}\n", right_name));
+    // a created or deleted file has no old or new side, which git spells /dev/null
+    let (created, deleted) = file_status(left_data, right_data);
+    let old_path = if created { "/dev/null".to_string() } else { format!("a/{}", left_name) };
+    let new_path = if deleted { "/dev/null".to_string() } else { format!("b/{}", right_name) };
+    writeln!(writer, "--- {}", old_path)?;
+    writeln!(writer, "+++ {}", new_path)?;
     
-    // generate hunks
-    let hunks = build_git_hunks(&left_lines, &right_lines, context_lines, options)?;
+    let rewrite = options.renders_as_rewrite(result, left_data, right_data);
+    let hunks = build_hunks(left_data, right_data, &result.operations, context_lines, rewrite);
+    let shown = options.max_hunks.map_or(hunks.len(), |max| max.min(hunks.len()));
+    let unterminated = (unterminated_last_line(left_data), unterminated_last_line(right_data));
     
-    for hunk in hunks {
-        output.push_str(&hunk.format_git_hunk());
+    for hunk in &hunks[..shown] {
+        writer.write_all(format_git_hunk(hunk, enable_color, options.git_line_numbers, unterminated).as_bytes())?;
+    }
+    if shown < hunks.len() {
+        writer.write_all(omitted_hunks_notice(hunks.len() - shown).as_bytes())?;
     }
     
-    Ok(output)
+    Ok(())
 }
 
 /// formats git-style patch header with metadata
@@ -57,7 +128,6 @@ fn format_git_header(
     right_name: &str,
     left_data: &[u8],
     right_data: &[u8],
-    result: &DiffResult,
 ) -> String {
     let mut header = String::new();
     
@@ -67,256 +137,83 @@ fn format_git_header(
     // file mode (assume text files)
     header.push_str("index 0000000..1111111 100644\n");
     
-    // detect new/deleted files
-    if left_data.is_empty() {
-        header.push_str(&format!("new file mode 100644\n"));
-    } else if right_data.is_empty() || result.edit_distance == left_data.len() {
-        header.push_str(&format!("deleted file mode 100644\n"));
+    let (created, deleted) = file_status(left_data, right_data);
+    if created {
+        header.push_str("new file mode 100644\n");
+    } else if deleted {
+        header.push_str("deleted file mode 100644\n");
     }
     
     header
 }
 
-/// builds git-style hunks with proper context
-fn build_git_hunks(
-    left_lines: &[&str],
-    right_lines: &[&str],
-    context_lines: usize,
-    _options: &FormatOptions,
-) -> Result<Vec<GitHunk>> {
-    let changes = compute_line_changes(left_lines, right_lines);
-    
-    if changes.is_empty() {
-        return Ok(vec![]);
-    }
-    
-    let mut hunks = Vec::new();
-    let mut current_hunk: Option<GitHunkBuilder> = None;
-    
-    for (i, change) in changes.iter().enumerate() {
-        match change {
-            LineChange::Equal(_) => {
-                if let Some(ref mut hunk) = current_hunk {
-                    if hunk.should_continue(i, context_lines, &changes) {
-                        hunk.add_line(change.clone(), i);
-                    } else {
-                        let built_hunk = std::mem::replace(hunk, GitHunkBuilder::new(i)).build();
-                        hunks.push(built_hunk);
-                        current_hunk = None;
-                        
-                        // start new hunk if more changes ahead
-                        if has_changes_ahead(&changes[i..], context_lines) {
-                            let mut new_hunk = GitHunkBuilder::new(i);
-                            new_hunk.add_context_before(&changes, i, context_lines);
-                            new_hunk.add_line(change.clone(), i);
-                            current_hunk = Some(new_hunk);
-                        }
-                    }
-                }
-            }
-            LineChange::Delete(_) | LineChange::Insert(_) => {
-                if current_hunk.is_none() {
-                    let mut new_hunk = GitHunkBuilder::new(i);
-                    new_hunk.add_context_before(&changes, i, context_lines);
-                    current_hunk = Some(new_hunk);
-                }
-                
-                if let Some(ref mut hunk) = current_hunk {
-                    hunk.add_line(change.clone(), i);
-                }
-            }
-        }
-    }
-    
-    // finish last hunk with trailing context
-    if let Some(mut hunk) = current_hunk {
-        hunk.add_context_after(&changes, context_lines);
-        hunks.push(hunk.build());
-    }
-    
-    Ok(hunks)
+/// whether the patch creates or deletes the file, as `(created, deleted)`
+/// 
+/// detected from emptiness only; edit distance can't tell a deletion apart
+/// from a full rewrite of the same length.
+fn file_status(left_data: &[u8], right_data: &[u8]) -> (bool, bool) {
+    (
+        left_data.is_empty() && !right_data.is_empty(),
+        right_data.is_empty() && !left_data.is_empty(),
+    )
 }
 
-/// git hunk builder
-#[derive(Debug)]
-struct GitHunkBuilder {
-    lines: Vec<GitHunkLine>,
-    old_start: usize,
-    new_start: usize,
-    _start_index: usize,
-    last_change_index: usize,
+/// 1-based number of the last line of `data` if it has no trailing `\n`
+fn unterminated_last_line(data: &[u8]) -> Option<usize> {
+    match data.last() {
+        Some(&byte) if byte != b'\n' => Some(data.split_inclusive(|&byte| byte == b'\n').count()),
+        _ => None,
+    }
 }
 
-impl GitHunkBuilder {
-    fn new(start_index: usize) -> Self {
-        Self {
-            lines: Vec::new(),
-            old_start: start_index + 1,
-            new_start: start_index + 1,
-            _start_index: start_index,
-            last_change_index: start_index,
-        }
-    }
+/// formats one hunk with its header, coloring header and changed lines
+/// 
+/// with `line_numbers`, each line starts with its number on the left, or on
+/// the right for insertions. `unterminated` holds the number of each side's
+/// last line when it lacks a trailing newline; such a line is followed by
+/// git's `\ No newline at end of file` marker.
+fn format_git_hunk(hunk: &Hunk, enable_color: bool, line_numbers: bool, unterminated: (Option<usize>, Option<usize>)) -> String {
+    let mut output = String::new();
     
-    fn add_context_before(&mut self, changes: &[LineChange], current_index: usize, context_lines: usize) {
-        let start = current_index.saturating_sub(context_lines);
-        for i in start..current_index {
-            if let LineChange::Equal(content) = &changes[i] {
-                self.lines.push(GitHunkLine {
-                    operation: GitLineOperation::Context,
-                    content: content.clone(),
-                });
-                if self.lines.len() == 1 {
-                    self.old_start = i + 1;
-                    self.new_start = i + 1;
-                }
-            }
-        }
+    let header = hunk.header();
+    if enable_color {
+        output.push_str(&Ansi(&header).cyan());
+    } else {
+        output.push_str(&header);
     }
+    output.push('\n');
     
-    fn add_context_after(&mut self, changes: &[LineChange], context_lines: usize) {
-        let start = self.last_change_index + 1;
-        let end = (start + context_lines).min(changes.len());
-        
-        for i in start..end {
-            if let LineChange::Equal(content) = &changes[i] {
-                self.lines.push(GitHunkLine {
-                    operation: GitLineOperation::Context,
-                    content: content.clone(),
-                });
-            }
-        }
-    }
-    
-    fn add_line(&mut self, change: LineChange, index: usize) {
-        let git_line = match change {
-            LineChange::Equal(content) => GitHunkLine {
-                operation: GitLineOperation::Context,
-                content,
-            },
-            LineChange::Delete(content) => {
-                self.last_change_index = index;
-                GitHunkLine {
-                    operation: GitLineOperation::Delete,
-                    content,
-                }
-            }
-            LineChange::Insert(content) => {
-                self.last_change_index = index;
-                GitHunkLine {
-                    operation: GitLineOperation::Insert,
-                    content,
-                }
-            }
+    let (mut old_line, mut new_line) = (hunk.old_start, hunk.new_start);
+    for line in &hunk.lines {
+        let (line_number, unterminated_line) = match line.kind {
+            LineKind::Insert => (new_line, unterminated.1),
+            LineKind::Context | LineKind::Delete => (old_line, unterminated.0),
         };
+        old_line += usize::from(line.kind != LineKind::Insert);
+        new_line += usize::from(line.kind != LineKind::Delete);
         
-        self.lines.push(git_line);
-    }
-    
-    fn should_continue(&self, current_index: usize, context_lines: usize, _changes: &[LineChange]) -> bool {
-        current_index - self.last_change_index <= context_lines * 2
-    }
-    
-    fn build(self) -> GitHunk {
-        let old_count = self.lines.iter()
-            .filter(|l| matches!(l.operation, GitLineOperation::Context | GitLineOperation::Delete))
-            .count();
-        let new_count = self.lines.iter()
-            .filter(|l| matches!(l.operation, GitLineOperation::Context | GitLineOperation::Insert))
-            .count();
-        
-        GitHunk {
-            old_start: self.old_start,
-            old_count,
-            new_start: self.new_start,
-            new_count,
-            lines: self.lines,
-        }
-    }
-}
-
-/// git hunk representation
-#[derive(Debug)]
-struct GitHunk {
-    old_start: usize,
-    old_count: usize,
-    new_start: usize,
-    new_count: usize,
-    lines: Vec<GitHunkLine>,
-}
-
-impl GitHunk {
-    fn format_git_hunk(&self) -> String {
-        let mut output = String::new();
-        
-        // hunk header
-        output.push_str(&format!("@@ -{},{} +{},{} @@\n",
-            self.old_start, self.old_count,
-            self.new_start, self.new_count));
+        let formatted = if line_numbers {
+            format!("{:4} {}{}", line_number, line.kind.prefix(), line.content)
+        } else {
+            format!("{}{}", line.kind.prefix(), line.content)
+        };
         
-        // hunk lines
-        for line in &self.lines {
-            let prefix = match line.operation {
-                GitLineOperation::Context => " ",
-                GitLineOperation::Delete => "-",
-                GitLineOperation::Insert => "+",
-            };
-            output.push_str(&format!("{}{}\n", prefix, line.content));
-        }
+        let formatted = if enable_color {
+            match line.kind {
+                LineKind::Context => formatted,
+                LineKind::Delete => Ansi(&formatted).red(),
+                LineKind::Insert => Ansi(&formatted).green(),
+            }
+        } else {
+            formatted
+        };
         
-        output
-    }
-}
-
-/// git hunk line
-#[derive(Debug)]
-struct GitHunkLine {
-    operation: GitLineOperation,
-    content: String,
-}
-
-/// git line operations
-#[derive(Debug, PartialEq)]
-enum GitLineOperation {
-    Context,
-    Delete,
-    Insert,
-}
-
-/// checks if there are changes ahead within context distance
-fn has_changes_ahead(changes: &[LineChange], context_lines: usize) -> bool {
-    changes.iter()
-        .take(context_lines * 2)
-        .any(|c| !matches!(c, LineChange::Equal(_)))
-}
-
-/// computes line changes
-fn compute_line_changes(left_lines: &[&str], right_lines: &[&str]) -> Vec<LineChange> {
-    let mut changes = Vec::new();
-    let mut i = 0;
-    let mut j = 0;
-    
-    while i < left_lines.len() || j < right_lines.len() {
-        if i < left_lines.len() && j < right_lines.len() && left_lines[i] == right_lines[j] {
-            changes.push(LineChange::Equal(left_lines[i].to_string()));
-            i += 1;
-            j += 1;
-        } else if i < left_lines.len() && (j >= right_lines.len() || left_lines[i] != *right_lines.get(j).unwrap_or(&"")) {
-            changes.push(LineChange::Delete(left_lines[i].to_string()));
-            i += 1;
-        } else if j < right_lines.len() {
-            changes.push(LineChange::Insert(right_lines[j].to_string()));
-            j += 1;
+        output.push_str(&formatted);
+        output.push('\n');
+        if unterminated_line == Some(line_number) {
+            output.push_str("\\ No newline at end of file\n");
         }
     }
     
-    changes
+    output
 }
-
-/// line change types
-#[derive(Debug, Clone)]
-enum LineChange {
-    Equal(String),
-    Delete(String),
-    Insert(String),
-}
\ No newline at end of file
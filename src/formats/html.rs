@@ -5,6 +5,9 @@
 use crate::{error::Result, types::DiffResult};
 use crate::formats::{FormatOptions, HtmlTheme};
 
+/// widest the `html_diffstat` bar gets, in blocks
+pub const DIFFSTAT_BLOCKS: usize = 10;
+
 /// formats diff result as html
 pub fn format(
     left_name: &str,
@@ -68,7 +71,7 @@ fn format_header(left_name: &str, right_name: &str, result: &DiffResult, options
     header.push_str("        </div>\n");
     
     if options.include_metadata {
-        header.push_str(&format_statistics(result));
+        header.push_str(&format_statistics(result, options));
     }
     
     header.push_str("    </header>\n");
@@ -76,10 +79,10 @@ fn format_header(left_name: &str, right_name: &str, result: &DiffResult, options
 }
 
 /// formats statistics section
-fn format_statistics(result: &DiffResult) -> String {
+fn format_statistics(result: &DiffResult, options: &FormatOptions) -> String {
     let mut stats = String::new();
     
-    let total_ops = result.operations.len();
+    let total_ops = result.expanded_operation_count();
     let insertions = result.operations.iter()
         .filter(|op| matches!(op, crate::types::DiffOperation::Insert(_)))
         .count();
@@ -96,27 +99,54 @@ fn format_statistics(result: &DiffResult) -> String {
     stats.push_str(&format!("            <div class=\"stat insertions\"><label>Insertions:</label> {}</div>\n", insertions));
     stats.push_str(&format!("            <div class=\"stat deletions\"><label>Deletions:</label> {}</div>\n", deletions));
     stats.push_str(&format!("            <div class=\"stat substitutions\"><label>Substitutions:</label> {}</div>\n", substitutions));
+    if options.html_diffstat {
+        // a substitution removes one byte and adds another, like a changed
+        // line in git's diffstat
+        stats.push_str(&format_diffstat(insertions + substitutions, deletions + substitutions));
+    }
     stats.push_str("        </div>\n");
     
     stats
 }
 
+/// formats the `+++---` bar for `html_diffstat`
+fn format_diffstat(added: usize, removed: usize) -> String {
+    let (insert_blocks, delete_blocks) = diffstat_blocks(added, removed);
+    
+    let mut bar = String::from("            <div class=\"stat diffstat\">");
+    bar.push_str(&"<span class=\"diffstat-insert\">+</span>".repeat(insert_blocks));
+    bar.push_str(&"<span class=\"diffstat-delete\">-</span>".repeat(delete_blocks));
+    bar.push_str("</div>\n");
+    bar
+}
+
+/// splits at most `DIFFSTAT_BLOCKS` blocks between additions and removals
+/// 
+/// small diffs get one block per change. larger ones are scaled down,
+/// keeping at least one block for a side with any changes.
+fn diffstat_blocks(added: usize, removed: usize) -> (usize, usize) {
+    let total = added + removed;
+    if total <= DIFFSTAT_BLOCKS {
+        return (added, removed);
+    }
+    
+    let scaled = (added * DIFFSTAT_BLOCKS + total / 2) / total;
+    let insert_blocks = scaled.clamp(usize::from(added > 0), DIFFSTAT_BLOCKS - usize::from(removed > 0));
+    (insert_blocks, DIFFSTAT_BLOCKS - insert_blocks)
+}
+
 /// formats the main diff content
 fn format_diff_content(
     left_data: &[u8],
-    _right_data: &[u8],
+    right_data: &[u8],
     result: &DiffResult,
     _context_lines: usize,
     options: &FormatOptions,
 ) -> Result<String> {
     let mut content = String::new();
     
-    // reconstruct right side text
-    let right_reconstructed = result.apply_to(left_data)
-        .map_err(|e| crate::Error::format(format!("failed to reconstruct: {}", e)))?;
-    
     let left_text = String::from_utf8_lossy(left_data);
-    let right_text = String::from_utf8_lossy(&right_reconstructed);
+    let right_text = String::from_utf8_lossy(right_data);
     
     let left_lines: Vec<&str> = left_text.lines().collect();
     let right_lines: Vec<&str> = right_text.lines().collect();
@@ -126,7 +156,8 @@ fn format_diff_content(
     if options.word_diff {
         content.push_str(&format_side_by_side(&left_lines, &right_lines, options));
     } else {
-        content.push_str(&format_unified_html(&left_lines, &right_lines, options));
+        let rewrite = options.renders_as_rewrite(result, left_data, right_data);
+        content.push_str(&format_unified_html(&left_lines, &right_lines, rewrite, options));
     }
     
     content.push_str("    </main>\n");
@@ -175,14 +206,20 @@ fn format_side_by_side(left_lines: &[&str], right_lines: &[&str], options: &Form
 }
 
 /// formats diff as unified view with highlighting
-fn format_unified_html(left_lines: &[&str], right_lines: &[&str], options: &FormatOptions) -> String {
+fn format_unified_html(left_lines: &[&str], right_lines: &[&str], rewrite: bool, options: &FormatOptions) -> String {
     let mut content = String::new();
     
     content.push_str("        <div class=\"unified\">\n");
     content.push_str("            <pre class=\"diff-block\">\n");
     
-    // simple line diff for display
-    let changes = compute_line_changes(left_lines, right_lines);
+    // simple line diff for display, or the whole of each side for a rewrite
+    let changes = if rewrite {
+        left_lines.iter().map(|line| LineChange::Delete(line.to_string()))
+            .chain(right_lines.iter().map(|line| LineChange::Insert(line.to_string())))
+            .collect()
+    } else {
+        compute_line_changes(left_lines, right_lines)
+    };
     
     for (i, change) in changes.iter().enumerate() {
         let line_num = if options.show_line_numbers {
@@ -313,6 +350,19 @@ fn generate_css(theme: &HtmlTheme) -> String {
             border-left: 3px solid #fd7e14;
         }}
         
+        .diffstat-insert, .diffstat-delete {{
+            font-weight: bold;
+            letter-spacing: 1px;
+        }}
+        
+        .diffstat-insert {{
+            color: #28a745;
+        }}
+        
+        .diffstat-delete {{
+            color: #dc3545;
+        }}
+        
         .diff-content {{
             border: 1px solid {border_color};
             border-radius: 8px;
@@ -389,7 +439,7 @@ fn generate_css(theme: &HtmlTheme) -> String {
 }
 
 /// escapes html special characters
-fn escape_html(input: &str) -> String {
+pub(crate) fn escape_html(input: &str) -> String {
     input
         .replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -0,0 +1,416 @@
+//! line-level hunks shared by the unified and git formats
+//!
+//! lines are matched with a longest-common-subsequence table over the
+//! rendered text while that table fits `MAX_LINE_TABLE_BYTES`; past it
+//! they're read off the byte-level edit script instead, in linear memory.
+//! like the formatters built on it, this runs after the constant-time diff
+//! and is not itself constant-time.
+
+use crate::types::DiffOperation;
+use serde::{Deserialize, Serialize};
+
+/// largest line table `build_hunks` allocates, 16 MiB
+/// 
+/// formatting runs after the diff, whose own matrix may still be alive, so
+/// this stays well below the diff's matrix limit; about 2000 lines a side
+/// fit.
+pub const MAX_LINE_TABLE_BYTES: usize = 16 * 1024 * 1024;
+
+/// a group of nearby line changes with surrounding context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hunk {
+    /// first left line covered (1-based); the line before the hunk when
+    /// `old_count` is 0, as in unified diff headers
+    pub old_start: usize,
+    /// number of left lines covered (context and deletions)
+    pub old_count: usize,
+    /// first right line covered, with the same convention as `old_start`
+    pub new_start: usize,
+    /// number of right lines covered (context and insertions)
+    pub new_count: usize,
+    /// the hunk's lines in output order
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// `@@ -old_start,old_count +new_start,new_count @@` header
+    pub fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@",
+                self.old_start, self.old_count,
+                self.new_start, self.new_count)
+    }
+}
+
+/// one line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkLine {
+    /// whether the line is context, removed or added
+    pub kind: LineKind,
+    /// line text without its line ending
+    pub content: String,
+}
+
+/// role of a line within a hunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineKind {
+    /// present on both sides
+    Context,
+    /// only in the left input
+    Delete,
+    /// only in the right input
+    Insert,
+}
+
+impl LineKind {
+    /// unified diff prefix character for this kind
+    pub fn prefix(self) -> char {
+        match self {
+            LineKind::Context => ' ',
+            LineKind::Delete => '-',
+            LineKind::Insert => '+',
+        }
+    }
+}
+
+/// splits both inputs into lines and groups the changes `operations` makes
+/// to them into hunks
+///
+/// `operations` must be the script turning `left_data` into `right_data`;
+/// it's only read when the line table would outgrow
+/// `MAX_LINE_TABLE_BYTES`. invalid utf-8 is replaced lossily. changes
+/// separated by more than `2 * context_lines` equal lines go into separate
+/// hunks. with `rewrite`
+/// every left line is deleted and every right line inserted, in one hunk.
+pub fn build_hunks(
+    left_data: &[u8],
+    right_data: &[u8],
+    operations: &[DiffOperation],
+    context_lines: usize,
+    rewrite: bool,
+) -> Vec<Hunk> {
+    let left_lines = line_spans(left_data);
+    let right_lines = line_spans(right_data);
+    
+    let changes = if rewrite {
+        rewrite_line_changes(left_data, &left_lines, right_data, &right_lines)
+    } else if line_table_fits(left_lines.len(), right_lines.len()) {
+        compute_line_changes(left_data, &left_lines, right_data, &right_lines)
+    } else {
+        script_line_changes(left_data, &left_lines, right_data, &right_lines, operations)
+    };
+    
+    // indices of changed lines; equal lines only ever appear as context
+    let changed: Vec<usize> = changes.iter()
+        .enumerate()
+        .filter(|(_, line)| line.kind != LineKind::Context)
+        .map(|(i, _)| i)
+        .collect();
+    
+    // group changes into clusters, splitting when the equal run between two
+    // changes is longer than the leading plus trailing context would cover
+    let mut clusters: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        match clusters.last_mut() {
+            Some((_, last)) if i - *last - 1 <= context_lines * 2 => *last = i,
+            _ => clusters.push((i, i)),
+        }
+    }
+    
+    // left and right lines consumed before each change
+    let mut before = Vec::with_capacity(changes.len());
+    let (mut old_line, mut new_line) = (0, 0);
+    for line in &changes {
+        before.push((old_line, new_line));
+        old_line += usize::from(line.kind != LineKind::Insert);
+        new_line += usize::from(line.kind != LineKind::Delete);
+    }
+    
+    clusters.into_iter()
+        .map(|(first, last)| {
+            let start = first.saturating_sub(context_lines);
+            let end = (last + context_lines).min(changes.len() - 1);
+            let lines = changes[start..=end].to_vec();
+    
+            let old_count = lines.iter().filter(|l| l.kind != LineKind::Insert).count();
+            let new_count = lines.iter().filter(|l| l.kind != LineKind::Delete).count();
+            let (old_before, new_before) = before[start];
+    
+            Hunk {
+                old_start: old_before + usize::from(old_count > 0),
+                old_count,
+                new_start: new_before + usize::from(new_count > 0),
+                new_count,
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// the line closing output cut short by `FormatOptions::max_hunks`
+pub fn omitted_hunks_notice(omitted: usize) -> String {
+    let noun = if omitted == 1 { "hunk" } else { "hunks" };
+    format!("… and {} more {} omitted\n", omitted, noun)
+}
+
+/// `(start, end)` byte range of every line, the end just past its `\n`
+fn line_spans(data: &[u8]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for line in data.split_inclusive(|&byte| byte == b'\n') {
+        spans.push((start, start + line.len()));
+        start += line.len();
+    }
+    spans
+}
+
+/// `data[start..end]` without its `\n` or `\r\n` ending
+fn line_body(data: &[u8], (start, end): (usize, usize)) -> &[u8] {
+    let line = &data[start..end];
+    match line.strip_suffix(b"\n") {
+        Some(body) => body.strip_suffix(b"\r").unwrap_or(body),
+        None => line,
+    }
+}
+
+/// what `compute_line_changes` compares of a line: its body, and whether
+/// it ends in `\n`
+fn compared_line(data: &[u8], (start, end): (usize, usize)) -> (&[u8], bool) {
+    (line_body(data, (start, end)), data[end - 1] == b'\n')
+}
+
+/// a hunk line showing `data[start..end]` without its line ending
+fn hunk_line(kind: LineKind, data: &[u8], span: (usize, usize)) -> HunkLine {
+    HunkLine { kind, content: String::from_utf8_lossy(line_body(data, span)).into_owned() }
+}
+
+/// every left line deleted, then every right line inserted
+fn rewrite_line_changes(
+    left_data: &[u8],
+    left_lines: &[(usize, usize)],
+    right_data: &[u8],
+    right_lines: &[(usize, usize)],
+) -> Vec<HunkLine> {
+    left_lines.iter().map(|&span| hunk_line(LineKind::Delete, left_data, span))
+        .chain(right_lines.iter().map(|&span| hunk_line(LineKind::Insert, right_data, span)))
+        .collect()
+}
+
+/// computes line-level changes between texts
+///
+/// uses a longest-common-subsequence table so unchanged lines after an edit
+/// resynchronise instead of being reported as deleted and re-inserted. a
+/// last line without its `\n` differs from the same text with one.
+fn compute_line_changes(
+    left_data: &[u8],
+    left_lines: &[(usize, usize)],
+    right_data: &[u8],
+    right_lines: &[(usize, usize)],
+) -> Vec<HunkLine> {
+    let left: Vec<_> = left_lines.iter().map(|&span| compared_line(left_data, span)).collect();
+    let right: Vec<_> = right_lines.iter().map(|&span| compared_line(right_data, span)).collect();
+    let n = left.len();
+    let m = right.len();
+    
+    // lcs[i * (m + 1) + j] = length of the lcs of left[i..] and right[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if left[i] == right[j] {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+    
+    let mut changes = Vec::new();
+    let mut i = 0;
+    let mut j = 0;
+    
+    while i < n || j < m {
+        if i < n && j < m && left[i] == right[j] {
+            changes.push(hunk_line(LineKind::Context, left_data, left_lines[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j >= m || lcs[at(i + 1, j)] >= lcs[at(i, j + 1)]) {
+            changes.push(hunk_line(LineKind::Delete, left_data, left_lines[i]));
+            i += 1;
+        } else {
+            changes.push(hunk_line(LineKind::Insert, right_data, right_lines[j]));
+            j += 1;
+        }
+    }
+    
+    changes
+}
+
+/// true if the table for `left_lines` by `right_lines` lines stays within
+/// `MAX_LINE_TABLE_BYTES`
+fn line_table_fits(left_lines: usize, right_lines: usize) -> bool {
+    (left_lines + 1)
+        .checked_mul(right_lines + 1)
+        .and_then(|cells| cells.checked_mul(std::mem::size_of::<u32>()))
+        .is_some_and(|bytes| bytes <= MAX_LINE_TABLE_BYTES)
+}
+
+/// computes line-level changes from the byte-level edit script
+///
+/// the fallback for inputs with too many lines for `compute_line_changes`'
+/// table. a left line is context, paired with a right line, when the script keeps
+/// every byte of both and maps the start of one onto the start of the
+/// other; every other line is deleted or inserted. pairs come out in order
+/// because the script does, so one pass over it is enough. a script that
+/// substitutes across line boundaries pairs fewer lines than the table
+/// would.
+fn script_line_changes(
+    left_data: &[u8],
+    left_lines: &[(usize, usize)],
+    right_data: &[u8],
+    right_lines: &[(usize, usize)],
+    operations: &[DiffOperation],
+) -> Vec<HunkLine> {
+    let mut left_dirty = vec![false; left_lines.len()];
+    let mut right_dirty = vec![false; right_lines.len()];
+    let mut partner: Vec<Option<usize>> = vec![None; left_lines.len()];
+    
+    // byte positions and the lines containing them
+    let (mut i, mut j) = (0, 0);
+    let (mut left_line, mut right_line) = (0, 0);
+    let mark = |dirty: &mut [bool], line: usize| {
+        if let Some(dirty) = dirty.get_mut(line) {
+            *dirty = true;
+        }
+    };
+    for op in operations {
+        let count = match *op {
+            DiffOperation::KeepRun(n) => n as usize,
+            _ => 1,
+        };
+        for _ in 0..count {
+            while left_line < left_lines.len() && left_lines[left_line].1 <= i {
+                left_line += 1;
+            }
+            while right_line < right_lines.len() && right_lines[right_line].1 <= j {
+                right_line += 1;
+            }
+            match op {
+                DiffOperation::Keep | DiffOperation::KeepRun(_) => {
+                    if left_line < left_lines.len() && right_line < right_lines.len()
+                        && left_lines[left_line].0 == i && right_lines[right_line].0 == j
+                    {
+                        partner[left_line] = Some(right_line);
+                    }
+                    i += 1;
+                    j += 1;
+                }
+                DiffOperation::Delete => {
+                    mark(&mut left_dirty, left_line);
+                    i += 1;
+                }
+                DiffOperation::Insert(_) => {
+                    mark(&mut right_dirty, right_line);
+                    j += 1;
+                }
+                DiffOperation::Substitute(_) => {
+                    mark(&mut left_dirty, left_line);
+                    mark(&mut right_dirty, right_line);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+    }
+    
+    let span_len = |(start, end): (usize, usize)| end - start;
+    let context_partner = |line: usize| partner[line].filter(|&other| {
+        !left_dirty[line] && !right_dirty[other]
+            && span_len(left_lines[line]) == span_len(right_lines[other])
+    });
+    
+    let mut changes = Vec::with_capacity(left_lines.len().max(right_lines.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < left_lines.len() || j < right_lines.len() {
+        match (i < left_lines.len()).then(|| context_partner(i)).flatten() {
+            Some(other) if other == j => {
+                changes.push(hunk_line(LineKind::Context, left_data, left_lines[i]));
+                i += 1;
+                j += 1;
+            }
+            Some(other) if other > j => {
+                changes.push(hunk_line(LineKind::Insert, right_data, right_lines[j]));
+                j += 1;
+            }
+            _ if i < left_lines.len() => {
+                changes.push(hunk_line(LineKind::Delete, left_data, left_lines[i]));
+                i += 1;
+            }
+            _ => {
+                changes.push(hunk_line(LineKind::Insert, right_data, right_lines[j]));
+                j += 1;
+            }
+        }
+    }
+    
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::ConstantTimeDiff;
+    use crate::types::SecurityConfig;
+
+    fn kinds(changes: &[HunkLine]) -> Vec<(LineKind, &str)> {
+        changes.iter().map(|line| (line.kind, line.content.as_str())).collect()
+    }
+
+    #[test]
+    fn test_line_table_cap() {
+        assert!(line_table_fits(2000, 2000));
+        assert!(line_table_fits(0, MAX_LINE_TABLE_BYTES / 4 - 1));
+        assert!(!line_table_fits(2048, 2048));
+        assert!(!line_table_fits(usize::MAX - 1, 2));
+    }
+
+    #[test]
+    fn test_script_line_changes_rebuild_both_sides() {
+        let differ = ConstantTimeDiff::new(SecurityConfig::default());
+        let cases: [(&[u8], &[u8]); 5] = [
+            (b"one\ntwo\nthree\n", b"one\nTWO\nthree\nfour\n"),
+            (b"keep\ndrop me\nkeep too\n", b"keep\nkeep too\n"),
+            (b"", b"new\nlines\n"),
+            (b"a\r\nb\r\n", b"a\r\nc\r\n"),
+            (b"same\n", b"same\n"),
+        ];
+        for (left, right) in cases {
+            let operations = differ.diff(left, right).unwrap().operations;
+            let changes = script_line_changes(left, &line_spans(left), right, &line_spans(right), &operations);
+            let side = |skip: LineKind| changes.iter()
+                .filter(|line| line.kind != skip)
+                .map(|line| line.content.as_str())
+                .collect::<Vec<_>>();
+            let lines = |data| std::str::from_utf8(data).unwrap().lines().collect::<Vec<_>>();
+            
+            // context and deletions are the left side, context and insertions the right
+            assert_eq!(side(LineKind::Insert), lines(left));
+            assert_eq!(side(LineKind::Delete), lines(right));
+        }
+    }
+
+    #[test]
+    fn test_script_line_changes_split_lines() {
+        // joining two lines keeps every byte but the newline, yet neither
+        // left line survives whole
+        let (left, right) = (b"ab\ncd\nend\n", b"abcd\nend\n");
+        let differ = ConstantTimeDiff::new(SecurityConfig::default());
+        let operations = differ.diff(left, right).unwrap().operations;
+        let changes = script_line_changes(left, &line_spans(left), right, &line_spans(right), &operations);
+        assert_eq!(kinds(&changes), vec![
+            (LineKind::Delete, "ab"),
+            (LineKind::Delete, "cd"),
+            (LineKind::Insert, "abcd"),
+            (LineKind::Context, "end"),
+        ]);
+    }
+}
@@ -3,21 +3,49 @@
 //! structured json output for programmatic consumption
 
 use crate::{error::Result, types::{DiffResult, DiffOperation}};
-use crate::formats::FormatOptions;
-use serde::{Serialize, Deserialize};
+use crate::formats::{contains_binary, render, FormatOptions};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Serialize, Serializer, Deserialize};
+use std::io::Write;
+
+/// schema version written to `metadata.format_version`
+pub const SCHEMA_VERSION: &str = "1.0";
+
+/// schema versions this crate can emit, oldest first
+pub const SUPPORTED_SCHEMA_VERSIONS: &[&str] = &["1.0"];
 
 /// represents the complete diff result in json format
-#[derive(Debug, Serialize, Deserialize)]
-struct JsonDiffResult {
+#[derive(Debug, Serialize)]
+struct JsonDiffResult<'a> {
     /// metadata about the comparison
     metadata: JsonMetadata,
     /// detailed statistics
     statistics: JsonStatistics,
     /// list of diff operations
-    operations: Vec<JsonOperation>,
+    operations: JsonOperations<'a>,
     /// line-based changes (if requested)
     #[serde(skip_serializing_if = "Option::is_none")]
     lines: Option<Vec<JsonLineChange>>,
+    /// base64 of the right input rebuilt from the operations (if requested)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reconstructed_right: Option<String>,
+}
+
+/// the `operations` array, converted one entry at a time as it's serialized
+#[derive(Debug)]
+struct JsonOperations<'a> {
+    operations: &'a [DiffOperation],
+    omit_keeps: bool,
+}
+
+impl Serialize for JsonOperations<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if self.omit_keeps {
+            serializer.collect_seq(convert_operations_omitting_keeps(self.operations))
+        } else {
+            serializer.collect_seq(convert_operations(self.operations))
+        }
+    }
 }
 
 /// metadata about the diff
@@ -33,8 +61,13 @@ struct JsonMetadata {
     right_size: usize,
     /// format version for compatibility
     format_version: String,
-    /// timestamp of comparison
-    timestamp: String,
+    /// whether either input is invalid utf-8, making `char` and `lines`
+    /// content lossy
+    contains_binary: bool,
+    /// rfc 3339 timestamp of comparison (omitted unless `include_timestamp`,
+    /// and always omitted when `deterministic`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
 }
 
 /// statistical information about the diff
@@ -79,6 +112,9 @@ struct JsonOperation {
     /// character representation (if printable)
     #[serde(skip_serializing_if = "Option::is_none")]
     char: Option<String>,
+    /// number of bytes kept by a keep_run operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    length: Option<u32>,
 }
 
 /// line-based change representation
@@ -106,6 +142,23 @@ pub fn format(
     result: &DiffResult,
     options: &FormatOptions,
 ) -> Result<String> {
+    render(|writer| write(writer, left_name, right_name, left_data, right_data, result, options))
+}
+
+/// writes diff result as json
+/// 
+/// produces the same bytes as `format`; operations are converted one at
+/// a time as they're written, rather than collected first.
+pub fn write(
+    writer: &mut dyn Write,
+    left_name: &str,
+    right_name: &str,
+    left_data: &[u8],
+    right_data: &[u8],
+    result: &DiffResult,
+    options: &FormatOptions,
+) -> Result<()> {
+    let format_version = schema_version(options)?;
     let statistics = compute_statistics(left_data, right_data, result);
     
     let json_result = JsonDiffResult {
@@ -114,30 +167,136 @@ pub fn format(
             right_name: right_name.to_string(),
             left_size: left_data.len(),
             right_size: right_data.len(),
-            format_version: "1.0".to_string(),
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            format_version: format_version.to_string(),
+            contains_binary: contains_binary(left_data, right_data),
+            timestamp: (options.include_timestamp && !options.deterministic)
+                .then(|| chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
         },
         statistics,
-        operations: convert_operations(&result.operations),
+        operations: JsonOperations {
+            operations: &result.operations,
+            omit_keeps: options.json_omit_keeps,
+        },
         lines: if should_include_lines(options) {
             Some(compute_line_changes(left_data, right_data, result)?)
         } else {
             None
         },
+        reconstructed_right: if options.json_include_reconstructed {
+            Some(STANDARD.encode(result.apply_to(left_data)?))
+        } else {
+            None
+        },
     };
     
-    let json_str = if options.json_pretty {
-        serde_json::to_string_pretty(&json_result)?
+    if options.json_pretty {
+        serde_json::to_writer_pretty(writer, &json_result)?;
     } else {
-        serde_json::to_string(&json_result)?
-    };
+        serde_json::to_writer(writer, &json_result)?;
+    }
     
-    Ok(json_str)
+    Ok(())
+}
+
+/// schema version to emit, honoring a pinned `json_schema_version`
+fn schema_version(options: &FormatOptions) -> Result<&'static str> {
+    match options.json_schema_version.as_deref() {
+        None => Ok(SCHEMA_VERSION),
+        Some(requested) => SUPPORTED_SCHEMA_VERSIONS.iter()
+            .find(|&&supported| supported == requested)
+            .copied()
+            .ok_or_else(|| crate::Error::configuration(format!(
+                "unsupported json schema version {} (supported: {})",
+                requested,
+                SUPPORTED_SCHEMA_VERSIONS.join(", ")
+            ))),
+    }
+}
+
+/// json schema document describing the output of `format`
+pub fn schema() -> serde_json::Value {
+    let count = serde_json::json!({ "type": "integer", "minimum": 0 });
+    
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "ctdiff json output",
+        "description": format!("ctdiff diff result, format_version {}", SCHEMA_VERSION),
+        "type": "object",
+        "required": ["metadata", "statistics", "operations"],
+        "additionalProperties": false,
+        "properties": {
+            "metadata": {
+                "type": "object",
+                "required": ["left_name", "right_name", "left_size", "right_size", "format_version"],
+                "additionalProperties": false,
+                "properties": {
+                    "left_name": { "type": "string" },
+                    "right_name": { "type": "string" },
+                    "left_size": count,
+                    "right_size": count,
+                    "format_version": { "type": "string", "enum": SUPPORTED_SCHEMA_VERSIONS },
+                    "contains_binary": { "type": "boolean" },
+                    "timestamp": { "type": "string", "format": "date-time" }
+                }
+            },
+            "statistics": {
+                "type": "object",
+                "required": ["edit_distance", "similarity", "identical", "operations"],
+                "additionalProperties": false,
+                "properties": {
+                    "edit_distance": count,
+                    "similarity": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "identical": { "type": "boolean" },
+                    "operations": {
+                        "type": "object",
+                        "required": ["total", "insertions", "deletions", "substitutions", "keeps"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "total": count,
+                            "insertions": count,
+                            "deletions": count,
+                            "substitutions": count,
+                            "keeps": count
+                        }
+                    }
+                }
+            },
+            "operations": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["type", "position"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "type": { "type": "string", "enum": ["keep", "keep_run", "insert", "delete", "substitute"] },
+                        "position": count,
+                        "value": { "type": "integer", "minimum": 0, "maximum": 255 },
+                        "char": { "type": "string" },
+                        "length": count
+                    }
+                }
+            },
+            "lines": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["type", "content"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "type": { "type": "string", "enum": ["equal", "delete", "insert"] },
+                        "old_line": count,
+                        "new_line": count,
+                        "content": { "type": "string" }
+                    }
+                }
+            },
+            "reconstructed_right": { "type": "string", "contentEncoding": "base64" }
+        }
+    })
 }
 
 /// computes statistics for json output
 fn compute_statistics(left_data: &[u8], right_data: &[u8], result: &DiffResult) -> JsonStatistics {
-    let total_ops = result.operations.len();
     let insertions = result.operations.iter()
         .filter(|op| matches!(op, DiffOperation::Insert(_)))
         .count();
@@ -147,7 +306,10 @@ fn compute_statistics(left_data: &[u8], right_data: &[u8], result: &DiffResult)
     let substitutions = result.operations.iter()
         .filter(|op| matches!(op, DiffOperation::Substitute(_)))
         .count();
-    let keeps = total_ops - insertions - deletions - substitutions;
+    let keeps = result.operations.iter()
+        .map(DiffOperation::kept_bytes)
+        .sum::<usize>();
+    let total_ops = result.expanded_operation_count();
     
     let max_len = left_data.len().max(right_data.len());
     let similarity = if max_len == 0 {
@@ -171,35 +333,76 @@ fn compute_statistics(left_data: &[u8], right_data: &[u8], result: &DiffResult)
 }
 
 /// converts diff operations to json format
-fn convert_operations(operations: &[DiffOperation]) -> Vec<JsonOperation> {
-    operations.iter().enumerate().map(|(pos, op)| {
-        match op {
-            DiffOperation::Keep => JsonOperation {
-                op_type: "keep".to_string(),
-                position: pos,
-                value: None,
-                char: None,
-            },
-            DiffOperation::Insert(byte) => JsonOperation {
-                op_type: "insert".to_string(),
-                position: pos,
-                value: Some(*byte),
-                char: char_representation(*byte),
-            },
-            DiffOperation::Delete => JsonOperation {
-                op_type: "delete".to_string(),
-                position: pos,
-                value: None,
-                char: None,
-            },
-            DiffOperation::Substitute(byte) => JsonOperation {
-                op_type: "substitute".to_string(),
-                position: pos,
-                value: Some(*byte),
-                char: char_representation(*byte),
-            },
+fn convert_operations(operations: &[DiffOperation]) -> impl Iterator<Item = JsonOperation> + '_ {
+    operations.iter().enumerate().map(|(pos, op)| convert_operation(pos, op))
+}
+
+/// converts diff operations to json format, merging each run of keeps into
+/// a single `keep_run` at the run's first position
+fn convert_operations_omitting_keeps(operations: &[DiffOperation]) -> impl Iterator<Item = JsonOperation> + '_ {
+    let mut operations = operations.iter().enumerate().peekable();
+    
+    std::iter::from_fn(move || {
+        let (pos, op) = operations.next()?;
+        if op.is_modification() {
+            return Some(convert_operation(pos, op));
+        }
+        
+        let mut count = op.kept_bytes() as u32;
+        while let Some(&(_, next)) = operations.peek() {
+            if next.is_modification() {
+                break;
+            }
+            // a run too long for one entry continues in the next
+            match count.checked_add(next.kept_bytes() as u32) {
+                Some(total) => count = total,
+                None => break,
+            }
+            operations.next();
         }
-    }).collect()
+        Some(convert_operation(pos, &DiffOperation::KeepRun(count)))
+    })
+}
+
+/// converts a single operation at `pos` to json format
+fn convert_operation(pos: usize, op: &DiffOperation) -> JsonOperation {
+    match op {
+        DiffOperation::Keep => JsonOperation {
+            op_type: "keep".to_string(),
+            position: pos,
+            value: None,
+            char: None,
+            length: None,
+        },
+        DiffOperation::KeepRun(n) => JsonOperation {
+            op_type: "keep_run".to_string(),
+            position: pos,
+            value: None,
+            char: None,
+            length: Some(*n),
+        },
+        DiffOperation::Insert(byte) => JsonOperation {
+            op_type: "insert".to_string(),
+            position: pos,
+            value: Some(*byte),
+            char: char_representation(*byte),
+            length: None,
+        },
+        DiffOperation::Delete => JsonOperation {
+            op_type: "delete".to_string(),
+            position: pos,
+            value: None,
+            char: None,
+            length: None,
+        },
+        DiffOperation::Substitute(byte) => JsonOperation {
+            op_type: "substitute".to_string(),
+            position: pos,
+            value: Some(*byte),
+            char: char_representation(*byte),
+            length: None,
+        },
+    }
 }
 
 /// creates character representation for byte value
@@ -221,15 +424,11 @@ fn should_include_lines(options: &FormatOptions) -> bool {
 /// computes line changes for json output
 fn compute_line_changes(
     left_data: &[u8],
-    _right_data: &[u8],
-    result: &DiffResult,
+    right_data: &[u8],
+    _result: &DiffResult,
 ) -> Result<Vec<JsonLineChange>> {
-    // reconstruct right side
-    let right_reconstructed = result.apply_to(left_data)
-        .map_err(|e| crate::Error::format(format!("failed to reconstruct: {}", e)))?;
-    
     let left_text = String::from_utf8_lossy(left_data);
-    let right_text = String::from_utf8_lossy(&right_reconstructed);
+    let right_text = String::from_utf8_lossy(right_data);
     
     let left_lines: Vec<&str> = left_text.lines().collect();
     let right_lines: Vec<&str> = right_text.lines().collect();
@@ -275,25 +474,3 @@ fn compute_line_changes(
     
     Ok(changes)
 }
-
-// add chrono dependency placeholder - would need to add to Cargo.toml
-mod chrono {
-    pub struct Utc;
-    impl Utc {
-        pub fn now() -> DateTime {
-            DateTime
-        }
-    }
-    
-    pub struct DateTime;
-    impl DateTime {
-        pub fn to_rfc3339(&self) -> String {
-            // placeholder - would use actual chrono in real implementation
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                .to_string()
-        }
-    }
-}
\ No newline at end of file
@@ -1,7 +1,14 @@
 //! output format implementations for diff results
 //! 
-//! provides multiple output formats including unified, json, html, git, and summary
+//! provides multiple output formats including unified, json, html, git, summary,
+//! side-by-side, and raw operation listings
+//!
+//! every format takes both inputs; `right_data` must be what the edit script
+//! produces from `left_data`, so formats read it instead of re-applying the
+//! script themselves.
 
+use crate::result::similarity_ratio;
+use crate::types::DiffResult;
 use serde::{Deserialize, Serialize};
 
 pub mod unified;
@@ -9,6 +16,13 @@ pub mod json;
 pub mod html;
 pub mod git;
 pub mod summary;
+pub mod side_by_side;
+pub mod operations;
+pub mod color;
+pub mod hunks;
+
+pub use color::{Ansi, ColorChoice};
+pub use hunks::{Hunk, HunkLine, LineKind};
 
 /// supported output formats
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -23,6 +37,10 @@ pub enum OutputFormat {
     Git,
     /// high-level diff statistics summary
     Summary,
+    /// two-column left/right comparison
+    SideBySide,
+    /// numbered list of raw edit operations
+    Operations,
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -33,6 +51,8 @@ impl std::fmt::Display for OutputFormat {
             OutputFormat::Html => write!(f, "html"),
             OutputFormat::Git => write!(f, "git"),
             OutputFormat::Summary => write!(f, "summary"),
+            OutputFormat::SideBySide => write!(f, "side-by-side"),
+            OutputFormat::Operations => write!(f, "operations"),
         }
     }
 }
@@ -47,11 +67,32 @@ impl std::str::FromStr for OutputFormat {
             "html" | "h" => Ok(OutputFormat::Html),
             "git" | "g" => Ok(OutputFormat::Git),
             "summary" | "s" => Ok(OutputFormat::Summary),
+            "side-by-side" | "sidebyside" | "y" => Ok(OutputFormat::SideBySide),
+            "operations" | "ops" | "o" => Ok(OutputFormat::Operations),
             _ => Err(crate::Error::invalid_input(format!("unknown format: {}", s))),
         }
     }
 }
 
+/// true if either input isn't valid utf-8
+/// 
+/// text formats decode lossily, so for such inputs they would show
+/// replacement characters instead of the real bytes. unified and git output
+/// say `Binary files … differ` instead, and json and summary output flag it.
+pub fn contains_binary(left_data: &[u8], right_data: &[u8]) -> bool {
+    std::str::from_utf8(left_data).is_err() || std::str::from_utf8(right_data).is_err()
+}
+
+/// collects a format's streaming `write` into a string, for its `format`
+/// 
+/// both entry points share one implementation, so their output is the same
+/// byte for byte.
+pub(crate) fn render(write: impl FnOnce(&mut dyn std::io::Write) -> crate::error::Result<()>) -> crate::error::Result<String> {
+    let mut buffer = Vec::new();
+    write(&mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
 /// format-specific configuration options
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FormatOptions {
@@ -63,12 +104,86 @@ pub struct FormatOptions {
     pub html_theme: HtmlTheme,
     /// include file metadata in output
     pub include_metadata: bool,
-    /// maximum line width for output
+    /// maximum line width for output, in characters; longer lines are cut
+    /// and end in `…`
     pub max_line_width: Option<usize>,
     /// show line numbers
     pub show_line_numbers: bool,
     /// word-level diffing for text
     pub word_diff: bool,
+    /// report relocated blocks (summary format)
+    #[serde(default)]
+    pub show_moves: bool,
+    /// json schema version to emit (`None` = `json::SCHEMA_VERSION`)
+    #[serde(default)]
+    pub json_schema_version: Option<String>,
+    /// include a comparison timestamp in json output; turn off for
+    /// reproducible output
+    #[serde(default = "default_include_timestamp")]
+    pub include_timestamp: bool,
+    /// byte-for-byte reproducible output for golden tests
+    /// 
+    /// drops timestamps and color regardless of the other options, and emits
+    /// `json_schema_version` or else `json::SCHEMA_VERSION`. formats otherwise
+    /// depend only on the inputs and options.
+    #[serde(default)]
+    pub deterministic: bool,
+    /// collapse unchanged bytes in json `operations` into `keep_run` entries
+    /// 
+    /// each run of keeps becomes one entry whose `length` is the run length,
+    /// so a large file with few changes stays small. statistics are unaffected.
+    #[serde(default)]
+    pub json_omit_keeps: bool,
+    /// stop unified and git output after this many hunks
+    /// 
+    /// the rest are summarized by a single `… and N more hunks omitted`
+    /// line, which `git apply` won't accept, so leave this unset for patches.
+    #[serde(default)]
+    pub max_hunks: Option<usize>,
+    /// report each input's shannon entropy (summary format)
+    /// 
+    /// inputs above 7.5 bits per byte are flagged as likely encrypted or
+    /// compressed.
+    #[serde(default)]
+    pub show_entropy: bool,
+    /// number each line of git hunks, like `show_line_numbers` does for
+    /// unified output
+    /// 
+    /// separate from `show_line_numbers`, which is on by default, because
+    /// `git apply` rejects numbered lines. `git::format_series` ignores it.
+    #[serde(default)]
+    pub git_line_numbers: bool,
+    /// add a `+++---` bar of insertions against deletions to the html
+    /// statistics, at most `html::DIFFSTAT_BLOCKS` blocks wide
+    #[serde(default)]
+    pub html_diffstat: bool,
+    /// add the right input, rebuilt by applying the operations to the
+    /// left, as base64 `reconstructed_right` in json output
+    /// 
+    /// unlike `lines` it keeps every byte, so it works for binary inputs.
+    #[serde(default)]
+    pub json_include_reconstructed: bool,
+    /// below this similarity, unified, git and html output show the change
+    /// as the whole left input deleted and then the whole right one
+    /// inserted, instead of interleaving the few lines that still match
+    /// 
+    /// only the rendering changes; the result and its operations don't.
+    #[serde(default)]
+    pub rewrite_threshold: Option<f64>,
+    /// regex for "section" lines, such as function headers; unified hunk
+    /// headers end with the nearest matching left line before the hunk,
+    /// like `git diff`'s `@@ ... @@ def foo():`
+    /// 
+    /// `unified::DEFAULT_HUNK_CONTEXT_PATTERN` matches any non-indented
+    /// line. an invalid regex is a configuration error when formatting, as
+    /// is any pattern without the `regex` feature.
+    #[serde(default)]
+    pub hunk_context_pattern: Option<String>,
+}
+
+/// serde default for `FormatOptions::include_timestamp`
+fn default_include_timestamp() -> bool {
+    true
 }
 
 /// html theme options
@@ -89,11 +204,32 @@ impl Default for FormatOptions {
             max_line_width: Some(120),
             show_line_numbers: true,
             word_diff: false,
+            show_moves: false,
+            json_schema_version: None,
+            include_timestamp: true,
+            deterministic: false,
+            json_omit_keeps: false,
+            max_hunks: None,
+            show_entropy: false,
+            git_line_numbers: false,
+            html_diffstat: false,
+            json_include_reconstructed: false,
+            rewrite_threshold: None,
+            hunk_context_pattern: None,
         }
     }
 }
 
 impl FormatOptions {
+    /// true if `rewrite_threshold` asks for this diff to be shown as a
+    /// full rewrite
+    pub(crate) fn renders_as_rewrite(&self, result: &DiffResult, left_data: &[u8], right_data: &[u8]) -> bool {
+        self.rewrite_threshold.is_some_and(|threshold| {
+            result.edit_distance != 0
+                && similarity_ratio(result.edit_distance, left_data.len(), right_data.len()) < threshold
+        })
+    }
+    
     /// creates minimal options for compact output
     pub fn minimal() -> Self {
         Self {
@@ -104,6 +240,18 @@ impl FormatOptions {
             max_line_width: None,
             show_line_numbers: false,
             word_diff: false,
+            show_moves: false,
+            json_schema_version: None,
+            include_timestamp: true,
+            deterministic: false,
+            json_omit_keeps: false,
+            max_hunks: None,
+            show_entropy: false,
+            git_line_numbers: false,
+            html_diffstat: false,
+            json_include_reconstructed: false,
+            rewrite_threshold: None,
+            hunk_context_pattern: None,
         }
     }
     
@@ -117,6 +265,18 @@ impl FormatOptions {
             max_line_width: Some(120),
             show_line_numbers: true,
             word_diff: true,
+            show_moves: true,
+            json_schema_version: None,
+            include_timestamp: true,
+            deterministic: false,
+            json_omit_keeps: false,
+            max_hunks: None,
+            show_entropy: false,
+            git_line_numbers: false,
+            html_diffstat: true,
+            json_include_reconstructed: false,
+            rewrite_threshold: None,
+            hunk_context_pattern: None,
         }
     }
 }
\ No newline at end of file
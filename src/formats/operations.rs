@@ -0,0 +1,58 @@
+//! operations list format implementation
+//!
+//! raw edit script listing, one operation per line with its index
+
+use crate::{error::Result, types::{DiffResult, DiffOperation}};
+use crate::formats::FormatOptions;
+use crate::formats::Ansi;
+
+/// formats diff result as a numbered list of operations
+pub fn format(
+    _left_name: &str,
+    _right_name: &str,
+    _left_data: &[u8],
+    _right_data: &[u8],
+    result: &DiffResult,
+    enable_color: bool,
+    options: &FormatOptions,
+) -> Result<String> {
+    let mut output = String::new();
+
+    // summary header
+    if options.include_metadata {
+        output.push_str(&format!("edit distance: {}\n", result.edit_distance));
+        output.push_str(&format!("operations: {}\n", result.expanded_operation_count()));
+        output.push_str("---\n");
+    }
+
+    for (i, op) in result.operations.iter().enumerate() {
+        let formatted = format!("{:4}: {}", i, describe_operation(op));
+
+        let formatted = if enable_color {
+            match op {
+                DiffOperation::Keep | DiffOperation::KeepRun(_) => formatted,
+                DiffOperation::Insert(_) => Ansi(&formatted).green(),
+                DiffOperation::Delete => Ansi(&formatted).red(),
+                DiffOperation::Substitute(_) => Ansi(&formatted).yellow(),
+            }
+        } else {
+            formatted
+        };
+
+        output.push_str(&formatted);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// human-readable description of a single operation
+fn describe_operation(op: &DiffOperation) -> String {
+    match op {
+        DiffOperation::Keep => "keep".to_string(),
+        DiffOperation::KeepRun(n) => format!("keep {}", n),
+        DiffOperation::Insert(b) => format!("insert '{}'", char::from(*b).escape_debug()),
+        DiffOperation::Delete => "delete".to_string(),
+        DiffOperation::Substitute(b) => format!("substitute '{}'", char::from(*b).escape_debug()),
+    }
+}
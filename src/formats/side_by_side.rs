@@ -0,0 +1,65 @@
+//! side-by-side format implementation
+//!
+//! two-column view of left and right lines for quick visual comparison
+
+use crate::{error::Result, types::DiffResult};
+use crate::formats::FormatOptions;
+use crate::formats::Ansi;
+
+/// width of the left column in characters
+const COLUMN_WIDTH: usize = 40;
+
+/// formats diff result as side-by-side columns
+pub fn format(
+    left_name: &str,
+    right_name: &str,
+    left_data: &[u8],
+    right_data: &[u8],
+    _result: &DiffResult,
+    enable_color: bool,
+    options: &FormatOptions,
+) -> Result<String> {
+    let mut output = String::new();
+
+    // column header
+    if options.include_metadata {
+        let header = format!("{:width$} | {}", left_name, right_name, width = COLUMN_WIDTH);
+        output.push_str(&header);
+        output.push('\n');
+        output.push_str(&"-".repeat(header.len()));
+        output.push('\n');
+    }
+
+    let left_text = String::from_utf8_lossy(left_data);
+    let right_text = String::from_utf8_lossy(right_data);
+
+    let left_lines: Vec<&str> = left_text.lines().collect();
+    let right_lines: Vec<&str> = right_text.lines().collect();
+
+    let max_lines = left_lines.len().max(right_lines.len());
+
+    for i in 0..max_lines {
+        let left = left_lines.get(i).copied().unwrap_or("");
+        let right = right_lines.get(i).copied().unwrap_or("");
+
+        let line_same = left == right;
+        let left_display = format!("{:width$}", left.chars().take(COLUMN_WIDTH).collect::<String>(), width = COLUMN_WIDTH);
+
+        let line_number = if options.show_line_numbers {
+            format!("{:4} ", i + 1)
+        } else {
+            String::new()
+        };
+
+        let formatted_line = if enable_color && !line_same {
+            format!("{}{} | {}", line_number, Ansi(&left_display).red(), Ansi(right).green())
+        } else {
+            format!("{}{} | {}", line_number, left_display, right)
+        };
+
+        output.push_str(&formatted_line);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
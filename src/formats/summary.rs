@@ -2,8 +2,9 @@
 //! 
 //! high-level diff statistics and summary information
 
-use crate::{error::Result, types::{DiffResult, DiffOperation}};
-use crate::formats::FormatOptions;
+use crate::{error::Result, moves, types::{DiffResult, DiffOperation}};
+use crate::formats::{contains_binary, FormatOptions};
+use crate::formats::Ansi;
 
 /// formats diff result as summary statistics
 pub fn format(
@@ -12,6 +13,7 @@ pub fn format(
     left_data: &[u8],
     right_data: &[u8],
     result: &DiffResult,
+    enable_color: bool,
     options: &FormatOptions,
 ) -> Result<String> {
     let mut output = String::new();
@@ -22,7 +24,7 @@ pub fn format(
     }
     
     // main statistics
-    output.push_str(&format_statistics(left_data, right_data, result, options));
+    output.push_str(&format_statistics(left_data, right_data, result, enable_color, options));
     
     // detailed breakdown if requested
     if options.include_metadata {
@@ -32,6 +34,12 @@ pub fn format(
     Ok(output)
 }
 
+/// notes how many lines were equal once line endings were normalized
+pub fn format_line_ending_note(count: usize) -> String {
+    let noun = if count == 1 { "line" } else { "lines" };
+    format!("Note: {} {} differed only in line endings\n", count, noun)
+}
+
 /// formats the summary header
 fn format_header(left_name: &str, right_name: &str) -> String {
     format!("Diff Summary: {} → {}\n{}\n", 
@@ -46,6 +54,7 @@ fn format_statistics(
     left_data: &[u8],
     right_data: &[u8],
     result: &DiffResult,
+    enable_color: bool,
     options: &FormatOptions,
 ) -> String {
     let mut stats = String::new();
@@ -61,12 +70,18 @@ fn format_statistics(
     let similarity_percent = (similarity * 100.0).round();
     
     // status line
-    if result.edit_distance == 0 {
-        stats.push_str("Status: IDENTICAL\n");
+    let status = if result.edit_distance == 0 {
+        "IDENTICAL".to_string()
     } else {
-        stats.push_str(&format!("Status: {} changes ({:.1}% similar)\n", 
-            result.edit_distance, similarity_percent));
-    }
+        format!("{} changes ({:.1}% similar)", result.edit_distance, similarity_percent)
+    };
+    let status = match (enable_color, result.edit_distance == 0) {
+        (false, _) => status,
+        (true, true) => Ansi(&status).green(),
+        (true, false) => Ansi(&status).yellow(),
+    };
+    stats.push_str(&format!("Status: {}\n", status));
+    stats.push_str(&format!("Change Kind: {}\n", result.classify()));
     
     stats.push_str(&format!("Edit Distance: {}\n", result.edit_distance));
     stats.push_str(&format!("Similarity: {:.2}%\n", similarity_percent));
@@ -74,6 +89,9 @@ fn format_statistics(
     // file sizes
     stats.push_str(&format!("Left Size: {} bytes\n", left_data.len()));
     stats.push_str(&format!("Right Size: {} bytes\n", right_data.len()));
+    if contains_binary(left_data, right_data) {
+        stats.push_str("Contains Binary: true\n");
+    }
     
     let size_change = right_data.len() as i64 - left_data.len() as i64;
     if size_change != 0 {
@@ -87,17 +105,21 @@ fn format_statistics(
     stats.push_str(&format!("  Total: {}\n", op_stats.total));
     
     if op_stats.insertions > 0 {
-        stats.push_str(&format!("  Insertions: {} ({:.1}%)\n", 
+        let line = format!("  Insertions: {} ({:.1}%)", 
             op_stats.insertions, 
             (op_stats.insertions as f64 / op_stats.total as f64) * 100.0
-        ));
+        );
+        stats.push_str(&if enable_color { Ansi(&line).green() } else { line });
+        stats.push('\n');
     }
     
     if op_stats.deletions > 0 {
-        stats.push_str(&format!("  Deletions: {} ({:.1}%)\n", 
+        let line = format!("  Deletions: {} ({:.1}%)", 
             op_stats.deletions,
             (op_stats.deletions as f64 / op_stats.total as f64) * 100.0
-        ));
+        );
+        stats.push_str(&if enable_color { Ansi(&line).red() } else { line });
+        stats.push('\n');
     }
     
     if op_stats.substitutions > 0 {
@@ -114,24 +136,89 @@ fn format_statistics(
         ));
     }
     
+    if result.edit_distance > 0 {
+        stats.push_str(&format_cost_breakdown(&result.operations));
+    }
+    
+    if options.show_entropy {
+        stats.push_str("\nEntropy:\n");
+        for (label, data) in [("Left", left_data), ("Right", right_data)] {
+            let entropy = shannon_entropy(data);
+            let flag = if entropy > HIGH_ENTROPY_THRESHOLD { " (likely encrypted or compressed)" } else { "" };
+            stats.push_str(&format!("  {}: {:.2} bits/byte{}\n", label, entropy, flag));
+        }
+    }
+    
+    if options.show_moves {
+        let moved = moves::detect_moves(left_data, right_data, result).len();
+        let noun = if moved == 1 { "block" } else { "blocks" };
+        stats.push_str(&format!("\n{} {} moved.\n", moved, noun));
+    }
+    
     // line-based statistics if text
     if is_likely_text(left_data) && is_likely_text(right_data) {
-        match format_line_statistics(left_data, right_data, result, options) {
-            Ok(line_stats) => stats.push_str(&line_stats),
-            Err(_) => {}, // ignore errors in statistics
+        // ignore errors in statistics
+        if let Ok(line_stats) = format_line_statistics(left_data, right_data, result, options) {
+            stats.push_str(&line_stats);
         }
     }
     
     stats
 }
 
+/// entropy above which the summary flags an input as likely encrypted or
+/// compressed, in bits per byte
+pub const HIGH_ENTROPY_THRESHOLD: f64 = 7.5;
+
+/// shannon entropy of `data` in bits per byte, from 0.0 to 8.0
+/// 
+/// empty input has entropy 0.0. not constant-time; like the rest of the
+/// summary this only runs when formatting.
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    
+    let mut histogram = [0usize; 256];
+    for &byte in data {
+        histogram[byte as usize] += 1;
+    }
+    
+    let total = data.len() as f64;
+    histogram.iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// formats each operation kind's share of the total cost
+fn format_cost_breakdown(operations: &[DiffOperation]) -> String {
+    let cost_of = |kind: fn(&DiffOperation) -> bool| -> usize {
+        operations.iter().filter(|op| kind(op)).map(DiffOperation::cost).sum()
+    };
+    let inserts = cost_of(|op| matches!(op, DiffOperation::Insert(_)));
+    let deletes = cost_of(|op| matches!(op, DiffOperation::Delete));
+    let substitutes = cost_of(|op| matches!(op, DiffOperation::Substitute(_)));
+    let total = (inserts + deletes + substitutes).max(1) as f64;
+    
+    let mut breakdown = String::from("\nCost breakdown:\n");
+    for (label, cost) in [("Insert", inserts), ("Delete", deletes), ("Substitute", substitutes)] {
+        breakdown.push_str(&format!("  {}: {} ({:.1}%)\n", label, cost, cost as f64 / total * 100.0));
+    }
+    
+    breakdown
+}
+
 /// formats detailed breakdown section
 fn format_detailed_breakdown(result: &DiffResult, options: &FormatOptions) -> String {
     let mut breakdown = String::new();
     
     breakdown.push_str("\nDetailed Breakdown:\n");
     breakdown.push_str(&"-".repeat(20));
-    breakdown.push_str("\n");
+    breakdown.push('\n');
     
     // operation type analysis
     let op_stats = compute_operation_stats(&result.operations);
@@ -160,18 +247,14 @@ fn format_detailed_breakdown(result: &DiffResult, options: &FormatOptions) -> St
 /// formats line-based statistics for text files
 fn format_line_statistics(
     left_data: &[u8],
-    _right_data: &[u8],
-    result: &DiffResult,
+    right_data: &[u8],
+    _result: &DiffResult,
     _options: &FormatOptions,
 ) -> Result<String> {
     let mut stats = String::new();
     
-    // reconstruct right side for line counting
-    let right_reconstructed = result.apply_to(left_data)
-        .map_err(|e| crate::Error::format(format!("failed to reconstruct: {}", e)))?;
-    
     let left_text = String::from_utf8_lossy(left_data);
-    let right_text = String::from_utf8_lossy(&right_reconstructed);
+    let right_text = String::from_utf8_lossy(right_data);
     
     let left_lines = left_text.lines().count();
     let right_lines = right_text.lines().count();
@@ -191,11 +274,11 @@ fn format_line_statistics(
 
 /// computes operation statistics
 fn compute_operation_stats(operations: &[DiffOperation]) -> OperationStats {
-    let total = operations.len();
     let insertions = operations.iter().filter(|op| matches!(op, DiffOperation::Insert(_))).count();
     let deletions = operations.iter().filter(|op| matches!(op, DiffOperation::Delete)).count();
     let substitutions = operations.iter().filter(|op| matches!(op, DiffOperation::Substitute(_))).count();
-    let keeps = total - insertions - deletions - substitutions;
+    let keeps = operations.iter().map(DiffOperation::kept_bytes).sum::<usize>();
+    let total = insertions + deletions + substitutions + keeps;
     
     OperationStats {
         total,
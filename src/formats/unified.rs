@@ -3,300 +3,180 @@
 //! standard unified diff format compatible with unix diff tools
 
 use crate::{error::Result, types::DiffResult};
-use crate::formats::FormatOptions;
-use colored::Colorize;
+use crate::formats::{contains_binary, render, FormatOptions};
+use crate::formats::Ansi;
+use crate::formats::hunks::{build_hunks, omitted_hunks_notice, Hunk, HunkLine, LineKind};
+use std::io::Write;
+
+/// `hunk_context_pattern` matching any line that doesn't start with whitespace
+pub const DEFAULT_HUNK_CONTEXT_PATTERN: &str = r"^\S";
 
 /// formats diff result as unified diff
+#[allow(clippy::too_many_arguments)]
 pub fn format(
     left_name: &str,
     right_name: &str,
     left_data: &[u8],
-    _right_data: &[u8],
+    right_data: &[u8],
     result: &DiffResult,
     context_lines: usize,
     enable_color: bool,
     options: &FormatOptions,
 ) -> Result<String> {
-    let mut output = String::new();
+    render(|writer| write(writer, left_name, right_name, left_data, right_data, result, context_lines, enable_color, options))
+}
+
+/// writes diff result as unified diff, one hunk at a time
+/// 
+/// produces the same bytes as `format` without collecting them in memory.
+#[allow(clippy::too_many_arguments)]
+pub fn write(
+    writer: &mut dyn Write,
+    left_name: &str,
+    right_name: &str,
+    left_data: &[u8],
+    right_data: &[u8],
+    result: &DiffResult,
+    context_lines: usize,
+    enable_color: bool,
+    options: &FormatOptions,
+) -> Result<()> {
+    // hunks of non-utf-8 input would be garbled, so say only that they differ, as diff does
+    if result.edit_distance != 0 && contains_binary(left_data, right_data) {
+        writeln!(writer, "Binary files {} and {} differ", left_name, right_name)?;
+        return Ok(());
+    }
     
     // add metadata if requested
     if options.include_metadata {
-        output.push_str(&format!("--- {}\n", left_name));
-        output.push_str(&format!("+++ {}\n", right_name));
+        writeln!(writer, "--- {}", left_name)?;
+        writeln!(writer, "+++ {}", right_name)?;
     }
     
     // if files are identical, return early
     if result.edit_distance == 0 {
-        return Ok(output);
+        return Ok(());
     }
     
-    // convert to text for line-based processing
-    let left_text = String::from_utf8_lossy(left_data);
-    let right_text = reconstruct_right_text(left_data, result)?;
-    let right_text_str = String::from_utf8_lossy(&right_text);
+    let rewrite = options.renders_as_rewrite(result, left_data, right_data);
+    let hunks = build_hunks(left_data, right_data, &result.operations, context_lines, rewrite);
+    let shown = options.max_hunks.map_or(hunks.len(), |max| max.min(hunks.len()));
     
+    let anchor_pattern = section_pattern(options)?;
+    let left_text = String::from_utf8_lossy(left_data);
     let left_lines: Vec<&str> = left_text.lines().collect();
-    let right_lines: Vec<&str> = right_text_str.lines().collect();
-    
-    // build hunks with context
-    let hunks = build_hunks(&left_lines, &right_lines, context_lines, options)?;
     
-    for hunk in hunks {
-        output.push_str(&hunk.format_header());
-        output.push('\n');
+    for hunk in &hunks[..shown] {
+        match anchor_pattern.as_ref().and_then(|pattern| hunk_anchor(&left_lines, hunk, pattern)) {
+            Some(anchor) => writeln!(writer, "{} {}", hunk.header(), anchor)?,
+            None => writeln!(writer, "{}", hunk.header())?,
+        }
         
-        for line in hunk.lines {
-            let formatted_line = format_line(&line, enable_color, options);
-            output.push_str(&formatted_line);
-            output.push('\n');
+        // context and deletions are numbered on the left, insertions on the right
+        let (mut old_line, mut new_line) = (hunk.old_start, hunk.new_start);
+        for line in &hunk.lines {
+            let line_number = match line.kind {
+                LineKind::Insert => new_line,
+                LineKind::Context | LineKind::Delete => old_line,
+            };
+            old_line += usize::from(line.kind != LineKind::Insert);
+            new_line += usize::from(line.kind != LineKind::Delete);
+            
+            writeln!(writer, "{}", format_line(line, line_number, enable_color, options))?;
         }
     }
     
-    Ok(output)
+    if shown < hunks.len() {
+        writer.write_all(omitted_hunks_notice(hunks.len() - shown).as_bytes())?;
+    }
+    
+    Ok(())
 }
 
-/// reconstructs the right-side text from operations
-fn reconstruct_right_text(left_data: &[u8], result: &DiffResult) -> Result<Vec<u8>> {
-    result.apply_to(left_data).map_err(|e| crate::Error::format(format!("failed to reconstruct text: {}", e)))
-}
+/// compiled `hunk_context_pattern`
+#[cfg(feature = "regex")]
+type SectionPattern = regex::Regex;
 
-/// builds hunks with proper context lines
-fn build_hunks(
-    left_lines: &[&str],
-    right_lines: &[&str], 
-    context_lines: usize,
-    options: &FormatOptions,
-) -> Result<Vec<Hunk>> {
-    // simple line-based diff for display
-    let changes = compute_line_changes(left_lines, right_lines);
-    
-    if changes.is_empty() {
-        return Ok(vec![]);
-    }
-    
-    // group changes into hunks with context
-    let mut hunks = Vec::new();
-    let mut current_hunk: Option<HunkBuilder> = None;
-    
-    for (i, change) in changes.iter().enumerate() {
-        match change {
-            LineChange::Equal(_) => {
-                if let Some(ref mut hunk) = current_hunk {
-                    // add context line to existing hunk or finish it
-                    if hunk.should_continue(i, context_lines) {
-                        hunk.add_line(change.clone(), i);
-                    } else {
-                        // finish current hunk and start new one if more changes follow
-                        let built_hunk = std::mem::replace(hunk, HunkBuilder::new()).build(options);
-                        hunks.push(built_hunk);
-                        current_hunk = None;
-                        
-                        // check if we need to start a new hunk soon
-                        if has_changes_ahead(&changes[i..], context_lines) {
-                            let mut new_hunk = HunkBuilder::new();
-                            new_hunk.add_line(change.clone(), i);
-                            current_hunk = Some(new_hunk);
-                        }
-                    }
-                }
-            }
-            LineChange::Delete(_) | LineChange::Insert(_) => {
-                if current_hunk.is_none() {
-                    let mut new_hunk = HunkBuilder::new();
-                    // add leading context
-                    let context_start = i.saturating_sub(context_lines);
-                    for j in context_start..i {
-                        if let LineChange::Equal(_) = &changes[j] {
-                            new_hunk.add_line(changes[j].clone(), j);
-                        }
-                    }
-                    current_hunk = Some(new_hunk);
-                }
-                
-                if let Some(ref mut hunk) = current_hunk {
-                    hunk.add_line(change.clone(), i);
-                }
-            }
-        }
-    }
-    
-    // finish last hunk
-    if let Some(hunk) = current_hunk {
-        hunks.push(hunk.build(options));
+/// stands in for the compiled pattern without the `regex` feature; never
+/// constructed, since setting a pattern is then an error
+#[cfg(not(feature = "regex"))]
+enum SectionPattern {}
+
+#[cfg(not(feature = "regex"))]
+impl SectionPattern {
+    fn is_match(&self, _line: &str) -> bool {
+        match *self {}
     }
-    
-    Ok(hunks)
 }
 
-/// checks if there are changes ahead within context distance
-fn has_changes_ahead(changes: &[LineChange], context_lines: usize) -> bool {
-    changes.iter().take(context_lines * 2).any(|c| !matches!(c, LineChange::Equal(_)))
+/// compiles `hunk_context_pattern`, if one is set
+/// 
+/// an invalid pattern, or any pattern when built without the `regex`
+/// feature, is a configuration error.
+fn section_pattern(options: &FormatOptions) -> Result<Option<SectionPattern>> {
+    let Some(pattern) = options.hunk_context_pattern.as_deref() else {
+        return Ok(None);
+    };
+    
+    #[cfg(feature = "regex")]
+    return regex::Regex::new(pattern).map(Some).map_err(|e| crate::Error::configuration(format!(
+        "invalid hunk context pattern {:?}: {}", pattern, e
+    )));
+    #[cfg(not(feature = "regex"))]
+    Err(crate::Error::configuration(format!(
+        "hunk context pattern {:?} needs ctdiff's `regex` feature", pattern
+    )))
 }
 
-/// computes line-level changes between texts
-fn compute_line_changes(left_lines: &[&str], right_lines: &[&str]) -> Vec<LineChange> {
-    // simple line-based diff using myers algorithm approximation
-    let mut changes = Vec::new();
-    let mut i = 0;
-    let mut j = 0;
-    
-    while i < left_lines.len() || j < right_lines.len() {
-        if i < left_lines.len() && j < right_lines.len() && left_lines[i] == right_lines[j] {
-            changes.push(LineChange::Equal(left_lines[i].to_string()));
-            i += 1;
-            j += 1;
-        } else if i < left_lines.len() && (j >= right_lines.len() || left_lines[i] != *right_lines.get(j).unwrap_or(&"")) {
-            changes.push(LineChange::Delete(left_lines[i].to_string()));
-            i += 1;
-        } else if j < right_lines.len() {
-            changes.push(LineChange::Insert(right_lines[j].to_string()));
-            j += 1;
-        }
-    }
-    
-    changes
+/// nearest left line before `hunk` that matches `pattern`
+fn hunk_anchor<'a>(left_lines: &[&'a str], hunk: &Hunk, pattern: &SectionPattern) -> Option<&'a str> {
+    // `old_start` is the line before the hunk when it covers no left lines
+    let before = if hunk.old_count > 0 { hunk.old_start - 1 } else { hunk.old_start };
+    left_lines[..before.min(left_lines.len())].iter()
+        .rev()
+        .find(|line| pattern.is_match(line))
+        .copied()
 }
 
 /// formats a single line with colors and options
-fn format_line(line: &HunkLine, enable_color: bool, options: &FormatOptions) -> String {
-    let prefix = match line.operation {
-        LineOperation::Context => " ",
-        LineOperation::Delete => "-",
-        LineOperation::Insert => "+",
-    };
+fn format_line(line: &HunkLine, line_number: usize, enable_color: bool, options: &FormatOptions) -> String {
+    let prefix = line.kind.prefix();
     
     let line_number = if options.show_line_numbers {
-        format!("{:4} ", line.line_number)
+        format!("{:4} ", line_number)
     } else {
         String::new()
     };
     
-    let content = if let Some(max_width) = options.max_line_width {
-        if line.content.len() > max_width {
-            format!("{}...", &line.content[..max_width.saturating_sub(3)])
-        } else {
-            line.content.clone()
-        }
-    } else {
-        line.content.clone()
+    let content = match options.max_line_width {
+        Some(max_width) => truncate_line(&line.content, max_width),
+        None => line.content.clone(),
     };
     
     let full_line = format!("{}{}{}", line_number, prefix, content);
     
     if enable_color {
-        match line.operation {
-            LineOperation::Context => full_line,
-            LineOperation::Delete => full_line.red().to_string(),
-            LineOperation::Insert => full_line.green().to_string(),
+        match line.kind {
+            LineKind::Context => full_line,
+            LineKind::Delete => Ansi(&full_line).red(),
+            LineKind::Insert => Ansi(&full_line).green(),
         }
     } else {
         full_line
     }
 }
 
-/// hunk builder for collecting lines
-#[derive(Debug)]
-struct HunkBuilder {
-    lines: Vec<HunkLine>,
-    old_start: usize,
-    new_start: usize,
-    last_line: usize,
-}
-
-impl HunkBuilder {
-    fn new() -> Self {
-        Self {
-            lines: Vec::new(),
-            old_start: 0,
-            new_start: 0,
-            last_line: 0,
-        }
-    }
-    
-    fn add_line(&mut self, change: LineChange, line_number: usize) {
-        if self.lines.is_empty() {
-            self.old_start = line_number + 1;
-            self.new_start = line_number + 1;
-        }
-        
-        let hunk_line = match change {
-            LineChange::Equal(content) => HunkLine {
-                operation: LineOperation::Context,
-                content,
-                line_number: line_number + 1,
-            },
-            LineChange::Delete(content) => HunkLine {
-                operation: LineOperation::Delete,
-                content,
-                line_number: line_number + 1,
-            },
-            LineChange::Insert(content) => HunkLine {
-                operation: LineOperation::Insert,
-                content,
-                line_number: line_number + 1,
-            },
-        };
-        
-        self.lines.push(hunk_line);
-        self.last_line = line_number;
-    }
-    
-    fn should_continue(&self, current_line: usize, context_lines: usize) -> bool {
-        current_line - self.last_line <= context_lines * 2
-    }
-    
-    fn build(self, _options: &FormatOptions) -> Hunk {
-        let old_count = self.lines.iter().filter(|l| matches!(l.operation, LineOperation::Context | LineOperation::Delete)).count();
-        let new_count = self.lines.iter().filter(|l| matches!(l.operation, LineOperation::Context | LineOperation::Insert)).count();
-        
-        Hunk {
-            old_start: self.old_start,
-            old_count,
-            new_start: self.new_start,
-            new_count,
-            lines: self.lines,
+/// shortens `content` to at most `max_width` characters, ending in `…` when cut
+/// 
+/// counts chars rather than bytes, so a cut never lands inside a multibyte
+/// character.
+fn truncate_line(content: &str, max_width: usize) -> String {
+    match content.char_indices().nth(max_width) {
+        None => content.to_string(),
+        Some(_) => {
+            let keep = max_width.saturating_sub(1);
+            let end = content.char_indices().nth(keep).map_or(content.len(), |(i, _)| i);
+            format!("{}…", &content[..end])
         }
     }
 }
-
-/// represents a hunk in unified diff format
-#[derive(Debug)]
-struct Hunk {
-    old_start: usize,
-    old_count: usize,
-    new_start: usize,
-    new_count: usize,
-    lines: Vec<HunkLine>,
-}
-
-impl Hunk {
-    fn format_header(&self) -> String {
-        format!("@@ -{},{} +{},{} @@", 
-                self.old_start, self.old_count,
-                self.new_start, self.new_count)
-    }
-}
-
-/// represents a line within a hunk
-#[derive(Debug, Clone)]
-struct HunkLine {
-    operation: LineOperation,
-    content: String,
-    line_number: usize,
-}
-
-/// line operation types
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum LineOperation {
-    Context,
-    Delete,
-    Insert,
-}
-
-/// line change types for diff computation
-#[derive(Debug, Clone)]
-enum LineChange {
-    Equal(String),
-    Delete(String),
-    Insert(String),
-}
\ No newline at end of file
@@ -0,0 +1,111 @@
+//! tokenization of inputs for coarser-than-byte diffs
+//!
+//! splits inputs into tokens that cover every byte exactly once, so the
+//! tokens concatenate back to the original input.
+
+use crate::types::DiffError;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// unit the diff is computed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Granularity {
+    /// individual bytes
+    #[default]
+    Byte,
+    /// unicode words; whitespace and punctuation between words are their own tokens
+    Word,
+    /// lines, each keeping its trailing `\n`
+    Line,
+    /// extended grapheme clusters, so a multi-byte character or a base with
+    /// its combining marks changes as one unit
+    Grapheme,
+}
+
+impl Granularity {
+    /// splits `input` into tokens for this granularity
+    ///
+    /// invalid utf-8 in word and grapheme mode becomes one token per
+    /// offending byte.
+    pub fn tokenize(self, input: &[u8]) -> Vec<&[u8]> {
+        match self {
+            Granularity::Byte => input.chunks(1).collect(),
+            Granularity::Line => input.split_inclusive(|&byte| byte == b'\n').collect(),
+            Granularity::Word => tokenize_utf8(input, |text| text.split_word_bounds().collect()),
+            Granularity::Grapheme => tokenize_utf8(input, |text| text.graphemes(true).collect()),
+        }
+    }
+}
+
+/// splits the valid utf-8 runs of `input` with `split`, and invalid bytes
+/// into single-byte tokens
+fn tokenize_utf8<'a>(input: &'a [u8], split: impl Fn(&'a str) -> Vec<&'a str>) -> Vec<&'a [u8]> {
+    let mut tokens = Vec::new();
+    for chunk in input.utf8_chunks() {
+        tokens.extend(split(chunk.valid()).into_iter().map(str::as_bytes));
+        tokens.extend(chunk.invalid().chunks(1));
+    }
+    tokens
+}
+
+/// caller-supplied split of an input into token byte ranges
+/// 
+/// for formats the built-in granularities don't know, such as csv fields.
+/// the ranges must cover the input in order: the first starts at 0, each
+/// starts where the previous one ended and the last ends at the input's
+/// length. empty ranges are allowed and become empty tokens.
+#[derive(Clone)]
+pub struct Tokenizer(Arc<SplitFn>);
+
+/// the function a `Tokenizer` wraps
+type SplitFn = dyn Fn(&[u8]) -> Vec<Range<usize>> + Send + Sync;
+
+impl Tokenizer {
+    /// wraps a function returning the token ranges of its input
+    pub fn new(split: impl Fn(&[u8]) -> Vec<Range<usize>> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(split))
+    }
+
+    /// splits `input` into tokens
+    /// 
+    /// fails with `DiffError::InvalidInput` if the ranges leave a gap,
+    /// overlap, run past the end or stop short of it.
+    pub fn tokenize<'a>(&self, input: &'a [u8]) -> Result<Vec<&'a [u8]>, DiffError> {
+        let ranges = (self.0)(input);
+        let mut end = 0;
+        for range in &ranges {
+            if range.start != end || range.end < range.start || range.end > input.len() {
+                return Err(DiffError::InvalidInput(format!(
+                    "tokenizer range {:?} doesn't continue from offset {} of a {} byte input",
+                    range, end, input.len()
+                )));
+            }
+            end = range.end;
+        }
+        if end != input.len() {
+            return Err(DiffError::InvalidInput(format!(
+                "tokenizer ranges stop at offset {} of a {} byte input", end, input.len()
+            )));
+        }
+        Ok(ranges.into_iter().map(|range| &input[range]).collect())
+    }
+}
+
+impl std::fmt::Debug for Tokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Tokenizer(..)")
+    }
+}
+
+impl std::fmt::Display for Granularity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Granularity::Byte => write!(f, "byte"),
+            Granularity::Word => write!(f, "word"),
+            Granularity::Line => write!(f, "line"),
+            Granularity::Grapheme => write!(f, "grapheme"),
+        }
+    }
+}
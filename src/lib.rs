@@ -4,6 +4,22 @@
 //! leaking information through execution time variations. critical for security
 //! applications where timing side-channels could reveal sensitive info.
 //!
+//! # Features
+//!
+//! the `std` feature (on by default) enables everything beyond the
+//! [`primitives`] module. with `default-features = false` the crate is
+//! `#![no_std]` and only exposes the constant-time primitives, which need
+//! nothing but slices and `subtle`.
+//!
+//! the `tui` feature adds the `tui` module and the `ctdiff tui` subcommand,
+//! an interactive scrollable viewer built on ratatui.
+//!
+//! the `wasm` feature adds the `wasm` module with a `wasm_bindgen` entry
+//! point for running diffs in the browser.
+//!
+//! the `capi` feature adds the `ffi` module, an `extern "C"` interface for
+//! calling ctdiff from c and c++.
+//!
 //! # Examples
 //!
 //! ## Basic Usage
@@ -13,7 +29,7 @@
 //!
 //! let diff = DiffBuilder::new()
 //!     .security_level(ctdiff::SecurityLevel::Balanced)
-//!     .build();
+//!     .build()?;
 //!
 //! let result = diff.compare(b"hello", b"world")?;
 //! println!("Edit distance: {}", result.edit_distance());
@@ -22,12 +38,12 @@
 //!
 //! ## Multiple Output Formats
 //!
-//! ```rust
+//! ```rust,no_run
 //! use ctdiff::{DiffBuilder, OutputFormat};
 //!
 //! let diff = DiffBuilder::new()
 //!     .output_format(OutputFormat::Json)
-//!     .build();
+//!     .build()?;
 //!
 //! let result = diff.compare_files("file1.txt", "file2.txt")?;
 //! let json_output = result.format()?;
@@ -35,31 +51,99 @@
 //! # Ok::<(), ctdiff::Error>(())
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
 // main public api exports
+#[cfg(feature = "std")]
 pub use crate::builder::DiffBuilder;
+#[cfg(feature = "std")]
 pub use crate::result::DiffResult;
+#[cfg(feature = "std")]
 pub use crate::error::{Error, Result};
+#[cfg(feature = "std")]
 pub use crate::security::{SecurityLevel, SecurityConfig};
-pub use crate::formats::OutputFormat;
+#[cfg(feature = "std")]
+pub use crate::formats::{ColorChoice, OutputFormat};
 
 // re-export core algorithm types for compatibility
-pub use crate::algorithm::{constant_time_diff, secure_diff, balanced_diff, ConstantTimeDiff};
-pub use crate::types::{DiffOperation, DiffError};
+#[cfg(feature = "std")]
+pub use crate::algorithm::{constant_time_diff, secure_diff, balanced_diff, ConstantTimeDiff, PreparedInput};
+#[cfg(feature = "std")]
+pub use crate::types::{AffixTrim, ChangeKind, ChangeRegion, DiffOperation, DiffError, LineDiffResult, LineOperation, PaddingStrategy, ScriptComparison, TokenDiffResult, TokenOperation};
+#[cfg(feature = "std")]
+pub use crate::granularity::{Granularity, Tokenizer};
+#[cfg(feature = "std")]
+pub use crate::moves::BlockMove;
+#[cfg(feature = "std")]
+pub use crate::patch::{apply_binary_delta, Patch};
+#[cfg(feature = "std")]
+pub use crate::formats::json::SCHEMA_VERSION as JSON_FORMAT_VERSION;
 
-// internal modules
+/// crate version, as in `Cargo.toml`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// whether this build's major version is `required_major`
+/// 
+/// for embedders checking at runtime that they're linked against the api
+/// they were written for. while the major version is 0, minor releases may
+/// also break the api, so compare `VERSION` directly if that matters.
+pub fn api_compatible(required_major: u32) -> bool {
+    env!("CARGO_PKG_VERSION_MAJOR").parse::<u32>() == Ok(required_major)
+}
+
+// no_std core
 pub mod primitives;
+
+// internal modules
+#[cfg(feature = "std")]
 pub mod types;
+#[cfg(feature = "std")]
 pub mod algorithm;
+#[cfg(feature = "std")]
+pub mod granularity;
+#[cfg(feature = "std")]
+pub mod moves;
+#[cfg(feature = "std")]
+pub mod normalize;
+#[cfg(feature = "std")]
+pub mod patch;
+#[cfg(feature = "std")]
+pub mod similarity;
+#[cfg(feature = "std")]
 pub mod builder;
+#[cfg(feature = "std")]
 pub mod result;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "std")]
 pub mod security;
+#[cfg(feature = "std")]
 pub mod formats;
 
+// interactive terminal viewer
+#[cfg(feature = "tui")]
+pub mod tui;
+
+// browser bindings
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// c interface
+#[cfg(feature = "capi")]
+pub mod ffi;
+
 // attack demonstration modules (for research/demo purposes only)
+#[cfg(feature = "std")]
 pub mod vulnerable;
+// timing needs a working `Instant`, which wasm32-unknown-unknown lacks
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 pub mod timing;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 pub mod attack;
 
+// inputs for the diff mode benchmarks
+#[cfg(all(feature = "bench", not(target_arch = "wasm32")))]
+pub mod bench;
+
 // convenience re-exports of common primitives
-pub use primitives::{ct_bytes_eq, ct_min, ct_max, ct_copy_if, ct_lookup, ct_memcmp};
\ No newline at end of file
+pub use primitives::{ct_bytes_eq, ct_min, ct_max, ct_min_slice, ct_argmin_slice, ct_copy_if, ct_lookup, ct_memcmp, ct_cmp};
\ No newline at end of file
@@ -3,10 +3,10 @@
 //! provides secure file comparison with timing attack resistance
 //! and familiar unix diff-style output formatting.
 
-use clap::{Parser, Subcommand, ValueEnum};
-use ctdiff::{ConstantTimeDiff, security::{SecurityConfig, SecurityLevel as NewSecurityLevel}};
-use ctdiff::{DiffBuilder, OutputFormat as NewOutputFormat};
-use ctdiff::attack::{AttackSimulator, AttackScenario};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use ctdiff::{ConstantTimeDiff, DiffError, security::{SecurityConfig, SecurityLevel as NewSecurityLevel}};
+use ctdiff::{ColorChoice, DiffBuilder, OutputFormat as NewOutputFormat};
+use ctdiff::attack::{self, AttackSimulator, AttackScenario};
 use std::fs;
 use std::path::PathBuf;
 use std::time::Instant;
@@ -14,13 +14,47 @@ use std::time::Instant;
 mod output;
 use output::{OutputFormat as LegacyOutputFormat, DiffFormatter};
 
+/// inputs are identical
+const EXIT_IDENTICAL: i32 = 0;
+/// inputs differ
+const EXIT_DIFFERENT: i32 = 1;
+/// usage, i/o or other errors
+const EXIT_ERROR: i32 = 2;
+/// an input exceeded a size or computation limit
+const EXIT_SECURITY_LIMIT: i32 = 3;
+
+/// refusal by a size or computation limit, reported with `EXIT_SECURITY_LIMIT`
+#[derive(Debug)]
+struct SecurityLimitExceeded(String);
+
+impl std::fmt::Display for SecurityLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SecurityLimitExceeded {}
+
+/// wraps a diff failure, keeping limit violations distinguishable for the exit code
+fn diff_error(error: DiffError) -> Box<dyn std::error::Error> {
+    let message = format!("diff failed: {}", error);
+    match error {
+        DiffError::InputTooLarge { .. } | DiffError::ComputationLimitExceeded(_) => {
+            Box::new(SecurityLimitExceeded(message))
+        }
+        _ => message.into(),
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "ctdiff")]
 #[command(about = "constant-time diff tool - secure file comparison resistant to timing attacks")]
 #[command(version = "0.1.0")]
 #[command(author = "Tanya Arora")]
-#[command(long_about = "ctdiff provides secure, constant-time file comparison resistant to timing attacks.
+#[command(long_about = "constant-time diff tool - secure file comparison resistant to timing attacks.
 Supports multiple output formats including unified diff, JSON, HTML, Git patches, and summaries.")]
+#[command(after_help = "Exit status: 0 if inputs are identical, 1 if they differ, 2 on usage or I/O errors, \
+3 if an input exceeds a security limit (size or computation; see --force).")]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
@@ -41,11 +75,11 @@ struct Cli {
     #[arg(long = "max-size")]
     max_size: Option<usize>,
     
-    /// output format
+    /// output format; json, html, git and summary use the library formats
     #[arg(short = 'f', long = "format", default_value = "unified")]
     format: LegacyOutputFormat,
     
-    /// new output format (json, html, git, summary)
+    /// library output format (json, html, git, summary); overrides --format
     #[arg(long = "new-format")]
     new_format: Option<NewOutputFormat>,
     
@@ -53,9 +87,17 @@ struct Cli {
     #[arg(short = 'o', long = "output")]
     output_file: Option<PathBuf>,
     
-    /// enable colored output
-    #[arg(short = 'c', long = "color")]
-    color: bool,
+    /// when to color output: always, never or auto (bare `--color` means always)
+    #[arg(
+        short = 'c',
+        long = "color",
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "never",
+        default_missing_value = "always"
+    )]
+    color: ColorChoice,
     
     /// show timing information (for security analysis)
     #[arg(long = "show-timing")]
@@ -69,9 +111,47 @@ struct Cli {
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
     
+    /// print a `git diff --stat` style summary instead of the diff; not
+    /// with a library format (json, html, git, summary)
+    #[arg(long = "stat", conflicts_with = "new_format")]
+    stat: bool,
+    
     /// force processing even if security warnings exist
     #[arg(long = "force")]
     force: bool,
+    
+    /// on an input too large for the security level, retry at the next
+    /// weaker level (maximum, then balanced, then fast) with a warning
+    #[arg(long = "degrade-on-limit")]
+    degrade_on_limit: bool,
+    
+    /// check that every file is identical to the first (exit 0 only if all match)
+    #[arg(long = "all-equal", value_name = "FILES", num_args = 2.., conflicts_with_all = ["file1", "file2"])]
+    all_equal: Option<Vec<PathBuf>>,
+    
+    /// print the json schema for `--new-format json` output and exit
+    #[arg(long = "json-schema")]
+    json_schema: bool,
+    
+    /// describe what the selected security level protects against and exit
+    #[arg(long = "explain-security")]
+    explain_security: bool,
+    
+    /// print `{"identical", "edit_distance", "exit_code"}` as json instead of the diff
+    #[arg(long = "exit-status-json")]
+    exit_status_json: bool,
+    
+    /// also write the diff as json to this file
+    #[arg(long = "also-json", value_name = "FILE")]
+    also_json: Option<PathBuf>,
+    
+    /// also write the diff as html to this file
+    #[arg(long = "also-html", value_name = "FILE")]
+    also_html: Option<PathBuf>,
+    
+    /// also write a summary of the diff to this file
+    #[arg(long = "also-summary", value_name = "FILE")]
+    also_summary: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -103,10 +183,57 @@ enum Commands {
         #[arg(long = "csv")]
         csv_output: bool,
         
+        /// write a standalone html report with timing histograms to file
+        #[arg(long = "html", value_name = "FILE")]
+        html_output: Option<PathBuf>,
+        
         /// security level for secure implementation testing
         #[arg(long = "security-level", default_value = "balanced")]
         security_level: SecurityLevel,
     },
+    
+    /// print edit distance and similarity of two files as json
+    Score {
+        /// first file to compare
+        #[arg(value_name = "FILE1")]
+        file1: PathBuf,
+        
+        /// second file to compare
+        #[arg(value_name = "FILE2")]
+        file2: PathBuf,
+        
+        /// security level for timing attack resistance
+        #[arg(short = 's', long = "security-level", default_value = "balanced")]
+        security_level: SecurityLevel,
+        
+        /// force processing even if security warnings exist
+        #[arg(long = "force")]
+        force: bool,
+    },
+    
+    /// browse a diff in an interactive terminal viewer
+    #[cfg(feature = "tui")]
+    Tui {
+        /// first file to compare
+        #[arg(value_name = "FILE1")]
+        file1: PathBuf,
+        
+        /// second file to compare
+        #[arg(value_name = "FILE2")]
+        file2: PathBuf,
+        
+        /// security level for timing attack resistance
+        #[arg(short = 's', long = "security-level", default_value = "balanced")]
+        security_level: SecurityLevel,
+        
+        /// context lines around each change
+        #[arg(short = 'u', long = "context", default_value = "3")]
+        context: usize,
+        
+        /// force processing even if security warnings exist
+        #[arg(long = "force")]
+        force: bool,
+    },
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -125,6 +252,8 @@ enum AttackScenarioArg {
     VersionControl,
     /// code review scenario
     CodeReview,
+    /// same-length vs different-length inputs
+    LengthLeakage,
 }
 
 impl AttackScenarioArg {
@@ -137,6 +266,7 @@ impl AttackScenarioArg {
             AttackScenarioArg::ChangeSize => Some(AttackScenario::ChangeSize),
             AttackScenarioArg::VersionControl => Some(AttackScenario::VersionControl),
             AttackScenarioArg::CodeReview => Some(AttackScenario::CodeReview),
+            AttackScenarioArg::LengthLeakage => Some(AttackScenario::LengthLeakage),
         }
     }
 }
@@ -152,6 +282,15 @@ enum SecurityLevel {
 }
 
 impl SecurityLevel {
+    /// the level `--degrade-on-limit` falls back to from this one
+    fn weaker(&self) -> Option<SecurityLevel> {
+        match self {
+            SecurityLevel::Maximum => Some(SecurityLevel::Balanced),
+            SecurityLevel::Balanced => Some(SecurityLevel::Fast),
+            SecurityLevel::Fast => None,
+        }
+    }
+    
     fn to_config(&self, max_size: Option<usize>) -> SecurityConfig {
         let new_level = match self {
             SecurityLevel::Maximum => NewSecurityLevel::Maximum,
@@ -165,6 +304,17 @@ impl SecurityLevel {
 fn main() {
     let cli = Cli::parse();
     
+    // clap can't express a conflict with only some values of --format
+    if cli.stat && cli.format.library_format().is_some() {
+        let format = cli.format.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!("the argument '--stat' cannot be used with '--format {}'", format),
+            )
+            .exit();
+    }
+    
     let result = match &cli.command {
         Some(Commands::AttackDemo { 
             scenario, 
@@ -173,18 +323,32 @@ fn main() {
             file2, 
             output_file, 
             csv_output, 
+            html_output,
             security_level 
         }) => {
             run_attack_demo(scenario, *iterations, file1.as_ref(), file2.as_ref(), 
-                          output_file.as_ref(), *csv_output, security_level)
+                          output_file.as_ref(), *csv_output, html_output.as_ref(), security_level)
+        }
+        Some(Commands::Score { file1, file2, security_level, force }) => {
+            run_score(file1, file2, security_level, *force)
+        }
+        #[cfg(feature = "tui")]
+        Some(Commands::Tui { file1, file2, security_level, context, force }) => {
+            run_tui(file1, file2, security_level, *context, *force)
         }
         None => {
             // backwards compatibility - run diff if files provided
-            if let (Some(file1), Some(file2)) = (&cli.file1, &cli.file2) {
+            if cli.json_schema {
+                print_json_schema()
+            } else if cli.explain_security {
+                print_security_explanation(&cli)
+            } else if let Some(files) = &cli.all_equal {
+                run_all_equal(&cli, files)
+            } else if let (Some(file1), Some(file2)) = (&cli.file1, &cli.file2) {
                 run_diff(&cli, file1, file2)
             } else {
                 eprintln!("ctdiff: missing file arguments. Use --help for usage information.");
-                std::process::exit(2);
+                std::process::exit(EXIT_ERROR);
             }
         }
     };
@@ -193,23 +357,49 @@ fn main() {
         Ok(exit_code) => std::process::exit(exit_code),
         Err(e) => {
             eprintln!("ctdiff: {}", e);
-            std::process::exit(2);
+            let code = if e.is::<SecurityLimitExceeded>() { EXIT_SECURITY_LIMIT } else { EXIT_ERROR };
+            std::process::exit(code);
         }
     }
 }
 
+fn print_json_schema() -> Result<i32, Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(&ctdiff::formats::json::schema())?);
+    Ok(0)
+}
+
+fn print_security_explanation(cli: &Cli) -> Result<i32, Box<dyn std::error::Error>> {
+    println!("{}", cli.security_level.to_config(cli.max_size).explain());
+    Ok(0)
+}
+
 fn run_diff(cli: &Cli, file1: &PathBuf, file2: &PathBuf) -> Result<i32, Box<dyn std::error::Error>> {
-    // read input files
-    let file1_data = read_file(file1)?;
-    let file2_data = read_file(file2)?;
+    // read input files, up to the weakest level's limit when degrading
+    let read_level = if cli.degrade_on_limit { SecurityLevel::Fast } else { cli.security_level.clone() };
+    let limit = (!cli.force).then(|| read_level.to_config(cli.max_size).max_input_size);
+    let file1_data = read_input(file1, limit)?;
+    let file2_data = read_input(file2, limit)?;
     
-    // check for security warnings
+    // check for security warnings, stepping down a level at a time with --degrade-on-limit
+    let mut security_level = cli.security_level.clone();
     if !cli.force {
-        check_security_warnings(&file1_data, &file2_data, &cli.security_level)?;
+        while let Err(error) = check_security_warnings(&file1_data, &file2_data, &security_level) {
+            match security_level.weaker().filter(|_| cli.degrade_on_limit) {
+                Some(weaker) => {
+                    eprintln!(
+                        "warning: input too large for {:?} security, retrying at {:?}; \
+                         timing attack resistance is reduced",
+                        security_level, weaker
+                    );
+                    security_level = weaker;
+                }
+                None => return Err(error),
+            }
+        }
     }
     
     // configure diff algorithm  
-    let mut config = cli.security_level.to_config(cli.max_size);
+    let mut config = security_level.to_config(cli.max_size);
     
     // if force flag is used, increase limits to accommodate larger files
     if cli.force {
@@ -221,24 +411,41 @@ fn run_diff(cli: &Cli, file1: &PathBuf, file2: &PathBuf) -> Result<i32, Box<dyn
     
     // perform diff with timing measurement
     let start_time = Instant::now();
-    let result = differ.diff(&file1_data, &file2_data).map_err(|e| format!("diff failed: {}", e))?;
+    let result = differ.diff(&file1_data, &file2_data).map_err(diff_error)?;
     let elapsed = start_time.elapsed();
     
     // check if files are identical
     let files_identical = result.edit_distance == 0;
+    let exit_code = if files_identical { EXIT_IDENTICAL } else { EXIT_DIFFERENT };
+    
+    write_extra_formats(cli, file1, file2, &file1_data, &file2_data, &result)?;
+    
+    if cli.exit_status_json {
+        let status = serde_json::json!({
+            "identical": files_identical,
+            "edit_distance": result.edit_distance,
+            "exit_code": exit_code,
+        });
+        println!("{}", status);
+        return Ok(exit_code);
+    }
     
     if !cli.quiet {
-        let output = if let Some(new_format) = &cli.new_format {
+        let library_format = cli.new_format.clone().or_else(|| cli.format.library_format());
+        let output = if cli.stat {
+            let formatter = DiffFormatter::new(cli.format.clone(), cli.color.should_color(), cli.context);
+            formatter.format_stat(&file2.display().to_string(), &result)
+        } else if let Some(new_format) = library_format {
             // use new library API
             let diff_builder = DiffBuilder::new()
-                .security_level(match cli.security_level {
+                .security_level(match security_level {
                     SecurityLevel::Maximum => NewSecurityLevel::Maximum,
                     SecurityLevel::Balanced => NewSecurityLevel::Balanced,
                     SecurityLevel::Fast => NewSecurityLevel::Fast,
                 })
-                .output_format(new_format.clone())
+                .output_format(new_format)
                 .context_lines(cli.context)
-                .color(cli.color)
+                .color_choice(cli.color)
                 .build()
                 .map_err(|e| format!("diff builder error: {}", e))?;
             
@@ -254,7 +461,7 @@ fn run_diff(cli: &Cli, file1: &PathBuf, file2: &PathBuf) -> Result<i32, Box<dyn
                 .map_err(|e| format!("format error: {}", e))?
         } else {
             // use legacy formatter
-            let formatter = DiffFormatter::new(cli.format.clone(), cli.color, cli.context);
+            let formatter = DiffFormatter::new(cli.format.clone(), cli.color.should_color(), cli.context);
             formatter.format_diff(
                 &file1.display().to_string(),
                 &file2.display().to_string(),
@@ -276,7 +483,7 @@ fn run_diff(cli: &Cli, file1: &PathBuf, file2: &PathBuf) -> Result<i32, Box<dyn
         if cli.show_timing {
             eprintln!("\ntiming: {:?} (constant-time guarantee: {})", 
                 elapsed, 
-                match cli.security_level {
+                match security_level {
                     SecurityLevel::Maximum => "strong",
                     SecurityLevel::Balanced => "moderate", 
                     SecurityLevel::Fast => "basic",
@@ -285,13 +492,223 @@ fn run_diff(cli: &Cli, file1: &PathBuf, file2: &PathBuf) -> Result<i32, Box<dyn
     }
     
     // return appropriate exit code
-    Ok(if files_identical { 0 } else { 1 })
+    Ok(exit_code)
+}
+
+/// writes the `--also-*` files from the diff already computed by `run_diff`
+fn write_extra_formats(
+    cli: &Cli,
+    file1: &std::path::Path,
+    file2: &std::path::Path,
+    file1_data: &[u8],
+    file2_data: &[u8],
+    result: &ctdiff::types::DiffResult,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let extras = [
+        (&cli.also_json, NewOutputFormat::Json),
+        (&cli.also_html, NewOutputFormat::Html),
+        (&cli.also_summary, NewOutputFormat::Summary),
+    ];
+    if extras.iter().all(|(path, _)| path.is_none()) {
+        return Ok(());
+    }
+    
+    let rich = ctdiff::DiffResult::new_with_names(
+        result.clone(),
+        file1_data.to_vec(),
+        file2_data.to_vec(),
+        file1.display().to_string(),
+        file2.display().to_string(),
+        NewOutputFormat::Unified,
+        ctdiff::formats::FormatOptions::default(),
+        cli.context,
+        ColorChoice::Never,
+    );
+    
+    for (path, format) in extras {
+        if let Some(path) = path {
+            let output = rich.format_as(format)
+                .map_err(|e| format!("format error: {}", e))?;
+            fs::write(path, output)
+                .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        }
+    }
+    
+    Ok(())
+}
+
+fn run_score(
+    file1: &PathBuf,
+    file2: &PathBuf,
+    security_level: &SecurityLevel,
+    force: bool,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let limit = (!force).then(|| security_level.to_config(None).max_input_size);
+    let file1_data = read_input(file1, limit)?;
+    let file2_data = read_input(file2, limit)?;
+    
+    if !force {
+        check_security_warnings(&file1_data, &file2_data, security_level)?;
+    }
+    
+    let mut config = security_level.to_config(None);
+    if force {
+        config.max_input_size = file1_data.len().max(file2_data.len()).max(config.max_input_size);
+        config.max_edit_distance = None;
+    }
+    
+    let report = DiffBuilder::new()
+        .security_config(config)
+        .build()
+        .map_err(|e| format!("diff builder error: {}", e))?
+        .score(&file1_data, &file2_data)
+        .map_err(|e| -> Box<dyn std::error::Error> {
+            let message = format!("diff failed: {}", e);
+            match e {
+                ctdiff::Error::InputTooLarge { .. } | ctdiff::Error::ResourceLimit { .. } => {
+                    Box::new(SecurityLimitExceeded(message))
+                }
+                _ => message.into(),
+            }
+        })?;
+    
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(0)
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(
+    file1: &PathBuf,
+    file2: &PathBuf,
+    security_level: &SecurityLevel,
+    context: usize,
+    force: bool,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    use ctdiff::{formats::FormatOptions, result::DiffResult, tui};
+    
+    let limit = (!force).then(|| security_level.to_config(None).max_input_size);
+    let file1_data = read_input(file1, limit)?;
+    let file2_data = read_input(file2, limit)?;
+    
+    if !force {
+        check_security_warnings(&file1_data, &file2_data, security_level)?;
+    }
+    
+    let mut config = security_level.to_config(None);
+    if force {
+        config.max_input_size = file1_data.len().max(file2_data.len()).max(config.max_input_size);
+        config.max_edit_distance = None;
+    }
+    
+    let differ = ConstantTimeDiff::new(config.to_legacy());
+    let result = differ.diff(&file1_data, &file2_data).map_err(diff_error)?;
+    let files_identical = result.edit_distance == 0;
+    
+    let result = DiffResult::new_with_names(
+        result,
+        file1_data,
+        file2_data,
+        file1.display().to_string(),
+        file2.display().to_string(),
+        NewOutputFormat::Unified,
+        FormatOptions::default(),
+        context,
+        false,
+    );
+    
+    tui::run(tui::DiffView::new(&result)?)?;
+    
+    Ok(if files_identical { EXIT_IDENTICAL } else { EXIT_DIFFERENT })
+}
+
+fn run_all_equal(cli: &Cli, files: &[PathBuf]) -> Result<i32, Box<dyn std::error::Error>> {
+    let limit = (!cli.force).then(|| cli.security_level.to_config(cli.max_size).max_input_size);
+    let contents = files.iter()
+        .map(|path| read_input(path, limit))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (reference, others) = contents.split_first()
+        .ok_or("--all-equal requires at least two files")?;
+    
+    // check for security warnings
+    if !cli.force {
+        for other in others {
+            check_security_warnings(reference, other, &cli.security_level)?;
+        }
+    }
+    
+    let mut config = cli.security_level.to_config(cli.max_size);
+    if cli.force {
+        let largest = contents.iter().map(Vec::len).max().unwrap_or(0);
+        config.max_input_size = largest.max(config.max_input_size);
+        config.max_edit_distance = None;
+    }
+    
+    let differ = ConstantTimeDiff::new(config.to_legacy());
+    
+    // diff every replica against the first without stopping early, so the
+    // total time doesn't reveal which replica diverged
+    let mut differing = Vec::new();
+    for (index, other) in others.iter().enumerate() {
+        let result = differ.diff(reference, other).map_err(diff_error)?;
+        if result.edit_distance != 0 {
+            differing.push(index + 1);
+        }
+    }
+    
+    if !cli.quiet {
+        for index in &differing {
+            eprintln!("ctdiff: file {} ({}) differs from file 0 ({})",
+                index, files[*index].display(), files[0].display());
+        }
+    }
+    
+    Ok(if differing.is_empty() { EXIT_IDENTICAL } else { EXIT_DIFFERENT })
 }
 
 fn read_file(path: &PathBuf) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e).into())
 }
 
+/// reads a file to diff, decompressing it first if it's gzip (`gzip` feature)
+/// 
+/// gzip is detected by its magic bytes, whatever the extension. `limit`
+/// caps the decompressed size so a small archive can't expand past the
+/// maximum input size; `None` (under `--force`) means no cap.
+fn read_input(path: &PathBuf, limit: Option<usize>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let data = read_file(path)?;
+    
+    #[cfg(feature = "gzip")]
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return decompress_gzip(path, &data, limit);
+    }
+    #[cfg(not(feature = "gzip"))]
+    let _ = limit;
+    
+    Ok(data)
+}
+
+#[cfg(feature = "gzip")]
+fn decompress_gzip(path: &std::path::Path, data: &[u8], limit: Option<usize>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    use std::io::Read;
+    
+    // read one byte past the limit to tell "exactly at" from "over"
+    let cap = limit.map_or(u64::MAX, |limit| limit as u64 + 1);
+    let mut decompressed = Vec::new();
+    flate2::read::MultiGzDecoder::new(data)
+        .take(cap)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| format!("failed to decompress {}: {}", path.display(), e))?;
+    
+    match limit {
+        Some(limit) if decompressed.len() > limit => Err(Box::new(SecurityLimitExceeded(format!(
+            "decompressed size of {} exceeds limit {} bytes. use --force to continue.",
+            path.display(),
+            limit
+        )))),
+        _ => Ok(decompressed),
+    }
+}
+
 fn check_security_warnings(file1: &[u8], file2: &[u8], security_level: &SecurityLevel) -> Result<(), Box<dyn std::error::Error>> {
     let max_size = match security_level {
         SecurityLevel::Maximum => 4 * 1024,
@@ -307,12 +724,13 @@ fn check_security_warnings(file1: &[u8], file2: &[u8], security_level: &Security
             max_size,
             security_level
         );
-        return Err(warning.into());
+        return Err(Box::new(SecurityLimitExceeded(warning)));
     }
     
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_attack_demo(
     scenario: &AttackScenarioArg,
     iterations: usize,
@@ -320,6 +738,7 @@ fn run_attack_demo(
     file2: Option<&PathBuf>,
     output_file: Option<&PathBuf>,
     csv_output: bool,
+    html_output: Option<&PathBuf>,
     security_level: &SecurityLevel,
 ) -> Result<i32, Box<dyn std::error::Error>> {
     println!("🚨 TIMING ATTACK DEMONSTRATION");
@@ -330,16 +749,17 @@ fn run_attack_demo(
     let config = security_level.to_config(None);
     let mut simulator = AttackSimulator::with_security_config(config.clone());
     
-    let report = match scenario {
+    let (report, results) = match scenario {
         AttackScenarioArg::Comprehensive => {
             println!("Running comprehensive timing attack analysis...");
-            simulator.generate_summary_report(iterations)
+            let results = simulator.run_comprehensive_demo(iterations);
+            (attack::summary_report(&results), results)
         }
         _ => {
             if let Some(attack_scenario) = scenario.to_attack_scenario() {
                 println!("Running {} scenario...", attack_scenario.description());
                 let results = simulator.simulate_attack(attack_scenario, iterations);
-                results.format_report()
+                (results.format_report(), vec![results])
             } else {
                 return Err("Invalid attack scenario".into());
             }
@@ -354,6 +774,12 @@ fn run_attack_demo(
         println!("Detailed report saved to: {}", output_path.display());
     }
     
+    // handle html export
+    if let Some(html_path) = html_output {
+        std::fs::write(html_path, attack::html_report(&results))?;
+        println!("HTML report saved to: {}", html_path.display());
+    }
+    
     // handle CSV export
     if csv_output {
         let measurements = simulator.get_measurements();
@@ -0,0 +1,140 @@
+//! block move detection over finished edit scripts
+//!
+//! a relocated block shows up in a byte diff as removed bytes in one changed
+//! region and the same bytes added in another. this pass pairs those up
+//! after the fact; it never changes the script and, like any walk over the
+//! finished result, its cost depends on content.
+
+use crate::types::{DiffOperation, DiffResult};
+use serde::{Deserialize, Serialize};
+
+/// shortest run of bytes reported as a move
+pub const MIN_MOVE_LEN: usize = 4;
+
+/// a block of bytes removed at one place and added back at another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockMove {
+    /// offset of the block in the left input
+    pub from_offset: usize,
+    /// offset of the block in the right input
+    pub to_offset: usize,
+    /// length of the block in bytes
+    pub len: usize,
+}
+
+/// a run of modifications, as the ranges it covers on each side
+struct ChangedRegion {
+    removed: std::ops::Range<usize>,
+    added: std::ops::Range<usize>,
+}
+
+/// finds blocks that were removed in one changed region and added in another
+///
+/// each removed region is matched against the added parts of the other
+/// regions by longest common substring; matches shorter than `MIN_MOVE_LEN`
+/// are ignored and each added byte is claimed by at most one move.
+pub fn detect_moves(left_data: &[u8], right_data: &[u8], result: &DiffResult) -> Vec<BlockMove> {
+    let regions = changed_regions(&result.operations);
+    let mut claimed = vec![false; right_data.len()];
+    let mut moves = Vec::new();
+
+    for (i, source) in regions.iter().enumerate() {
+        let removed = &left_data[source.removed.clone()];
+        let mut best: Option<BlockMove> = None;
+
+        for (j, target) in regions.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let found = longest_unclaimed_match(removed, right_data, target.added.clone(), &claimed);
+            if let Some((from, to, len)) = found {
+                if best.is_none_or(|b| len > b.len) {
+                    best = Some(BlockMove {
+                        from_offset: source.removed.start + from,
+                        to_offset: to,
+                        len,
+                    });
+                }
+            }
+        }
+
+        if let Some(block) = best.filter(|b| b.len >= MIN_MOVE_LEN) {
+            claimed[block.to_offset..block.to_offset + block.len].fill(true);
+            moves.push(block);
+        }
+    }
+
+    moves
+}
+
+/// splits a script into its changed regions
+///
+/// keep runs shorter than `MIN_MOVE_LEN` don't end a region, so a moved
+/// block that happens to line up with a stray byte or two stays in one piece.
+fn changed_regions(operations: &[DiffOperation]) -> Vec<ChangedRegion> {
+    let mut regions = Vec::new();
+    let mut pos_a = 0;
+    let mut pos_b = 0;
+    let mut current: Option<ChangedRegion> = None;
+    let mut gap = 0;
+
+    for op in operations {
+        let (consumed, produced) = match op {
+            DiffOperation::Keep => (1, 1),
+            DiffOperation::KeepRun(n) => (*n as usize, *n as usize),
+            DiffOperation::Insert(_) => (0, 1),
+            DiffOperation::Delete => (1, 0),
+            DiffOperation::Substitute(_) => (1, 1),
+        };
+        pos_a += consumed;
+        pos_b += produced;
+
+        if op.is_modification() {
+            let region = current.get_or_insert(ChangedRegion {
+                removed: pos_a - consumed..pos_a - consumed,
+                added: pos_b - produced..pos_b - produced,
+            });
+            region.removed.end = pos_a;
+            region.added.end = pos_b;
+            gap = 0;
+        } else if current.is_some() {
+            gap += consumed;
+            if gap >= MIN_MOVE_LEN {
+                regions.extend(current.take());
+            }
+        }
+    }
+    regions.extend(current);
+
+    regions
+}
+
+/// longest substring of `needle` found in `haystack[range]` without touching
+/// claimed bytes, as (offset in needle, offset in haystack, length)
+fn longest_unclaimed_match(
+    needle: &[u8],
+    haystack: &[u8],
+    range: std::ops::Range<usize>,
+    claimed: &[bool],
+) -> Option<(usize, usize, usize)> {
+    let mut best: Option<(usize, usize, usize)> = None;
+    let mut previous = vec![0usize; range.len() + 1];
+    let mut row = vec![0usize; range.len() + 1];
+
+    for (i, &byte) in needle.iter().enumerate() {
+        for (k, pos) in range.clone().enumerate() {
+            row[k + 1] = if byte == haystack[pos] && !claimed[pos] {
+                previous[k] + 1
+            } else {
+                0
+            };
+            let len = row[k + 1];
+            if len > 0 && best.is_none_or(|(_, _, best_len)| len > best_len) {
+                best = Some((i + 1 - len, pos + 1 - len, len));
+            }
+        }
+        std::mem::swap(&mut previous, &mut row);
+    }
+
+    best
+}
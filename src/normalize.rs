@@ -0,0 +1,230 @@
+//! whitespace, line-ending and case normalization applied before diffing
+//!
+//! rewrites both inputs the same way so that layout-only changes (tabs vs
+//! spaces, trailing or interior whitespace, CRLF vs LF) and, optionally,
+//! case changes don't show up as edits. the diff then describes the
+//! normalized bytes, not the originals.
+//!
+//! normalization runs before the constant-time comparison and its cost
+//! depends on how much whitespace the inputs contain.
+
+use std::borrow::Cow;
+
+/// how inputs are rewritten before diffing; the default changes nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Normalization {
+    /// replace each tab with spaces up to the next multiple of this width
+    pub expand_tabs: Option<usize>,
+    /// drop spaces and tabs at the end of every line
+    pub ignore_trailing_whitespace: bool,
+    /// drop all whitespace except newlines
+    pub ignore_all_whitespace: bool,
+    /// convert `\r\n` and lone `\r` line endings to `\n`
+    pub normalize_newlines: bool,
+    /// lowercase both inputs, ASCII letters only unless `unicode_case` is set
+    pub ignore_case: bool,
+    /// with `ignore_case`, lowercase non-ASCII characters as well
+    pub unicode_case: bool,
+}
+
+impl Normalization {
+    /// true if `apply` returns its input unchanged
+    pub fn is_identity(&self) -> bool {
+        self.is_layout_identity() && !self.ignore_case
+    }
+
+    /// true if `apply_layout` returns its input unchanged
+    fn is_layout_identity(&self) -> bool {
+        self.expand_tabs.is_none() && !self.ignore_trailing_whitespace && !self.ignore_all_whitespace
+            && !self.normalize_newlines
+    }
+
+    /// rewrites `input`, borrowing it when no normalization is configured
+    ///
+    /// line endings are unified first, then tabs are expanded, then
+    /// whitespace is removed, then case is folded. line endings (`\n`, and
+    /// the `\r` of `\r\n` unless newlines are normalized or all whitespace
+    /// is ignored) are kept.
+    pub fn apply<'a>(&self, input: &'a [u8]) -> Cow<'a, [u8]> {
+        let layout = self.apply_layout(input);
+        if self.ignore_case {
+            Cow::Owned(self.fold_case(&layout))
+        } else {
+            layout
+        }
+    }
+
+    /// `apply` without the case folding
+    pub(crate) fn apply_layout<'a>(&self, input: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.is_layout_identity() {
+            return Cow::Borrowed(input);
+        }
+
+        let unified;
+        let input = if self.normalize_newlines {
+            unified = unify_newlines(input);
+            &unified[..]
+        } else {
+            input
+        };
+
+        let mut output = Vec::with_capacity(input.len());
+        for line in input.split_inclusive(|&byte| byte == b'\n') {
+            let (body, ending) = split_line_ending(line);
+            let start = output.len();
+
+            match self.expand_tabs {
+                Some(width) => expand_tabs(body, width, &mut output),
+                None => output.extend_from_slice(body),
+            }
+            if self.ignore_all_whitespace {
+                let mut kept = start;
+                for i in start..output.len() {
+                    if !is_blank(output[i]) {
+                        output[kept] = output[i];
+                        kept += 1;
+                    }
+                }
+                output.truncate(kept);
+            } else if self.ignore_trailing_whitespace {
+                while output.len() > start && is_blank(output[output.len() - 1]) {
+                    output.pop();
+                }
+            }
+
+            if self.ignore_all_whitespace {
+                output.extend(ending.iter().filter(|&&byte| byte == b'\n'));
+            } else {
+                output.extend_from_slice(ending);
+            }
+        }
+
+        Cow::Owned(output)
+    }
+
+    /// `apply` for an owned buffer, reusing it when nothing changes
+    pub fn apply_owned(&self, input: Vec<u8>) -> Vec<u8> {
+        if self.is_identity() {
+            input
+        } else {
+            self.apply(&input).into_owned()
+        }
+    }
+
+    /// lowercases `input` as `ignore_case` does, whether or not it's set
+    ///
+    /// the output always has the same length as the input, byte for byte:
+    /// with `unicode_case`, a character whose lowercase form encodes to a
+    /// different number of bytes (or to several characters, like `İ`) is
+    /// left as it is, and so is invalid UTF-8.
+    pub(crate) fn fold_case(&self, input: &[u8]) -> Vec<u8> {
+        if !self.unicode_case {
+            return input.to_ascii_lowercase();
+        }
+
+        let mut output = Vec::with_capacity(input.len());
+        for chunk in input.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                let mut lower = c.to_lowercase();
+                match (lower.next(), lower.next()) {
+                    (Some(l), None) if l.len_utf8() == c.len_utf8() => {
+                        output.extend_from_slice(l.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                    _ => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                }
+            }
+            output.extend_from_slice(chunk.invalid());
+        }
+        output
+    }
+}
+
+/// counts lines that differ only in their line endings
+///
+/// lines are paired by position, so the count is exact when the inputs
+/// have the same lines and approximate once lines are added or removed.
+pub fn line_ending_only_changes(left: &[u8], right: &[u8]) -> usize {
+    split_lines(left).into_iter()
+        .zip(split_lines(right))
+        .filter(|(l, r)| l != r && strip_line_ending(l) == strip_line_ending(r))
+        .count()
+}
+
+/// splits after every `\n`, `\r\n` or lone `\r`
+fn split_lines(input: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < input.len() {
+        let end = match input[i] {
+            b'\r' if input.get(i + 1) == Some(&b'\n') => i + 2,
+            b'\r' | b'\n' => i + 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        lines.push(&input[start..end]);
+        start = end;
+        i = end;
+    }
+    if start < input.len() {
+        lines.push(&input[start..]);
+    }
+    lines
+}
+
+/// replaces `\r\n` and lone `\r` with `\n`
+fn unify_newlines(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut bytes = input.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'\r' {
+            bytes.next_if_eq(&&b'\n');
+            output.push(b'\n');
+        } else {
+            output.push(byte);
+        }
+    }
+    output
+}
+
+/// a line without its `\n`, `\r\n` or `\r` ending
+fn strip_line_ending(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\n")
+        .map(|body| body.strip_suffix(b"\r").unwrap_or(body))
+        .or_else(|| line.strip_suffix(b"\r"))
+        .unwrap_or(line)
+}
+
+/// splits a line into its content and its `\n` or `\r\n` ending
+fn split_line_ending(line: &[u8]) -> (&[u8], &[u8]) {
+    let ending_len = match line {
+        [.., b'\r', b'\n'] => 2,
+        [.., b'\n'] => 1,
+        _ => 0,
+    };
+    line.split_at(line.len() - ending_len)
+}
+
+/// appends `line` with tabs expanded to the next multiple of `width` columns
+///
+/// a width of 0 removes tabs.
+fn expand_tabs(line: &[u8], width: usize, output: &mut Vec<u8>) {
+    let mut column = 0;
+    for &byte in line {
+        if byte == b'\t' {
+            let spaces = if width == 0 { 0 } else { width - column % width };
+            output.extend(std::iter::repeat_n(b' ', spaces));
+            column += spaces;
+        } else {
+            output.push(byte);
+            column += 1;
+        }
+    }
+}
+
+/// whitespace other than a newline
+fn is_blank(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | 0x0b | 0x0c)
+}
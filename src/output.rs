@@ -1,11 +1,12 @@
 //! output formatting for diff results
 //! 
 //! implements various output formats including unified diff compatible
-//! with standard unix diff tools and security-focused formatting.
+//! with standard unix diff tools and security-focused formatting. the
+//! side-by-side and operations views are shared with the library `formats`.
 
 use ctdiff::types::{DiffOperation, DiffResult};
+use ctdiff::formats::{self, Ansi, FormatOptions};
 use clap::ValueEnum;
-use colored::Colorize;
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum OutputFormat {
@@ -17,6 +18,36 @@ pub enum OutputFormat {
     Operations,
     /// security-focused minimal output
     Minimal,
+    /// structured json (library format)
+    Json,
+    /// standalone html page (library format)
+    Html,
+    /// git-style patch (library format)
+    Git,
+    /// statistics summary (library format)
+    Summary,
+}
+
+impl OutputFormat {
+    /// the library format this selects, for those rendered by `DiffBuilder`
+    /// rather than `DiffFormatter`
+    pub fn library_format(&self) -> Option<formats::OutputFormat> {
+        match self {
+            OutputFormat::Json => Some(formats::OutputFormat::Json),
+            OutputFormat::Html => Some(formats::OutputFormat::Html),
+            OutputFormat::Git => Some(formats::OutputFormat::Git),
+            OutputFormat::Summary => Some(formats::OutputFormat::Summary),
+            OutputFormat::Unified | OutputFormat::SideBySide | OutputFormat::Operations | OutputFormat::Minimal => None,
+        }
+    }
+}
+
+/// options for formats shared with the library, matching the cli's plain layout
+fn shared_format_options() -> FormatOptions {
+    FormatOptions {
+        show_line_numbers: false,
+        ..FormatOptions::default()
+    }
 }
 
 pub struct DiffFormatter {
@@ -43,10 +74,17 @@ impl DiffFormatter {
         result: &DiffResult,
     ) -> Result<String, Box<dyn std::error::Error>> {
         match self.format {
-            OutputFormat::Unified => self.format_unified(file1_name, file2_name, file1_data, result),
-            OutputFormat::SideBySide => self.format_side_by_side(file1_name, file2_name, file1_data, file2_data, result),
-            OutputFormat::Operations => self.format_operations(result),
+            OutputFormat::Unified => self.format_unified(file1_name, file2_name, file1_data, file2_data, result),
+            OutputFormat::SideBySide => Ok(formats::side_by_side::format(
+                file1_name, file2_name, file1_data, file2_data, result, self.use_color, &shared_format_options(),
+            )?),
+            OutputFormat::Operations => Ok(formats::operations::format(
+                file1_name, file2_name, file1_data, file2_data, result, self.use_color, &shared_format_options(),
+            )?),
             OutputFormat::Minimal => self.format_minimal(result),
+            OutputFormat::Json | OutputFormat::Html | OutputFormat::Git | OutputFormat::Summary => {
+                Err(format!("{:?} output is rendered by the library, not DiffFormatter", self.format).into())
+            }
         }
     }
     
@@ -55,8 +93,14 @@ impl DiffFormatter {
         file1_name: &str,
         file2_name: &str,
         file1_data: &[u8],
+        file2_data: &[u8],
         result: &DiffResult,
     ) -> Result<String, Box<dyn std::error::Error>> {
+        // same notice as the library's unified format
+        if result.edit_distance != 0 && formats::contains_binary(file1_data, file2_data) {
+            return Ok(format!("Binary files {} and {} differ\n", file1_name, file2_name));
+        }
+        
         let mut output = String::new();
         
         // header
@@ -78,8 +122,8 @@ impl DiffFormatter {
                 let formatted_line = if self.use_color {
                     match line.operation {
                         LineOperation::Context => format!(" {}", line.content),
-                        LineOperation::Delete => format!("-{}", line.content).red().to_string(),
-                        LineOperation::Insert => format!("+{}", line.content).green().to_string(),
+                        LineOperation::Delete => Ansi(&format!("-{}", line.content)).red(),
+                        LineOperation::Insert => Ansi(&format!("+{}", line.content)).green(),
                     }
                 } else {
                     match line.operation {
@@ -96,85 +140,6 @@ impl DiffFormatter {
         Ok(output)
     }
     
-    fn format_side_by_side(
-        &self,
-        file1_name: &str,
-        file2_name: &str,
-        file1_data: &[u8],
-        _file2_data: &[u8],
-        result: &DiffResult,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let mut output = String::new();
-        
-        // header
-        let header = format!("{:40} | {}", file1_name, file2_name);
-        output.push_str(&header);
-        output.push('\n');
-        output.push_str(&"-".repeat(header.len()));
-        output.push('\n');
-        
-        // reconstruct both sequences line by line
-        let file1_lines = self.bytes_to_display_lines(file1_data);
-        let file2_lines = self.bytes_to_display_lines(&result.apply_to(file1_data)?);
-        
-        let max_lines = file1_lines.len().max(file2_lines.len());
-        
-        for i in 0..max_lines {
-            let empty_string = String::new();
-            let left = file1_lines.get(i).unwrap_or(&empty_string);
-            let right = file2_lines.get(i).unwrap_or(&empty_string);
-            
-            let line_same = left == right;
-            let left_display = format!("{:40}", left.chars().take(40).collect::<String>());
-            
-            let formatted_line = if self.use_color && !line_same {
-                format!("{} | {}", left_display.red(), right.green())
-            } else if line_same {
-                format!("{} | {}", left_display, right)
-            } else {
-                format!("{} | {}", left_display, right)
-            };
-            
-            output.push_str(&formatted_line);
-            output.push('\n');
-        }
-        
-        Ok(output)
-    }
-    
-    fn format_operations(&self, result: &DiffResult) -> Result<String, Box<dyn std::error::Error>> {
-        let mut output = String::new();
-        
-        output.push_str(&format!("edit distance: {}\n", result.edit_distance));
-        output.push_str(&format!("operations: {}\n", result.operations.len()));
-        output.push_str("---\n");
-        
-        for (i, op) in result.operations.iter().enumerate() {
-            let op_str = match op {
-                DiffOperation::Keep => "keep".to_string(),
-                DiffOperation::Insert(b) => format!("insert '{}'", char::from(*b).escape_debug()),
-                DiffOperation::Delete => "delete".to_string(),
-                DiffOperation::Substitute(b) => format!("substitute '{}'", char::from(*b).escape_debug()),
-            };
-            
-            let formatted = if self.use_color {
-                match op {
-                    DiffOperation::Keep => format!("{:4}: {}", i, op_str),
-                    DiffOperation::Insert(_) => format!("{:4}: {}", i, op_str).green().to_string(),
-                    DiffOperation::Delete => format!("{:4}: {}", i, op_str).red().to_string(),
-                    DiffOperation::Substitute(_) => format!("{:4}: {}", i, op_str).yellow().to_string(),
-                }
-            } else {
-                format!("{:4}: {}", i, op_str)
-            };
-            
-            output.push_str(&formatted);
-            output.push('\n');
-        }
-        
-        Ok(output)
-    }
-    
     fn format_minimal(&self, result: &DiffResult) -> Result<String, Box<dyn std::error::Error>> {
         if result.edit_distance == 0 {
             Ok("files identical\n".to_string())
@@ -183,11 +148,36 @@ impl DiffFormatter {
         }
     }
     
-    fn bytes_to_display_lines(&self, data: &[u8]) -> Vec<String> {
-        String::from_utf8_lossy(data)
-            .lines()
-            .map(|line| line.to_string())
-            .collect()
+    /// formats a `git diff --stat` style summary: a `name | N +++---` line
+    /// and a totals line, or nothing for identical files
+    /// 
+    /// counts are in bytes; a substitution counts as one insertion and one
+    /// deletion, like a changed line in git's stat.
+    pub fn format_stat(&self, file_name: &str, result: &DiffResult) -> String {
+        if result.edit_distance == 0 {
+            return String::new();
+        }
+        
+        let substitutions = result.operations.iter()
+            .filter(|op| matches!(op, DiffOperation::Substitute(_)))
+            .count();
+        let insertions = substitutions + result.operations.iter()
+            .filter(|op| matches!(op, DiffOperation::Insert(_)))
+            .count();
+        let deletions = substitutions + result.operations.iter()
+            .filter(|op| matches!(op, DiffOperation::Delete))
+            .count();
+        
+        let (insert_marks, delete_marks) = stat_marks(insertions, deletions);
+        let (plus, minus) = ("+".repeat(insert_marks), "-".repeat(delete_marks));
+        let (plus, minus) = if self.use_color { (Ansi(&plus).green(), Ansi(&minus).red()) } else { (plus, minus) };
+        
+        format!(
+            " {} | {} {}{}\n 1 file changed, {} insertion{}(+), {} deletion{}(-)\n",
+            file_name, insertions + deletions, plus, minus,
+            insertions, if insertions == 1 { "" } else { "s" },
+            deletions, if deletions == 1 { "" } else { "s" },
+        )
     }
     
     fn build_hunks(&self, file1_data: &[u8], result: &DiffResult) -> Result<Vec<Hunk>, Box<dyn std::error::Error>> {
@@ -264,6 +254,22 @@ impl DiffFormatter {
     }
 }
 
+/// widest `+++---` bar printed by `--stat`
+const STAT_WIDTH: usize = 40;
+
+/// splits at most `STAT_WIDTH` marks between insertions and deletions,
+/// keeping at least one mark for a side with any changes
+fn stat_marks(insertions: usize, deletions: usize) -> (usize, usize) {
+    let total = insertions + deletions;
+    if total <= STAT_WIDTH {
+        return (insertions, deletions);
+    }
+    
+    let scaled = (insertions * STAT_WIDTH + total / 2) / total;
+    let insert_marks = scaled.clamp(usize::from(insertions > 0), STAT_WIDTH - usize::from(deletions > 0));
+    (insert_marks, STAT_WIDTH - insert_marks)
+}
+
 struct Hunk {
     old_start: usize,
     old_count: usize,
@@ -0,0 +1,229 @@
+//! byte-level patches that can be stored and applied later
+//!
+//! a `Patch` is the edit script of a diff on its own, without the inputs or
+//! any formatting. unlike the text formats, which decode lines with
+//! `from_utf8_lossy`, it carries every inserted byte exactly, so binary and
+//! non-utf-8 content round-trips losslessly.
+
+use crate::{
+    error::Result,
+    types::{apply_operations, operations_output_len, DiffError, DiffOperation, DiffResult as LegacyDiffResult},
+};
+use serde::{Deserialize, Serialize};
+
+/// serializable edit script turning a left input into a right input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patch {
+    operations: Vec<DiffOperation>,
+    left_len: usize,
+    right_len: usize,
+}
+
+impl Patch {
+    /// operations in order; unchanged stretches are `KeepRun`s
+    pub fn operations(&self) -> &[DiffOperation] {
+        &self.operations
+    }
+
+    /// length of the input the patch applies to
+    pub fn left_len(&self) -> usize {
+        self.left_len
+    }
+
+    /// length of the output the patch produces
+    pub fn right_len(&self) -> usize {
+        self.right_len
+    }
+
+    /// rebuilds the right input from the left one
+    ///
+    /// fails if `input` isn't `left_len` bytes, or if the operations (say,
+    /// from a hand-edited serialized patch) don't consume exactly the input
+    /// or don't produce `right_len` bytes.
+    pub fn apply(&self, input: &[u8]) -> Result<Vec<u8>> {
+        self.can_apply(input)?;
+        Ok(apply_operations(&self.operations, input)?)
+    }
+
+    /// checks that `apply` would succeed on `input`, without building the
+    /// output
+    ///
+    /// runs the same checks as `apply` and fails with the same error: the
+    /// length first (`InputLengthMismatch`), then whether the operations
+    /// fit the input and produce `right_len` bytes. only lengths are
+    /// checked, so this is cheap enough to weed out mismatched inputs in a
+    /// batch before applying anything.
+    pub fn can_apply(&self, input: &[u8]) -> std::result::Result<(), DiffError> {
+        if input.len() != self.left_len {
+            return Err(DiffError::InputLengthMismatch {
+                expected: self.left_len,
+                got: input.len(),
+            });
+        }
+
+        let output_len = operations_output_len(&self.operations, input.len())?;
+        if output_len != self.right_len {
+            return Err(DiffError::InvalidScript(format!(
+                "patch produced {} bytes, expected {}", output_len, self.right_len
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl From<&LegacyDiffResult> for Patch {
+    /// copies the script, run-length encoding kept bytes
+    fn from(result: &LegacyDiffResult) -> Self {
+        let mut compacted = result.clone();
+        compacted.compact();
+
+        Self {
+            operations: compacted.operations,
+            left_len: result.original_len_a,
+            right_len: result.original_len_b,
+        }
+    }
+}
+
+/// leading bytes of a binary delta; the last one is the format version
+const BINARY_DELTA_MAGIC: &[u8; 4] = b"CTD\x01";
+
+/// binary delta command copying bytes of the original
+const DELTA_COPY: u64 = 0;
+/// binary delta command skipping bytes of the original
+const DELTA_DELETE: u64 = 1;
+/// binary delta command inserting literal bytes
+const DELTA_INSERT: u64 = 2;
+/// binary delta command replacing bytes of the original with literal bytes
+const DELTA_REPLACE: u64 = 3;
+
+/// encodes an edit script as a compact binary delta
+/// 
+/// after the magic come the left and right lengths, then one command per
+/// run of like operations: a varint holding the run length shifted left
+/// by two over the command tag, followed by the run's bytes for inserts
+/// and replacements. unchanged stretches cost a few bytes whatever their
+/// length.
+pub(crate) fn encode_binary_delta(operations: &[DiffOperation], left_len: usize, right_len: usize) -> Vec<u8> {
+    let mut delta = BINARY_DELTA_MAGIC.to_vec();
+    write_varint(&mut delta, left_len as u64);
+    write_varint(&mut delta, right_len as u64);
+    
+    let mut tag = DELTA_COPY;
+    let mut count = 0u64;
+    let mut literals = Vec::new();
+    for op in operations {
+        let (op_tag, op_count, literal) = match *op {
+            DiffOperation::Keep => (DELTA_COPY, 1, None),
+            DiffOperation::KeepRun(n) => (DELTA_COPY, u64::from(n), None),
+            DiffOperation::Delete => (DELTA_DELETE, 1, None),
+            DiffOperation::Insert(byte) => (DELTA_INSERT, 1, Some(byte)),
+            DiffOperation::Substitute(byte) => (DELTA_REPLACE, 1, Some(byte)),
+        };
+        if op_tag != tag {
+            write_delta_command(&mut delta, tag, count, &literals);
+            (tag, count) = (op_tag, 0);
+            literals.clear();
+        }
+        count += op_count;
+        literals.extend(literal);
+    }
+    write_delta_command(&mut delta, tag, count, &literals);
+    
+    delta
+}
+
+/// rebuilds the right input from `original` and a delta from `to_binary_delta`
+/// 
+/// fails with `InputLengthMismatch` if `original` isn't the length the
+/// delta was made for, and with `InvalidScript` or the other script errors
+/// if the delta is malformed or truncated. lengths in the delta are not
+/// trusted for allocation.
+pub fn apply_binary_delta(original: &[u8], delta: &[u8]) -> Result<Vec<u8>> {
+    let mut rest = delta.strip_prefix(BINARY_DELTA_MAGIC)
+        .ok_or_else(|| DiffError::InvalidScript("not a binary delta".to_string()))?;
+    let left_len = read_delta_len(&mut rest)?;
+    let right_len = read_delta_len(&mut rest)?;
+    if original.len() != left_len {
+        return Err(DiffError::InputLengthMismatch {
+            expected: left_len,
+            got: original.len(),
+        }.into());
+    }
+    
+    // every output byte is copied from the original or a literal in the delta
+    let mut output = Vec::with_capacity(right_len.min(original.len() + rest.len()));
+    let mut position = 0usize;
+    while !rest.is_empty() {
+        let command = read_varint(&mut rest)?;
+        let count = usize::try_from(command >> 2)
+            .map_err(|_| DiffError::InvalidScript("binary delta run too long".to_string()))?;
+        let tag = command & 3;
+        
+        let consumed = if tag == DELTA_INSERT { 0 } else { count };
+        let end = position.checked_add(consumed)
+            .filter(|&end| end <= original.len())
+            .ok_or(DiffError::ScriptOverrunsInput)?;
+        match tag {
+            DELTA_COPY => output.extend_from_slice(&original[position..end]),
+            DELTA_DELETE => {}
+            _ => {
+                if rest.len() < count {
+                    return Err(DiffError::InvalidScript("binary delta is truncated".to_string()).into());
+                }
+                let (literals, remaining) = rest.split_at(count);
+                output.extend_from_slice(literals);
+                rest = remaining;
+            }
+        }
+        position = end;
+    }
+    
+    if position != original.len() {
+        return Err(DiffError::ScriptUnderconsumesInput {
+            remaining: original.len() - position,
+        }.into());
+    }
+    if output.len() != right_len {
+        return Err(DiffError::InvalidScript(format!(
+            "binary delta produced {} bytes, expected {}", output.len(), right_len
+        )).into());
+    }
+    Ok(output)
+}
+
+/// appends one run, skipping empty ones
+fn write_delta_command(delta: &mut Vec<u8>, tag: u64, count: u64, literals: &[u8]) {
+    if count > 0 {
+        write_varint(delta, (count << 2) | tag);
+        delta.extend_from_slice(literals);
+    }
+}
+
+/// appends `value` as a little-endian base-128 varint
+fn write_varint(delta: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        delta.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    delta.push(value as u8);
+}
+
+/// reads a varint from the front of `input`, advancing past it
+fn read_varint(input: &mut &[u8]) -> std::result::Result<u64, DiffError> {
+    let mut value = 0u64;
+    for (i, &byte) in input.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *input = &input[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(DiffError::InvalidScript("binary delta has a truncated or oversized varint".to_string()))
+}
+
+/// reads a length header from the front of `input`
+fn read_delta_len(input: &mut &[u8]) -> std::result::Result<usize, DiffError> {
+    usize::try_from(read_varint(input)?)
+        .map_err(|_| DiffError::InvalidScript("binary delta length too large".to_string()))
+}
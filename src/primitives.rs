@@ -2,6 +2,15 @@
 //! 
 //! provides basic building blocks for constant-time algorithms that resist
 //! timing attacks through uniform execution patterns.
+//!
+//! this module is the crate's `no_std` core: it depends only on `core` and
+//! `subtle` and never allocates, so it builds with `default-features = false`.
+//! keep `std::` paths out of here. to check the no_std build locally (or in ci):
+//!
+//! ```text
+//! cargo build --lib --no-default-features
+//! cargo build --lib --no-default-features --target thumbv7em-none-eabihf
+//! ```
 
 use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater, ConstantTimeLess};
 
@@ -36,6 +45,42 @@ pub fn ct_max(a: u32, b: u32) -> u32 {
     u32::conditional_select(&b, &a, a_is_larger)
 }
 
+/// constant-time minimum of a slice
+/// 
+/// scans every element, keeping the running minimum with conditional
+/// selects, so the time depends only on the length and not on where the
+/// minimum sits.
+/// 
+/// # Panics
+/// 
+/// panics if `data` is empty, which has no minimum.
+pub fn ct_min_slice(data: &[u32]) -> u32 {
+    assert!(!data.is_empty(), "empty slice");
+    
+    data[1..].iter().fold(data[0], |min, &value| ct_min(min, value))
+}
+
+/// constant-time index of the minimum of a slice; the first one on ties
+/// 
+/// same unconditional scan as `ct_min_slice`, also carrying the index, so
+/// which position won isn't revealed by timing.
+/// 
+/// # Panics
+/// 
+/// panics if `data` is empty, like `ct_min_slice`.
+pub fn ct_argmin_slice(data: &[u32]) -> usize {
+    assert!(!data.is_empty(), "empty slice");
+    
+    let mut min = data[0];
+    let mut index = 0u64;
+    for (i, &value) in data.iter().enumerate().skip(1) {
+        let is_smaller = value.ct_lt(&min);
+        min = u32::conditional_select(&min, &value, is_smaller);
+        index = u64::conditional_select(&index, &(i as u64), is_smaller);
+    }
+    index as usize
+}
+
 /// constant-time conditional copy
 /// 
 /// copies src to dst if condition is true, otherwise leaves dst unchanged.
@@ -80,7 +125,7 @@ pub fn ct_memcmp(a: &[u8], b: &[u8]) -> i32 {
     
     // if all compared bytes equal, compare by length
     if result == 0 {
-        use std::cmp::Ordering;
+        use core::cmp::Ordering;
         match a.len().cmp(&b.len()) {
             Ordering::Less => -1,
             Ordering::Greater => 1,
@@ -91,6 +136,14 @@ pub fn ct_memcmp(a: &[u8], b: &[u8]) -> i32 {
     }
 }
 
+/// constant-time lexicographic comparison returning an `Ordering`
+/// 
+/// same byte loop as `ct_memcmp`; only its final sign is converted, so
+/// this can order keys directly (e.g. in a `BTreeMap`-style `Ord` impl).
+pub fn ct_cmp(a: &[u8], b: &[u8]) -> core::cmp::Ordering {
+    ct_memcmp(a, b).cmp(&0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
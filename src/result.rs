@@ -4,22 +4,36 @@
 
 use crate::{
     error::Result,
-    formats::{OutputFormat, FormatOptions, unified, json, html, git, summary},
-    types::DiffResult as LegacyDiffResult,
+    moves::{self, BlockMove},
+    patch::Patch,
+    formats::{ColorChoice, OutputFormat, FormatOptions, Hunk, hunks, unified, json, html, git, summary, side_by_side, operations},
+    types::{ChangeKind, ChangeRegion, DiffOperation, DiffResult as LegacyDiffResult, ScriptComparison},
 };
+use serde::{Deserialize, Serialize};
 
 /// rich diff result with formatting capabilities
-#[derive(Debug, Clone)]
+/// 
+/// serializable so computed diffs can be cached and re-formatted later; the
+/// input buffers are stored as base64 strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiffResult {
     inner: LegacyDiffResult,
+    #[serde(with = "base64_bytes")]
     left_data: Vec<u8>,
+    #[serde(with = "base64_bytes")]
     right_data: Vec<u8>,
     left_name: String,
     right_name: String,
     output_format: OutputFormat,
     format_options: FormatOptions,
     context_lines: usize,
-    enable_color: bool,
+    color_choice: ColorChoice,
+    /// lines that differed only in line endings, when newlines were normalized
+    #[serde(default)]
+    line_ending_changes: Option<usize>,
+    /// changed tokens, for diffs coarser than bytes
+    #[serde(default)]
+    token_edit_distance: Option<usize>,
 }
 
 impl DiffResult {
@@ -31,7 +45,7 @@ impl DiffResult {
         output_format: OutputFormat,
         format_options: FormatOptions,
         context_lines: usize,
-        enable_color: bool,
+        color: impl Into<ColorChoice>,
     ) -> Self {
         Self {
             inner,
@@ -42,11 +56,14 @@ impl DiffResult {
             output_format,
             format_options,
             context_lines,
-            enable_color,
+            color_choice: color.into(),
+            line_ending_changes: None,
+            token_edit_distance: None,
         }
     }
     
     /// creates a new diff result with file names
+    #[allow(clippy::too_many_arguments)]
     pub fn new_with_names(
         inner: LegacyDiffResult,
         left_data: Vec<u8>,
@@ -56,7 +73,7 @@ impl DiffResult {
         output_format: OutputFormat,
         format_options: FormatOptions,
         context_lines: usize,
-        enable_color: bool,
+        color: impl Into<ColorChoice>,
     ) -> Self {
         Self {
             inner,
@@ -67,7 +84,9 @@ impl DiffResult {
             output_format,
             format_options,
             context_lines,
-            enable_color,
+            color_choice: color.into(),
+            line_ending_changes: None,
+            token_edit_distance: None,
         }
     }
     
@@ -76,37 +95,204 @@ impl DiffResult {
         self.inner.edit_distance
     }
     
+    /// gets the edit distance in the diff's tokens
+    /// 
+    /// `edit_distance` counts changed bytes even for word, line, grapheme or
+    /// custom token diffs; this counts each replaced, deleted or inserted
+    /// token once instead, so a word swapped for another is one edit. for
+    /// byte diffs the two are equal.
+    pub fn token_edit_distance(&self) -> usize {
+        self.token_edit_distance.unwrap_or(self.inner.edit_distance)
+    }
+    
+    /// records the count reported by `token_edit_distance`
+    pub(crate) fn with_token_edit_distance(mut self, count: usize) -> Self {
+        self.token_edit_distance = Some(count);
+        self
+    }
+    
     /// checks if inputs are identical
     pub fn is_identical(&self) -> bool {
         self.inner.edit_distance == 0
     }
     
+    /// left input the diff was computed from
+    pub fn left_data(&self) -> &[u8] {
+        &self.left_data
+    }
+    
+    /// right input the diff was computed from
+    /// 
+    /// the edit script rebuilds exactly these bytes from `left_data`, so
+    /// formatting uses them as they are rather than applying the script.
+    pub fn right_data(&self) -> &[u8] {
+        &self.right_data
+    }
+    
+    /// display name of the left input
+    pub fn left_name(&self) -> &str {
+        &self.left_name
+    }
+    
+    /// display name of the right input
+    pub fn right_name(&self) -> &str {
+        &self.right_name
+    }
+    
+    /// number of lines that differed only in line endings
+    /// 
+    /// `None` unless the diff was built with `normalize_newlines`, since the
+    /// result otherwise can't tell which changes were line endings.
+    pub fn line_ending_changes(&self) -> Option<usize> {
+        self.line_ending_changes
+    }
+    
+    /// records the count reported by `line_ending_changes`
+    pub(crate) fn with_line_ending_changes(mut self, count: usize) -> Self {
+        self.line_ending_changes = Some(count);
+        self
+    }
+    
     /// gets the raw diff operations
-    pub fn operations(&self) -> &[crate::types::DiffOperation] {
+    pub fn operations(&self) -> &[DiffOperation] {
         &self.inner.operations
     }
     
     /// gets similarity ratio (0.0 = completely different, 1.0 = identical)
     pub fn similarity(&self) -> f64 {
-        let max_len = self.left_data.len().max(self.right_data.len());
-        if max_len == 0 {
-            return 1.0;
+        similarity_ratio(self.edit_distance(), self.left_data.len(), self.right_data.len())
+    }
+    
+    /// bytes of the left input kept by the edit script, in order
+    ///
+    /// these form a common subsequence of both inputs, but despite the name
+    /// not necessarily the longest: the script minimizes edits with
+    /// substitutions allowed, so where it substitutes instead of deleting
+    /// and inserting it keeps fewer bytes than a longest common subsequence
+    /// would. with `allow_substitution` off it keeps a longest one.
+    pub fn longest_common_subsequence(&self) -> Vec<u8> {
+        let mut common = Vec::with_capacity(self.lcs_length());
+        let mut pos = 0;
+    
+        for op in self.operations() {
+            let kept = op.kept_bytes();
+            common.extend_from_slice(&self.left_data[pos..pos + kept]);
+            pos += match op {
+                DiffOperation::Insert(_) => 0,
+                DiffOperation::Delete | DiffOperation::Substitute(_) => 1,
+                DiffOperation::Keep | DiffOperation::KeepRun(_) => kept,
+            };
+        }
+    
+        common
+    }
+    
+    /// number of bytes kept by the edit script, the length of
+    /// `longest_common_subsequence` without collecting it
+    pub fn lcs_length(&self) -> usize {
+        self.operations().iter().map(|op| op.kept_bytes()).sum()
+    }
+    
+    /// line-level hunks, as shown by the unified and git formats
+    /// 
+    /// changes more than `2 * context_lines` equal lines apart land in
+    /// separate hunks. empty when the inputs are identical.
+    pub fn hunks(&self, context_lines: usize) -> Vec<Hunk> {
+        if self.is_identical() {
+            return Vec::new();
         }
-        1.0 - (self.edit_distance() as f64 / max_len as f64)
+        hunks::build_hunks(&self.left_data, &self.right_data, &self.inner.operations, context_lines, false)
+    }
+    
+    /// stable hash of the edit script, for use as a cache key
+    /// 
+    /// see [`crate::types::DiffResult::fingerprint`]; names, inputs and
+    /// format settings are not included.
+    pub fn fingerprint(&self) -> u64 {
+        self.inner.fingerprint()
+    }
+    
+    /// compares this result's edit script against another's
+    /// 
+    /// see [`crate::types::DiffResult::compare_scripts`].
+    pub fn compare_scripts(&self, other: &DiffResult) -> ScriptComparison {
+        self.inner.compare_scripts(&other.inner)
+    }
+    
+    /// constant-time equality of the two edit scripts
+    /// 
+    /// see [`crate::types::DiffResult::ct_eq`]; inputs and names aren't compared.
+    pub fn ct_eq(&self, other: &DiffResult) -> subtle::Choice {
+        self.inner.ct_eq(&other.inner)
+    }
+    
+    /// whether the change only inserts, only deletes, only substitutes, or mixes them
+    /// 
+    /// see [`crate::types::DiffResult::classify`].
+    pub fn classify(&self) -> ChangeKind {
+        self.inner.classify()
+    }
+    
+    /// runs of adjacent edits as `(offset, old bytes, new bytes)` regions
+    /// 
+    /// see [`crate::types::DiffResult::change_regions`]; offsets are into
+    /// the left input.
+    pub fn change_regions(&self) -> Result<Vec<ChangeRegion>> {
+        Ok(self.inner.change_regions(&self.left_data)?)
+    }
+    
+    /// maps an offset in the left input to the right one
+    /// 
+    /// see [`crate::types::DiffResult::map_offset_left_to_right`].
+    pub fn map_offset_left_to_right(&self, left_offset: usize) -> Option<usize> {
+        self.inner.map_offset_left_to_right(left_offset)
+    }
+    
+    /// maps an offset in the right input to the left one
+    /// 
+    /// see [`crate::types::DiffResult::map_offset_right_to_left`].
+    pub fn map_offset_right_to_left(&self, right_offset: usize) -> Option<usize> {
+        self.inner.map_offset_right_to_left(right_offset)
+    }
+    
+    /// the edit script as a standalone, binary-safe patch
+    /// 
+    /// unlike formatted output this keeps every byte, so
+    /// `to_patch().apply(left)` reproduces the right input exactly.
+    pub fn to_patch(&self) -> Patch {
+        Patch::from(&self.inner)
+    }
+    
+    /// the edit script as a compact binary delta
+    /// 
+    /// see [`crate::types::DiffResult::to_binary_delta`].
+    pub fn to_binary_delta(&self) -> Vec<u8> {
+        self.inner.to_binary_delta()
+    }
+    
+    /// finds blocks that moved between the inputs
+    /// 
+    /// purely analytical: the edit script is unchanged. see
+    /// [`crate::moves::detect_moves`].
+    pub fn detect_moves(&self) -> Vec<BlockMove> {
+        moves::detect_moves(&self.left_data, &self.right_data, &self.inner)
     }
     
     /// formats result using configured output format
     pub fn format(&self) -> Result<String> {
+        let enable_color = !self.format_options.deterministic && self.color_choice.should_color();
+        let right_data = &self.right_data;
+        
         match self.output_format {
             OutputFormat::Unified => {
                 unified::format(
                     &self.left_name,
                     &self.right_name,
                     &self.left_data,
-                    &self.right_data,
+                    right_data,
                     &self.inner,
                     self.context_lines,
-                    self.enable_color,
+                    enable_color,
                     &self.format_options,
                 )
             }
@@ -115,7 +301,7 @@ impl DiffResult {
                     &self.left_name,
                     &self.right_name,
                     &self.left_data,
-                    &self.right_data,
+                    right_data,
                     &self.inner,
                     &self.format_options,
                 )
@@ -125,7 +311,7 @@ impl DiffResult {
                     &self.left_name,
                     &self.right_name,
                     &self.left_data,
-                    &self.right_data,
+                    right_data,
                     &self.inner,
                     self.context_lines,
                     &self.format_options,
@@ -136,25 +322,126 @@ impl DiffResult {
                     &self.left_name,
                     &self.right_name,
                     &self.left_data,
-                    &self.right_data,
+                    right_data,
                     &self.inner,
                     self.context_lines,
+                    enable_color,
                     &self.format_options,
                 )
             }
             OutputFormat::Summary => {
-                summary::format(
+                let mut output = summary::format(
                     &self.left_name,
                     &self.right_name,
                     &self.left_data,
-                    &self.right_data,
+                    right_data,
                     &self.inner,
+                    enable_color,
+                    &self.format_options,
+                )?;
+                if let Some(count) = self.line_ending_changes.filter(|&count| count > 0) {
+                    output.push_str(&summary::format_line_ending_note(count));
+                }
+                Ok(output)
+            }
+            OutputFormat::SideBySide => {
+                side_by_side::format(
+                    &self.left_name,
+                    &self.right_name,
+                    &self.left_data,
+                    right_data,
+                    &self.inner,
+                    enable_color,
+                    &self.format_options,
+                )
+            }
+            OutputFormat::Operations => {
+                operations::format(
+                    &self.left_name,
+                    &self.right_name,
+                    &self.left_data,
+                    right_data,
+                    &self.inner,
+                    enable_color,
                     &self.format_options,
                 )
             }
         }
     }
     
+    /// writes the configured format to `writer` as it's produced
+    /// 
+    /// unified and git output are written a hunk at a time and json
+    /// operations one at a time, rather than collected into one string
+    /// first. the hunks are still all built before the first is written,
+    /// so memory grows with the number of changes, not with the size of
+    /// the output. the other formats are rendered with `format` first. the
+    /// bytes written are the same as `format` returns.
+    /// 
+    /// an error can come after part of the output was written.
+    pub fn format_stream<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let enable_color = !self.format_options.deterministic && self.color_choice.should_color();
+        
+        match self.output_format {
+            OutputFormat::Unified => unified::write(
+                writer,
+                &self.left_name,
+                &self.right_name,
+                &self.left_data,
+                &self.right_data,
+                &self.inner,
+                self.context_lines,
+                enable_color,
+                &self.format_options,
+            ),
+            OutputFormat::Json => json::write(
+                writer,
+                &self.left_name,
+                &self.right_name,
+                &self.left_data,
+                &self.right_data,
+                &self.inner,
+                &self.format_options,
+            ),
+            OutputFormat::Git => git::write(
+                writer,
+                &self.left_name,
+                &self.right_name,
+                &self.left_data,
+                &self.right_data,
+                &self.inner,
+                self.context_lines,
+                enable_color,
+                &self.format_options,
+            ),
+            OutputFormat::Html | OutputFormat::Summary | OutputFormat::SideBySide | OutputFormat::Operations => {
+                writer.write_all(self.format()?.as_bytes())?;
+                Ok(())
+            }
+        }
+    }
+    
+    /// formats as plain unified text (no color, no line numbers) for the tui
+    #[cfg(feature = "tui")]
+    pub(crate) fn unified_plain(&self) -> Result<String> {
+        let options = FormatOptions {
+            include_metadata: true,
+            show_line_numbers: false,
+            ..self.format_options.clone()
+        };
+        
+        unified::format(
+            &self.left_name,
+            &self.right_name,
+            &self.left_data,
+            &self.right_data,
+            &self.inner,
+            self.context_lines,
+            false,
+            &options,
+        )
+    }
+    
     /// formats result with specific format (overrides configured format)
     pub fn format_as(&self, format: OutputFormat) -> Result<String> {
         let mut result = self.clone();
@@ -162,17 +449,35 @@ impl DiffResult {
         result.format()
     }
     
-    /// writes formatted result to file
+    /// writes formatted result to file, through `format_stream`
+    /// 
+    /// the file is truncated before formatting starts, so a format error
+    /// leaves it holding whatever was written up to that point.
     pub fn write_to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
-        let formatted = self.format()?;
-        std::fs::write(path, formatted)?;
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.format_stream(&mut file)?;
+        std::io::Write::flush(&mut file)?;
         Ok(())
     }
     
-    /// writes formatted result to writer
+    /// writes formatted result to writer, through `format_stream`
     pub fn write_to<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
-        let formatted = self.format()?;
-        writer.write_all(formatted.as_bytes())?;
+        self.format_stream(&mut writer)
+    }
+    
+    /// writes formatted result to an async writer (requires async feature)
+    /// 
+    /// formatting is cpu-bound so it runs on the blocking pool; only the
+    /// write itself happens on the async runtime.
+    #[cfg(feature = "async")]
+    pub async fn write_to_async<W: tokio::io::AsyncWrite + Unpin>(&self, mut writer: W) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+        
+        let result = self.clone();
+        let formatted = tokio::task::spawn_blocking(move || result.format()).await??;
+        
+        writer.write_all(formatted.as_bytes()).await?;
+        writer.flush().await?;
         Ok(())
     }
     
@@ -185,7 +490,6 @@ impl DiffResult {
     
     /// gets detailed statistics about the diff
     pub fn statistics(&self) -> DiffStatistics {
-        let total_ops = self.inner.operations.len();
         let insertions = self.inner.operations.iter()
             .filter(|op| matches!(op, crate::types::DiffOperation::Insert(_)))
             .count();
@@ -195,7 +499,13 @@ impl DiffResult {
         let substitutions = self.inner.operations.iter()
             .filter(|op| matches!(op, crate::types::DiffOperation::Substitute(_)))
             .count();
-        let keeps = total_ops - insertions - deletions - substitutions;
+        let keeps = self.inner.operations.iter()
+            .map(crate::types::DiffOperation::kept_bytes)
+            .sum::<usize>();
+        let total_ops = insertions + deletions + substitutions + keeps;
+        let weighted_cost = self.inner.operations.iter()
+            .map(crate::types::DiffOperation::cost)
+            .sum();
         
         DiffStatistics {
             edit_distance: self.edit_distance(),
@@ -205,6 +515,7 @@ impl DiffResult {
             deletions,
             substitutions,
             keeps,
+            weighted_cost,
             left_size: self.left_data.len(),
             right_size: self.right_data.len(),
         }
@@ -228,8 +539,55 @@ pub struct DiffStatistics {
     pub substitutions: usize,
     /// number of keeps (unchanged)
     pub keeps: usize,
+    /// summed cost of all operations; equals `edit_distance` under unit costs
+    pub weighted_cost: usize,
+    /// size of left input
+    pub left_size: usize,
+    /// size of right input
+    pub right_size: usize,
+}
+
+/// edit distance and similarity of a pair, without the edit script
+/// 
+/// returned by [`crate::Diff::score`] for bulk scoring where the full
+/// result would be wasted.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreReport {
+    /// total edit distance
+    pub edit_distance: usize,
+    /// similarity ratio (0.0 to 1.0), as `DiffResult::similarity`
+    pub similarity: f64,
     /// size of left input
     pub left_size: usize,
     /// size of right input
     pub right_size: usize,
-}
\ No newline at end of file
+}
+
+/// one minus edit distance over the longer length; 1.0 for two empty inputs
+pub(crate) fn similarity_ratio(edit_distance: usize, left_len: usize, right_len: usize) -> f64 {
+    let max_len = left_len.max(right_len);
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - (edit_distance as f64 / max_len as f64)
+}
+
+/// serde helper storing byte buffers as base64 strings
+/// 
+/// use with `#[serde(with = "ctdiff::result::base64_bytes")]` on a `Vec<u8>`
+/// field to avoid json's one-number-per-byte arrays.
+pub mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+    
+    /// encodes bytes as a base64 string
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+    
+    /// decodes a base64 string back into bytes
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
@@ -3,6 +3,8 @@
 //! defines security policies and configurations for constant-time operations
 
 use crate::types::SecurityConfig as LegacySecurityConfig;
+use serde::{Deserialize, Serialize};
+pub use crate::types::{AffixTrim, PaddingStrategy, DEFAULT_MAX_MATRIX_BYTES};
 
 /// high-level security levels for easy configuration
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -35,22 +37,38 @@ pub struct SecurityConfig {
     pub pad_inputs: bool,
     /// padding size for inputs (none = auto-calculate)
     pub padding_size: Option<usize>,
-    /// whether to validate inputs for security
+    /// whether to validate inputs for security (rejects the fixed padding
+    /// byte when padding is on)
     pub validate_inputs: bool,
     /// maximum edit distance allowed
     pub max_edit_distance: Option<usize>,
+    /// bytes used to fill the padded region
+    pub padding_byte: PaddingStrategy,
     /// enable memory protection features
     pub memory_protection: bool,
     /// constant-time guarantees level
     pub timing_protection: TimingProtection,
+    /// return an all-keep result without building the matrix when the
+    /// inputs are byte-identical (leaks whether they were identical)
+    pub fast_identical_check: bool,
+    /// maximum number of operations in a result (none = unlimited)
+    pub max_operations: Option<usize>,
+    /// trim fixed-size matching prefix/suffix windows before diffing
+    /// (leaks one bit per window)
+    pub affix_trim: AffixTrim,
+    /// largest edit matrix to allocate, in bytes (none = unlimited)
+    pub max_matrix_bytes: Option<usize>,
+    /// allow substitutions; when off a change is a delete plus an insert
+    pub allow_substitution: bool,
 }
 
 /// timing protection levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum TimingProtection {
     /// maximum constant-time guarantees
     Strict,
     /// balanced timing protection
+    #[default]
     Moderate,
     /// basic timing protection
     Basic,
@@ -68,8 +86,14 @@ impl SecurityConfig {
             padding_size: Some(max_input_size.next_power_of_two()),
             validate_inputs: true,
             max_edit_distance: Some(max_input_size / 2),
+            padding_byte: PaddingStrategy::default(),
             memory_protection: true,
             timing_protection: TimingProtection::Strict,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
     
@@ -82,8 +106,14 @@ impl SecurityConfig {
             padding_size: None, // auto-calculate
             validate_inputs: true,
             max_edit_distance: Some(max_input_size / 4),
+            padding_byte: PaddingStrategy::default(),
             memory_protection: true,
             timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
     
@@ -96,8 +126,14 @@ impl SecurityConfig {
             padding_size: None,
             validate_inputs: false,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
             memory_protection: false,
             timing_protection: TimingProtection::Basic,
+            fast_identical_check: true,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
     
@@ -109,8 +145,14 @@ impl SecurityConfig {
             padding_size: None,
             validate_inputs: false,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
             memory_protection: false,
             timing_protection: TimingProtection::None,
+            fast_identical_check: true,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
     
@@ -122,6 +164,13 @@ impl SecurityConfig {
             padding_size: self.padding_size,
             validate_inputs: self.validate_inputs,
             max_edit_distance: self.max_edit_distance,
+            padding_byte: self.padding_byte,
+            timing_protection: self.timing_protection,
+            fast_identical_check: self.fast_identical_check,
+            max_operations: self.max_operations,
+            affix_trim: self.affix_trim,
+            max_matrix_bytes: self.max_matrix_bytes,
+            allow_substitution: self.allow_substitution,
         }
     }
     
@@ -145,6 +194,70 @@ impl SecurityConfig {
         
         Ok(())
     }
+    
+    /// describes in plain words what this configuration protects against
+    pub fn explain(&self) -> SecurityExplanation {
+        let timing_guarantee = match (self.timing_protection, self.pad_inputs) {
+            (TimingProtection::Strict, true) => "strong: run time depends only on the padded size, not on content or real lengths",
+            (TimingProtection::Strict, false) => "strong for content, but without padding the input lengths show in run time",
+            (TimingProtection::Moderate, true) => "moderate: run time doesn't depend on content; lengths are hidden up to the padded size",
+            (TimingProtection::Moderate, false) => "moderate: run time doesn't depend on content, but input lengths show in it",
+            (TimingProtection::Basic, _) => "basic: no strong guarantee; input lengths, and whether inputs are identical, can show in run time",
+            (TimingProtection::None, _) => "none: vulnerable to timing attacks",
+        };
+        
+        let dos_protection = match self.max_input_size {
+            usize::MAX => "none: inputs of any size are accepted",
+            size if size <= 64 * 1024 => "strong: the small input limit keeps matrix size and run time low",
+            size if size <= 1024 * 1024 => "moderate: inputs are capped, but near the cap the quadratic matrix gets expensive",
+            _ => "weak: the input limit is high enough for the quadratic matrix to exhaust memory or time",
+        };
+        
+        let memory_behavior = match (self.pad_inputs, self.memory_protection) {
+            (true, true) => "memory use depends only on the padded size; memory protection on",
+            (true, false) => "memory use depends only on the padded size; memory protection off",
+            (false, true) => "memory use grows with the real input sizes; memory protection on",
+            (false, false) => "memory use grows with the real input sizes; memory protection off",
+        };
+        
+        let recommended_for = match self.timing_protection {
+            TimingProtection::Strict => "secrets such as keys, tokens and passwords",
+            TimingProtection::Moderate => "general use, including sensitive data",
+            TimingProtection::Basic => "non-sensitive data where speed matters",
+            TimingProtection::None => "benchmarking only",
+        };
+        
+        SecurityExplanation {
+            timing_guarantee,
+            dos_protection,
+            memory_behavior,
+            recommended_for,
+        }
+    }
+}
+
+/// human-readable description of a security configuration
+/// 
+/// see [`SecurityConfig::explain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SecurityExplanation {
+    /// how far run time can reveal anything about the inputs
+    pub timing_guarantee: &'static str,
+    /// how well input limits bound the work an attacker can cause
+    pub dos_protection: &'static str,
+    /// what drives memory use
+    pub memory_behavior: &'static str,
+    /// data this configuration suits
+    pub recommended_for: &'static str,
+}
+
+impl std::fmt::Display for SecurityExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Timing guarantee: {}", self.timing_guarantee)?;
+        writeln!(f, "DoS protection:   {}", self.dos_protection)?;
+        writeln!(f, "Memory behavior:  {}", self.memory_behavior)?;
+        write!(f, "Recommended for:  {}", self.recommended_for)
+    }
 }
 
 impl Default for SecurityConfig {
@@ -0,0 +1,78 @@
+//! fast similarity estimates that skip the edit matrix
+//!
+//! **not constant-time.** these heuristics hash content and compare it with
+//! ordinary branching, so their run time depends on what the inputs contain.
+//! use them for bulk near-duplicate screening of data that isn't secret, and
+//! `Diff` when timing matters. `pairwise_matrix` can use either: the
+//! `EditDistance` metric runs the constant-time diff on every pair.
+
+use crate::algorithm::ConstantTimeDiff;
+use crate::result::similarity_ratio;
+use crate::types::{DiffError, SecurityConfig};
+use std::collections::HashSet;
+
+/// how `pairwise_matrix` scores a pair of documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityMetric {
+    /// one minus the constant-time edit distance over the longer length
+    EditDistance,
+    /// `ngram_jaccard` with this n-gram length; fast but not constant-time
+    NgramJaccard(usize),
+}
+
+/// jaccard index of the byte n-gram sets of `a` and `b`
+///
+/// returns the share of distinct n-grams the inputs have in common, from
+/// 0.0 (none) to 1.0 (same set), in time linear in the input lengths. `n` of
+/// 0 is treated as 1. inputs shorter than `n` have no n-grams; two such
+/// inputs score 1.0 if equal and 0.0 otherwise.
+pub fn ngram_jaccard(a: &[u8], b: &[u8], n: usize) -> f64 {
+    let n = n.max(1);
+    let grams_a: HashSet<&[u8]> = a.windows(n).collect();
+    let grams_b: HashSet<&[u8]> = b.windows(n).collect();
+
+    if grams_a.is_empty() && grams_b.is_empty() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let shared = grams_a.intersection(&grams_b).count();
+    let union = grams_a.len() + grams_b.len() - shared;
+    shared as f64 / union as f64
+}
+
+/// similarity of every pair of `docs`, as an n×n matrix
+///
+/// `matrix[i][j]` scores `docs[i]` against `docs[j]`. both metrics are
+/// symmetric, so only the upper triangle is computed and mirrored, and the
+/// diagonal is 1.0. `EditDistance` diffs with the default security
+/// configuration and fails if a pair exceeds its limits; use
+/// `Diff::pairwise_similarity` for another configuration.
+pub fn pairwise_matrix(docs: &[&[u8]], metric: SimilarityMetric) -> Result<Vec<Vec<f64>>, DiffError> {
+    match metric {
+        SimilarityMetric::EditDistance => {
+            let differ = ConstantTimeDiff::new(SecurityConfig::default());
+            symmetric_matrix(docs, |a, b| {
+                let result = differ.diff(a, b)?;
+                Ok(similarity_ratio(result.edit_distance, a.len(), b.len()))
+            })
+        }
+        SimilarityMetric::NgramJaccard(n) => symmetric_matrix(docs, |a, b| Ok(ngram_jaccard(a, b, n))),
+    }
+}
+
+/// fills an n×n matrix with 1.0 on the diagonal, scoring each pair above it
+/// once and mirroring the score below
+pub(crate) fn symmetric_matrix<E>(
+    docs: &[&[u8]],
+    mut score: impl FnMut(&[u8], &[u8]) -> Result<f64, E>,
+) -> Result<Vec<Vec<f64>>, E> {
+    let mut matrix = vec![vec![1.0; docs.len()]; docs.len()];
+    for i in 0..docs.len() {
+        for j in i + 1..docs.len() {
+            let similarity = score(docs[i], docs[j])?;
+            matrix[i][j] = similarity;
+            matrix[j][i] = similarity;
+        }
+    }
+    Ok(matrix)
+}
@@ -81,6 +81,12 @@ pub struct TimingStatistics {
     pub confidence_interval_95: (Duration, Duration),
     /// coefficient of variation (std_dev / mean)
     pub coefficient_of_variation: f64,
+    /// the measurements, sorted ascending
+    /// 
+    /// left out of serialized statistics, which stay summary-sized; a
+    /// deserialized copy has none, so its `histogram` is empty.
+    #[serde(skip)]
+    pub samples: Vec<Duration>,
 }
 
 impl TimingStatistics {
@@ -114,7 +120,7 @@ impl TimingStatistics {
         // median
         let mut sorted_nanos = nanos.clone();
         sorted_nanos.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        let median_nanos = if count % 2 == 0 {
+        let median_nanos = if count.is_multiple_of(2) {
             (sorted_nanos[count / 2 - 1] + sorted_nanos[count / 2]) / 2.0
         } else {
             sorted_nanos[count / 2]
@@ -151,9 +157,42 @@ impl TimingStatistics {
             variance,
             confidence_interval_95: (ci_lower, ci_upper),
             coefficient_of_variation,
+            samples: {
+                let mut samples = durations.to_vec();
+                samples.sort();
+                samples
+            },
         })
     }
     
+    /// counts measurements in `buckets` equal-width ranges from `min` to `max`
+    /// 
+    /// each entry is the lower bound of a range and how many measurements
+    /// fall in it; the last range includes `max`. shows the shape of the
+    /// distribution, e.g. two separate peaks where a leak splits the timings.
+    pub fn histogram(&self, buckets: usize) -> Vec<(Duration, usize)> {
+        if buckets == 0 || self.samples.is_empty() {
+            return Vec::new();
+        }
+        
+        let min = self.min.as_nanos() as f64;
+        let width = (self.max.as_nanos() as f64 - min) / buckets as f64;
+        let mut histogram: Vec<(Duration, usize)> = (0..buckets)
+            .map(|i| (Duration::from_nanos((min + width * i as f64) as u64), 0))
+            .collect();
+        
+        for sample in &self.samples {
+            let index = if width > 0.0 {
+                ((sample.as_nanos() as f64 - min) / width) as usize
+            } else {
+                0
+            };
+            histogram[index.min(buckets - 1)].1 += 1;
+        }
+        
+        histogram
+    }
+    
     /// check if timing difference is statistically significant
     pub fn is_significantly_different(&self, other: &Self, p_value: f64) -> bool {
         // simple t-test approximation
@@ -189,6 +228,25 @@ impl TimingStatistics {
     }
 }
 
+/// renders histogram counts as a one-line ascii sparkline
+/// 
+/// one character per bucket, from blank (empty) to `@` (fullest bucket).
+pub fn render_sparkline(histogram: &[(Duration, usize)]) -> String {
+    const LEVELS: &[u8] = b" .:-=+*#%@";
+    let peak = histogram.iter().map(|&(_, count)| count).max().unwrap_or(0);
+    
+    histogram.iter()
+        .map(|&(_, count)| {
+            let level = match count {
+                0 => 0,
+                // any non-empty bucket gets at least the lowest mark
+                _ => (count * (LEVELS.len() - 1)).div_ceil(peak),
+            };
+            LEVELS[level] as char
+        })
+        .collect()
+}
+
 /// high-precision timer for measuring operations
 pub struct PrecisionTimer {
     start_time: Option<Instant>,
@@ -204,6 +262,15 @@ impl PrecisionTimer {
         }
     }
     
+    /// timer holding previously recorded measurements, e.g. reloaded from
+    /// a file written from `export`, for offline re-analysis
+    pub fn from_measurements(measurements: Vec<TimingMeasurement>) -> Self {
+        Self {
+            start_time: None,
+            measurements,
+        }
+    }
+    
     /// start timing measurement
     pub fn start(&mut self) {
         self.start_time = Some(Instant::now());
@@ -260,6 +327,11 @@ impl PrecisionTimer {
         &self.measurements
     }
     
+    /// copy of all measurements, to persist and reload with `from_measurements`
+    pub fn export(&self) -> Vec<TimingMeasurement> {
+        self.measurements.clone()
+    }
+    
     /// clear all measurements
     pub fn clear(&mut self) {
         self.measurements.clear();
@@ -412,6 +484,30 @@ mod tests {
         assert_eq!(measurement.iterations, 1);
     }
     
+    #[test]
+    fn test_export_round_trip() {
+        let mut timer = PrecisionTimer::new();
+        for i in 0..5 {
+            timer.measure("fast".to_string(), || thread::sleep(Duration::from_micros(10 * i)));
+            timer.measure("slow".to_string(), || thread::sleep(Duration::from_micros(100 * i)));
+        }
+        
+        // persist as jsonl, one measurement per line, and reload
+        let jsonl: String = timer.export().iter()
+            .map(|m| serde_json::to_string(m).unwrap() + "\n")
+            .collect();
+        let reloaded = PrecisionTimer::from_measurements(
+            jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+        );
+        
+        assert_eq!(reloaded.measurements().len(), 10);
+        for label in ["fast", "slow"] {
+            let original = serde_json::to_value(timer.statistics_for_label(label).unwrap()).unwrap();
+            let replayed = serde_json::to_value(reloaded.statistics_for_label(label).unwrap()).unwrap();
+            assert_eq!(original, replayed);
+        }
+    }
+    
     #[test]
     fn test_timing_statistics() {
         let durations = vec![
@@ -450,4 +546,37 @@ mod tests {
         // statistical significance may not be detected with small test samples
         // but ratio should still show clear difference
     }
+    
+    #[test]
+    fn test_histogram() {
+        let mut durations: Vec<Duration> = (0..40).map(|i| Duration::from_nanos(100 + i)).collect();
+        durations.extend((0..60).map(|i| Duration::from_nanos(1000 + i)));
+        let stats = TimingStatistics::from_durations(&durations).unwrap();
+        
+        let histogram = stats.histogram(10);
+        assert_eq!(histogram.len(), 10);
+        assert_eq!(histogram.iter().map(|&(_, count)| count).sum::<usize>(), stats.count);
+        
+        // two peaks at either end with empty buckets between them
+        let populated: Vec<usize> = histogram.iter()
+            .enumerate()
+            .filter(|(_, &(_, count))| count > 0)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(populated, vec![0, 9]);
+        assert_eq!(histogram[0], (Duration::from_nanos(100), 40));
+        assert_eq!(histogram[9].1, 60);
+        
+        let sparkline = render_sparkline(&histogram);
+        assert_eq!(sparkline, "*        @");
+        
+        // identical samples all land in the first bucket
+        let flat = TimingStatistics::from_durations(&[Duration::from_nanos(5); 4]).unwrap();
+        assert_eq!(flat.histogram(3).iter().map(|&(_, count)| count).collect::<Vec<_>>(), vec![4, 0, 0]);
+        assert!(flat.histogram(0).is_empty());
+        
+        // serialized statistics stay summary-sized
+        let json = export::stats_to_json(&stats).unwrap();
+        assert!(!json.contains("samples"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+//! interactive terminal viewer for diff results
+//!
+//! renders the unified diff in a scrollable ratatui view with a status bar.
+//! the view model is kept separate from the terminal loop so it can be built
+//! and inspected without entering raw mode.
+
+use crate::{error::Result, result::DiffResult};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::Paragraph,
+    DefaultTerminal, Frame,
+};
+
+/// kind of a line in the diff view, used for styling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// `---`/`+++` file header
+    Header,
+    /// `@@` hunk header
+    Hunk,
+    /// unchanged context line
+    Context,
+    /// line present only on the right
+    Insert,
+    /// line present only on the left
+    Delete,
+}
+
+/// a single rendered line of the diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewLine {
+    /// how the line should be styled
+    pub kind: LineKind,
+    /// line text including its diff prefix
+    pub text: String,
+}
+
+/// scrollable view model over a unified diff
+#[derive(Debug, Clone)]
+pub struct DiffView {
+    lines: Vec<ViewLine>,
+    scroll: usize,
+    edit_distance: usize,
+    similarity: f64,
+}
+
+impl DiffView {
+    /// builds the view model from a diff result
+    pub fn new(result: &DiffResult) -> Result<Self> {
+        let unified = result.unified_plain()?;
+        let lines = unified.lines().map(classify_line).collect();
+        let stats = result.statistics();
+
+        Ok(Self {
+            lines,
+            scroll: 0,
+            edit_distance: stats.edit_distance,
+            similarity: stats.similarity,
+        })
+    }
+
+    /// all lines in the view
+    pub fn lines(&self) -> &[ViewLine] {
+        &self.lines
+    }
+
+    /// index of the first visible line
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// scrolls down by `amount` lines, stopping at the last line
+    pub fn scroll_down(&mut self, amount: usize) {
+        self.scroll = (self.scroll + amount).min(self.lines.len().saturating_sub(1));
+    }
+
+    /// scrolls up by `amount` lines, stopping at the first line
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+
+    /// text shown in the status bar
+    pub fn status_line(&self) -> String {
+        format!(
+            " edit distance: {} | similarity: {:.1}% | line {}/{} | j/k scroll, q quit",
+            self.edit_distance,
+            self.similarity * 100.0,
+            (self.scroll + 1).min(self.lines.len()),
+            self.lines.len(),
+        )
+    }
+
+    /// draws the diff and status bar into a frame
+    fn render(&self, frame: &mut Frame) {
+        let [body, status] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let lines: Vec<Line> = self.lines.iter()
+            .map(|line| Line::styled(line.text.as_str(), line_style(line.kind)))
+            .collect();
+        let scroll = u16::try_from(self.scroll).unwrap_or(u16::MAX);
+
+        frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), body);
+        frame.render_widget(
+            Paragraph::new(self.status_line()).style(Style::default().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+    }
+}
+
+/// runs the interactive viewer until the user quits
+///
+/// takes over the terminal (raw mode, alternate screen) and restores it on exit
+pub fn run(mut view: DiffView) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut view);
+    ratatui::restore();
+    result
+}
+
+/// handles input and redraws until quit
+fn event_loop(terminal: &mut DefaultTerminal, view: &mut DiffView) -> Result<()> {
+    loop {
+        terminal.draw(|frame| view.render(frame))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let page = terminal.size()?.height.saturating_sub(1) as usize;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('j') | KeyCode::Down => view.scroll_down(1),
+                KeyCode::Char('k') | KeyCode::Up => view.scroll_up(1),
+                KeyCode::PageDown | KeyCode::Char(' ') => view.scroll_down(page),
+                KeyCode::PageUp => view.scroll_up(page),
+                KeyCode::Char('g') | KeyCode::Home => view.scroll_up(view.scroll),
+                KeyCode::Char('G') | KeyCode::End => view.scroll_down(view.lines.len()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// classifies a unified diff line by its prefix
+fn classify_line(text: &str) -> ViewLine {
+    let kind = if text.starts_with("---") || text.starts_with("+++") {
+        LineKind::Header
+    } else if text.starts_with("@@") {
+        LineKind::Hunk
+    } else if text.starts_with('+') {
+        LineKind::Insert
+    } else if text.starts_with('-') {
+        LineKind::Delete
+    } else {
+        LineKind::Context
+    };
+
+    ViewLine { kind, text: text.to_string() }
+}
+
+/// style for each line kind
+fn line_style(kind: LineKind) -> Style {
+    match kind {
+        LineKind::Header => Style::default().add_modifier(Modifier::BOLD),
+        LineKind::Hunk => Style::default().fg(Color::Cyan),
+        LineKind::Context => Style::default(),
+        LineKind::Insert => Style::default().fg(Color::Green),
+        LineKind::Delete => Style::default().fg(Color::Red),
+    }
+}
@@ -4,6 +4,8 @@
 //! design choices to prevent information leakage through type structure.
 
 use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq};
+use crate::security::TimingProtection;
 
 /// basic diff operation that can be applied to transform one sequence into another
 /// 
@@ -19,6 +21,8 @@ pub enum DiffOperation {
     Delete,
     /// substitute byte at current position with new byte
     Substitute(u8),
+    /// keep the next n bytes unchanged (produced by `DiffResult::compact`)
+    KeepRun(u32),
 }
 
 impl DiffOperation {
@@ -27,10 +31,26 @@ impl DiffOperation {
     /// uses conditional selection to avoid branches on operation type.
     pub fn is_modification(&self) -> bool {
         match self {
-            DiffOperation::Keep => false,
+            DiffOperation::Keep | DiffOperation::KeepRun(_) => false,
             DiffOperation::Insert(_) | DiffOperation::Delete | DiffOperation::Substitute(_) => true,
         }
     }
+    
+    /// number of unchanged bytes this operation covers (0 for modifications)
+    pub fn kept_bytes(&self) -> usize {
+        match self {
+            DiffOperation::Keep => 1,
+            DiffOperation::KeepRun(n) => *n as usize,
+            DiffOperation::Insert(_) | DiffOperation::Delete | DiffOperation::Substitute(_) => 0,
+        }
+    }
+    
+    /// contribution of this operation to the edit distance
+    /// 
+    /// the algorithm uses unit costs, so every modification costs 1.
+    pub fn cost(&self) -> usize {
+        usize::from(self.is_modification())
+    }
 }
 
 /// result of constant-time diff computation containing edit script
@@ -65,6 +85,46 @@ impl DiffResult {
         }
     }
 
+    /// checked constructor that derives edit distance from the operations
+    /// 
+    /// rejects scripts that don't consume exactly `len_a` input bytes or
+    /// don't produce exactly `len_b` output bytes.
+    pub fn try_from_operations(
+        operations: Vec<DiffOperation>,
+        len_a: usize,
+        len_b: usize,
+    ) -> Result<Self, DiffError> {
+        let mut pos_a = 0;
+        let mut pos_b = 0;
+        let mut edit_distance = 0;
+        
+        for op in &operations {
+            let (consumed, produced) = match op {
+                DiffOperation::Keep | DiffOperation::Substitute(_) => (1, 1),
+                DiffOperation::KeepRun(n) => (*n as usize, *n as usize),
+                DiffOperation::Insert(_) => (0, 1),
+                DiffOperation::Delete => (1, 0),
+            };
+            pos_a += consumed;
+            pos_b += produced;
+            edit_distance += op.is_modification() as usize;
+        }
+        
+        if pos_a > len_a {
+            return Err(DiffError::ScriptOverrunsInput);
+        }
+        if pos_a < len_a {
+            return Err(DiffError::ScriptUnderconsumesInput { remaining: len_a - pos_a });
+        }
+        if pos_b != len_b {
+            return Err(DiffError::InvalidScript(format!(
+                "script produces {} bytes, expected {}", pos_b, len_b
+            )));
+        }
+        
+        Ok(Self::new(operations, edit_distance, len_a, len_b))
+    }
+
     /// verify that edit script is consistent with reported metadata
     /// 
     /// performs sanity checks without revealing information about content.
@@ -79,6 +139,10 @@ impl DiffResult {
                     pos_a += 1;
                     pos_b += 1;
                 }
+                DiffOperation::KeepRun(n) => {
+                    pos_a += *n as usize;
+                    pos_b += *n as usize;
+                }
                 DiffOperation::Insert(_) => {
                     pos_b += 1;
                     modifications += 1;
@@ -106,48 +170,630 @@ impl DiffResult {
     /// during reconstruction.
     pub fn apply_to(&self, input: &[u8]) -> Result<Vec<u8>, DiffError> {
         if input.len() != self.original_len_a {
-            return Err(DiffError::InvalidInput(format!("input length mismatch: expected {}, got {}", self.original_len_a, input.len())));
+            return Err(DiffError::InputLengthMismatch {
+                expected: self.original_len_a,
+                got: input.len(),
+            });
         }
 
-        let mut result = Vec::new();
-        let mut input_pos = 0;
-
-        for (op_idx, op) in self.operations.iter().enumerate() {
-            match op {
-                DiffOperation::Keep => {
-                    if input_pos >= input.len() {
-                        return Err(DiffError::InvalidScript(format!("script extends beyond input at operation {} (Keep): input_pos={}, input_len={}", op_idx, input_pos, input.len())));
+        apply_operations(&self.operations, input)
+    }
+    
+    /// builds the reverse script, turning the second input back into `left`
+    /// 
+    /// inserts and deletes swap places; deletes and substitutions need the
+    /// original byte, which is why `left` (the first input) is required.
+    pub fn invert(&self, left: &[u8]) -> Result<DiffResult, DiffError> {
+        if left.len() != self.original_len_a {
+            return Err(DiffError::InputLengthMismatch {
+                expected: self.original_len_a,
+                got: left.len(),
+            });
+        }
+        
+        let mut operations = Vec::with_capacity(self.operations.len());
+        let mut pos = 0;
+        
+        for op in &self.operations {
+            let consumed = match op {
+                DiffOperation::Insert(_) => 0,
+                DiffOperation::KeepRun(n) => *n as usize,
+                DiffOperation::Keep | DiffOperation::Delete | DiffOperation::Substitute(_) => 1,
+            };
+            if pos + consumed > left.len() {
+                return Err(DiffError::ScriptOverrunsInput);
+            }
+            
+            operations.push(match op {
+                DiffOperation::Keep => DiffOperation::Keep,
+                DiffOperation::KeepRun(n) => DiffOperation::KeepRun(*n),
+                DiffOperation::Insert(_) => DiffOperation::Delete,
+                DiffOperation::Delete => DiffOperation::Insert(left[pos]),
+                DiffOperation::Substitute(_) => DiffOperation::Substitute(left[pos]),
+            });
+            pos += consumed;
+        }
+        
+        if pos != left.len() {
+            return Err(DiffError::ScriptUnderconsumesInput {
+                remaining: left.len() - pos,
+            });
+        }
+        
+        Ok(DiffResult::new(operations, self.edit_distance, self.original_len_b, self.original_len_a))
+    }
+    
+    /// the script's changes as replacement regions, one per run of adjacent
+    /// edits
+    /// 
+    /// each region says which bytes of `left` (the first input) were
+    /// replaced and by what; a region of pure insertions has empty
+    /// `left_bytes`, and one of pure deletions empty `right_bytes`. regions
+    /// are in order and separated by at least one kept byte.
+    pub fn change_regions(&self, left: &[u8]) -> Result<Vec<ChangeRegion>, DiffError> {
+        if left.len() != self.original_len_a {
+            return Err(DiffError::InputLengthMismatch {
+                expected: self.original_len_a,
+                got: left.len(),
+            });
+        }
+        
+        let mut regions: Vec<ChangeRegion> = Vec::new();
+        let mut in_region = false;
+        let mut pos = 0;
+        
+        for op in &self.operations {
+            let consumed = match op {
+                DiffOperation::Insert(_) => 0,
+                DiffOperation::KeepRun(n) => *n as usize,
+                DiffOperation::Keep | DiffOperation::Delete | DiffOperation::Substitute(_) => 1,
+            };
+            if pos + consumed > left.len() {
+                return Err(DiffError::ScriptOverrunsInput);
+            }
+            
+            if op.is_modification() {
+                if !in_region {
+                    regions.push(ChangeRegion { left_offset: pos, left_bytes: Vec::new(), right_bytes: Vec::new() });
+                    in_region = true;
+                }
+                let region = regions.last_mut().expect("a region was just opened");
+                match op {
+                    DiffOperation::Delete => region.left_bytes.push(left[pos]),
+                    DiffOperation::Insert(byte) => region.right_bytes.push(*byte),
+                    DiffOperation::Substitute(byte) => {
+                        region.left_bytes.push(left[pos]);
+                        region.right_bytes.push(*byte);
                     }
-                    result.push(input[input_pos]);
-                    input_pos += 1;
+                    DiffOperation::Keep | DiffOperation::KeepRun(_) => {}
                 }
-                DiffOperation::Insert(byte) => {
-                    result.push(*byte);
-                    // no input_pos increment for insert
+            } else {
+                in_region = false;
+            }
+            pos += consumed;
+        }
+        
+        if pos != left.len() {
+            return Err(DiffError::ScriptUnderconsumesInput {
+                remaining: left.len() - pos,
+            });
+        }
+        
+        Ok(regions)
+    }
+    
+    /// offset in the second input that `left_offset` in the first maps to
+    /// 
+    /// kept and substituted bytes map one to one; insertions and deletions
+    /// shift the mapping. a deleted position maps to where the next
+    /// surviving byte of the first input ends up, or to the end of the
+    /// second input if none follows. the end of the first input maps to
+    /// the end of the second; offsets beyond it give `None`.
+    pub fn map_offset_left_to_right(&self, left_offset: usize) -> Option<usize> {
+        self.map_offset(left_offset, true)
+    }
+    
+    /// offset in the first input that `right_offset` in the second maps to
+    /// 
+    /// the inverse of `map_offset_left_to_right`: an inserted position maps
+    /// to where the next byte surviving from the first input came from.
+    pub fn map_offset_right_to_left(&self, right_offset: usize) -> Option<usize> {
+        self.map_offset(right_offset, false)
+    }
+    
+    /// walks the script to map `offset` from one side to the other
+    fn map_offset(&self, offset: usize, from_left: bool) -> Option<usize> {
+        let (from_len, to_len) = if from_left {
+            (self.original_len_a, self.original_len_b)
+        } else {
+            (self.original_len_b, self.original_len_a)
+        };
+        if offset > from_len {
+            return None;
+        }
+        
+        let (mut pos_a, mut pos_b) = (0, 0);
+        for op in &self.operations {
+            let (consumed, produced) = match op {
+                DiffOperation::Keep | DiffOperation::Substitute(_) => (1, 1),
+                DiffOperation::KeepRun(n) => (*n as usize, *n as usize),
+                DiffOperation::Insert(_) => (0, 1),
+                DiffOperation::Delete => (1, 0),
+            };
+            
+            // a byte present on both sides; an offset already passed over
+            // by a deletion or insertion lands on its first position
+            if consumed > 0 && produced > 0 {
+                let (from, to) = if from_left { (pos_a, pos_b) } else { (pos_b, pos_a) };
+                if offset < from + consumed {
+                    return Some(to + offset.saturating_sub(from));
                 }
-                DiffOperation::Delete => {
-                    if input_pos >= input.len() {
-                        return Err(DiffError::InvalidScript(format!("script extends beyond input at operation {} (Delete): input_pos={}, input_len={}", op_idx, input_pos, input.len())));
+            }
+            pos_a += consumed;
+            pos_b += produced;
+        }
+        Some(to_len)
+    }
+    
+    /// encodes the script as a compact binary delta for
+    /// [`crate::patch::apply_binary_delta`]
+    /// 
+    /// runs of kept, deleted, inserted or substituted bytes each become one
+    /// varint-prefixed command, with inserted and substituted bytes stored
+    /// as is, so a small change to a large input gives a delta of a few
+    /// bytes. like `compact`, encoding walks the finished script.
+    pub fn to_binary_delta(&self) -> Vec<u8> {
+        crate::patch::encode_binary_delta(&self.operations, self.original_len_a, self.original_len_b)
+    }
+    
+    /// run-length encodes runs of `Keep` into `KeepRun` to save memory
+    /// 
+    /// the edit distance and reconstruction are unchanged. compaction walks
+    /// the finished script, so its cost depends on the result; run it outside
+    /// any timing-sensitive section.
+    pub fn compact(&mut self) {
+        let mut compacted = Vec::with_capacity(self.operations.len());
+        let mut run: u32 = 0;
+        
+        for op in &self.operations {
+            match op {
+                DiffOperation::Keep | DiffOperation::KeepRun(_) => {
+                    let mut kept = op.kept_bytes() as u32;
+                    // split runs that would overflow the counter
+                    while kept > u32::MAX - run {
+                        kept -= u32::MAX - run;
+                        compacted.push(DiffOperation::KeepRun(u32::MAX));
+                        run = 0;
                     }
-                    input_pos += 1;
-                    // no result push for delete
+                    run += kept;
                 }
-                DiffOperation::Substitute(byte) => {
-                    if input_pos >= input.len() {
-                        return Err(DiffError::InvalidScript(format!("script extends beyond input at operation {} (Substitute): input_pos={}, input_len={}", op_idx, input_pos, input.len())));
+                other => {
+                    if run > 0 {
+                        compacted.push(DiffOperation::KeepRun(run));
+                        run = 0;
                     }
-                    result.push(*byte);
-                    input_pos += 1;
+                    compacted.push(*other);
+                }
+            }
+        }
+        if run > 0 {
+            compacted.push(DiffOperation::KeepRun(run));
+        }
+        
+        compacted.shrink_to_fit();
+        self.operations = compacted;
+    }
+    
+    /// number of operations with every `KeepRun` counted byte by byte
+    /// 
+    /// the same for a script and its compacted form, and the operation
+    /// total reported by every format.
+    pub fn expanded_operation_count(&self) -> usize {
+        self.operations.iter()
+            .map(|op| op.kept_bytes() + op.cost())
+            .sum()
+    }
+    
+    /// true if no single-byte `Keep` operations remain in the script
+    pub fn is_compacted(&self) -> bool {
+        !self.operations.iter().any(|op| matches!(op, DiffOperation::Keep))
+    }
+    
+    /// stable 64-bit hash of the script, lengths and edit distance
+    /// 
+    /// uses fnv-1a, which has no random seed, over little-endian integers,
+    /// so the value is the same across runs, processes and platforms and
+    /// can key a cache. operations are hashed as stored: a compacted script
+    /// fingerprints differently from its uncompacted form. not a
+    /// cryptographic hash.
+    pub fn fingerprint(&self) -> u64 {
+        use std::hash::Hasher;
+        
+        let mut hasher = fnv::FnvHasher::default();
+        hasher.write(&(self.original_len_a as u64).to_le_bytes());
+        hasher.write(&(self.original_len_b as u64).to_le_bytes());
+        hasher.write(&(self.edit_distance as u64).to_le_bytes());
+        hasher.write(&(self.operations.len() as u64).to_le_bytes());
+        for op in &self.operations {
+            match *op {
+                DiffOperation::Keep => hasher.write_u8(0),
+                DiffOperation::Insert(byte) => hasher.write(&[1, byte]),
+                DiffOperation::Delete => hasher.write_u8(2),
+                DiffOperation::Substitute(byte) => hasher.write(&[3, byte]),
+                DiffOperation::KeepRun(n) => {
+                    hasher.write_u8(4);
+                    hasher.write(&n.to_le_bytes());
+                }
+            }
+        }
+        
+        hasher.finish()
+    }
+    
+    /// constant-time equality with another result
+    /// 
+    /// agrees with `==`, but checks the lengths, the edit distance and every
+    /// operation up to the longer script's length (padding the shorter one)
+    /// before combining the outcomes, so the time taken depends only on the
+    /// two script lengths and not on where, or whether, they differ.
+    pub fn ct_eq(&self, other: &DiffResult) -> Choice {
+        let mut equal = (self.original_len_a as u64).ct_eq(&(other.original_len_a as u64))
+            & (self.original_len_b as u64).ct_eq(&(other.original_len_b as u64))
+            & (self.edit_distance as u64).ct_eq(&(other.edit_distance as u64))
+            & (self.operations.len() as u64).ct_eq(&(other.operations.len() as u64));
+        
+        let len = self.operations.len().max(other.operations.len());
+        for i in 0..len {
+            let a = encode_operation(self.operations.get(i));
+            let b = encode_operation(other.operations.get(i));
+            equal &= a.ct_eq(&b);
+        }
+        
+        equal
+    }
+    
+    /// classifies the change by which kinds of modification the script holds
+    pub fn classify(&self) -> ChangeKind {
+        let has = |kind: fn(&DiffOperation) -> bool| self.operations.iter().any(kind);
+        let inserts = has(|op| matches!(op, DiffOperation::Insert(_)));
+        let deletes = has(|op| matches!(op, DiffOperation::Delete));
+        let substitutes = has(|op| matches!(op, DiffOperation::Substitute(_)));
+        
+        match (inserts, deletes, substitutes) {
+            (false, false, false) => ChangeKind::Identical,
+            (true, false, false) => ChangeKind::PureInsertion,
+            (false, true, false) => ChangeKind::PureDeletion,
+            (false, false, true) => ChangeKind::PureSubstitution,
+            _ => ChangeKind::Mixed,
+        }
+    }
+    
+    /// compares this edit script against another, operation by operation
+    /// 
+    /// meant for checking that two diff implementations agree. operations
+    /// are compared as stored, so a compacted script diverges from its
+    /// uncompacted form at the first `KeepRun`; compact both sides first to
+    /// ignore that difference.
+    pub fn compare_scripts(&self, other: &DiffResult) -> ScriptComparison {
+        let first_divergence = self.operations.iter()
+            .zip(&other.operations)
+            .position(|(a, b)| a != b)
+            .or_else(|| {
+                let shorter = self.operations.len().min(other.operations.len());
+                (self.operations.len() != other.operations.len()).then_some(shorter)
+            });
+        
+        ScriptComparison {
+            identical: first_divergence.is_none(),
+            first_divergence,
+            edit_distance_delta: other.edit_distance as isize - self.edit_distance as isize,
+        }
+    }
+}
+
+/// runs `operations` over `input`, checking the script stays within it
+/// 
+/// shared by `DiffResult::apply_to` and `Patch::apply`; callers check the
+/// expected input length first.
+pub(crate) fn apply_operations(operations: &[DiffOperation], input: &[u8]) -> Result<Vec<u8>, DiffError> {
+    let mut result = Vec::new();
+    let mut input_pos = 0;
+
+    for op in operations {
+        match op {
+            DiffOperation::Keep => {
+                if input_pos >= input.len() {
+                    return Err(DiffError::ScriptOverrunsInput);
+                }
+                result.push(input[input_pos]);
+                input_pos += 1;
+            }
+            DiffOperation::KeepRun(n) => {
+                let end = input_pos + *n as usize;
+                if end > input.len() {
+                    return Err(DiffError::ScriptOverrunsInput);
                 }
+                result.extend_from_slice(&input[input_pos..end]);
+                input_pos = end;
+            }
+            DiffOperation::Insert(byte) => {
+                result.push(*byte);
+                // no input_pos increment for insert
+            }
+            DiffOperation::Delete => {
+                if input_pos >= input.len() {
+                    return Err(DiffError::ScriptOverrunsInput);
+                }
+                input_pos += 1;
+                // no result push for delete
+            }
+            DiffOperation::Substitute(byte) => {
+                if input_pos >= input.len() {
+                    return Err(DiffError::ScriptOverrunsInput);
+                }
+                result.push(*byte);
+                input_pos += 1;
             }
         }
+    }
+
+    if input_pos != input.len() {
+        return Err(DiffError::ScriptUnderconsumesInput {
+            remaining: input.len() - input_pos,
+        });
+    }
 
-        if input_pos != input.len() {
-            return Err(DiffError::InvalidScript(format!("script does not consume entire input: consumed {}, expected {}", input_pos, input.len())));
+    Ok(result)
+}
+
+/// length of what `apply_operations` would produce from an input of
+/// `input_len` bytes, failing with the same errors, without building it
+pub(crate) fn operations_output_len(operations: &[DiffOperation], input_len: usize) -> Result<usize, DiffError> {
+    let mut output_len = 0;
+    let mut input_pos = 0;
+
+    for op in operations {
+        let (consumed, produced) = match op {
+            DiffOperation::Keep | DiffOperation::Substitute(_) => (1, 1),
+            DiffOperation::KeepRun(n) => (*n as usize, *n as usize),
+            DiffOperation::Insert(_) => (0, 1),
+            DiffOperation::Delete => (1, 0),
+        };
+        if consumed > input_len - input_pos {
+            return Err(DiffError::ScriptOverrunsInput);
         }
+        input_pos += consumed;
+        output_len += produced;
+    }
 
-        Ok(result)
+    if input_pos != input_len {
+        return Err(DiffError::ScriptUnderconsumesInput {
+            remaining: input_len - input_pos,
+        });
     }
+
+    Ok(output_len)
+}
+
+/// packs an operation into one word for `DiffResult::ct_eq`
+/// 
+/// tag in the high half, payload in the low half; 0 pads past the end.
+fn encode_operation(op: Option<&DiffOperation>) -> u64 {
+    match op {
+        None => 0,
+        Some(DiffOperation::Keep) => 1 << 32,
+        Some(DiffOperation::Insert(byte)) => 2 << 32 | u64::from(*byte),
+        Some(DiffOperation::Delete) => 3 << 32,
+        Some(DiffOperation::Substitute(byte)) => 4 << 32 | u64::from(*byte),
+        Some(DiffOperation::KeepRun(n)) => 5 << 32 | u64::from(*n),
+    }
+}
+
+/// one step of a line-level edit script from `ConstantTimeDiff::diff_lines`
+/// 
+/// lines include their trailing `\n`, if any.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineOperation {
+    /// keep the next line of the first input
+    Keep,
+    /// insert this line
+    Insert(Vec<u8>),
+    /// drop the next line of the first input
+    Delete,
+    /// replace the next line of the first input with this one
+    Replace(Vec<u8>),
+}
+
+impl LineOperation {
+    /// whether the operation changes content
+    pub fn is_modification(&self) -> bool {
+        !matches!(self, LineOperation::Keep)
+    }
+}
+
+/// line-level diff: operations over lines rather than bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineDiffResult {
+    /// sequence of line operations turning the first input into the second
+    pub operations: Vec<LineOperation>,
+    /// number of inserted, deleted or replaced lines
+    pub edit_distance: usize,
+    /// line count of the first input
+    pub original_lines_a: usize,
+    /// line count of the second input
+    pub original_lines_b: usize,
+}
+
+impl LineDiffResult {
+    /// rebuilds the second input from the first
+    /// 
+    /// `input` is split the same way as in `diff_lines` and must have
+    /// `original_lines_a` lines.
+    pub fn apply_to(&self, input: &[u8]) -> Result<Vec<u8>, DiffError> {
+        let lines = split_lines(input);
+        if lines.len() != self.original_lines_a {
+            return Err(DiffError::InputLengthMismatch {
+                expected: self.original_lines_a,
+                got: lines.len(),
+            });
+        }
+        
+        let mut output = Vec::with_capacity(input.len());
+        let mut remaining = lines.into_iter();
+        for op in &self.operations {
+            if matches!(op, LineOperation::Keep | LineOperation::Delete | LineOperation::Replace(_)) {
+                let line = remaining.next().ok_or_else(|| {
+                    DiffError::InvalidScript("line script extends beyond input".to_string())
+                })?;
+                if *op == LineOperation::Keep {
+                    output.extend_from_slice(line);
+                }
+            }
+            if let LineOperation::Insert(line) | LineOperation::Replace(line) = op {
+                output.extend_from_slice(line);
+            }
+        }
+        
+        if remaining.next().is_some() {
+            return Err(DiffError::InvalidScript("line script does not consume entire input".to_string()));
+        }
+        Ok(output)
+    }
+}
+
+/// one step of a token-level edit script from `ConstantTimeDiff::diff_tokens`
+/// 
+/// `left` and `right` are indices into the first and second token slices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenOperation {
+    /// the tokens at both indices are equal
+    Keep { left: usize, right: usize },
+    /// the left token is replaced by the right one
+    Substitute { left: usize, right: usize },
+    /// the left token is dropped
+    Delete { left: usize },
+    /// the right token is added
+    Insert { right: usize },
+}
+
+impl TokenOperation {
+    /// whether the operation changes content
+    pub fn is_modification(&self) -> bool {
+        !matches!(self, TokenOperation::Keep { .. })
+    }
+}
+
+/// token-level diff over caller-supplied tokens of type `T`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenDiffResult<T> {
+    /// operations in order, referring to tokens by index
+    pub operations: Vec<TokenOperation>,
+    /// number of substituted, deleted or inserted tokens
+    pub edit_distance: usize,
+    /// token count of the first input
+    pub original_len_a: usize,
+    /// token count of the second input
+    pub original_len_b: usize,
+    #[serde(skip)]
+    tokens: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> TokenDiffResult<T> {
+    /// wraps a finished script
+    pub(crate) fn new(operations: Vec<TokenOperation>, original_len_a: usize, original_len_b: usize) -> Self {
+        let edit_distance = operations.iter().filter(|op| op.is_modification()).count();
+        Self {
+            operations,
+            edit_distance,
+            original_len_a,
+            original_len_b,
+            tokens: std::marker::PhantomData,
+        }
+    }
+
+    /// the right-hand tokens, rebuilt by following the script
+    /// 
+    /// kept tokens come from `left` and the rest from `right`, so this is a
+    /// check that the script is consistent with both slices.
+    pub fn apply_to(&self, left: &[T], right: &[T]) -> Result<Vec<T>, DiffError>
+    where
+        T: Clone,
+    {
+        for (expected, got) in [(self.original_len_a, left.len()), (self.original_len_b, right.len())] {
+            if expected != got {
+                return Err(DiffError::InputLengthMismatch { expected, got });
+            }
+        }
+        
+        let token = |tokens: &[T], index: usize| tokens.get(index).cloned().ok_or_else(|| {
+            DiffError::InvalidScript(format!("token index {} out of range", index))
+        });
+        self.operations.iter()
+            .filter_map(|op| match *op {
+                TokenOperation::Keep { left: index, .. } => Some(token(left, index)),
+                TokenOperation::Substitute { right: index, .. } | TokenOperation::Insert { right: index } => {
+                    Some(token(right, index))
+                }
+                TokenOperation::Delete { .. } => None,
+            })
+            .collect()
+    }
+}
+
+/// splits after each `\n`; a final line without one is still a line
+pub(crate) fn split_lines(input: &[u8]) -> Vec<&[u8]> {
+    input.split_inclusive(|&byte| byte == b'\n').collect()
+}
+
+/// a stretch of the first input replaced by new bytes, from
+/// `DiffResult::change_regions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeRegion {
+    /// position in the first input where the region starts
+    pub left_offset: usize,
+    /// bytes of the first input the region removes
+    pub left_bytes: Vec<u8>,
+    /// bytes the region puts in their place
+    pub right_bytes: Vec<u8>,
+}
+
+/// overall nature of a change, from `DiffResult::classify`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    /// no modifications
+    Identical,
+    /// only insertions, e.g. appended content
+    PureInsertion,
+    /// only deletions, e.g. truncation
+    PureDeletion,
+    /// only substitutions, so both inputs have the same length
+    PureSubstitution,
+    /// more than one kind of modification
+    Mixed,
+}
+
+impl std::fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeKind::Identical => write!(f, "identical"),
+            ChangeKind::PureInsertion => write!(f, "pure insertion"),
+            ChangeKind::PureDeletion => write!(f, "pure deletion"),
+            ChangeKind::PureSubstitution => write!(f, "pure substitution"),
+            ChangeKind::Mixed => write!(f, "mixed"),
+        }
+    }
+}
+
+/// outcome of `DiffResult::compare_scripts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptComparison {
+    /// true if both scripts contain the same operations in the same order
+    pub identical: bool,
+    /// index of the first differing operation; the shorter script's length
+    /// when one script is a prefix of the other
+    pub first_divergence: Option<usize>,
+    /// other edit distance minus this one
+    pub edit_distance_delta: isize,
 }
 
 /// security configuration for diff computation
@@ -163,9 +809,108 @@ pub struct SecurityConfig {
     /// target padding size when pad_inputs is enabled
     pub padding_size: Option<usize>,
     /// whether to validate inputs for malicious patterns
+    /// 
+    /// with padding on and a `Fixed` padding byte, inputs containing that
+    /// byte are rejected with `InvalidInput`, since they would be ambiguous
+    /// with the padded region. the default `OutOfBand` padding reserves no
+    /// byte, so nothing is rejected unless `Fixed` is picked.
     pub validate_inputs: bool,
     /// maximum edit distance to compute (prevents excessive computation)
     pub max_edit_distance: Option<usize>,
+    /// bytes used to fill the padded region when pad_inputs is enabled
+    #[serde(default)]
+    pub padding_byte: PaddingStrategy,
+    /// timing guarantees required; gates features that trade them away
+    #[serde(default)]
+    pub timing_protection: TimingProtection,
+    /// skip the matrix when the inputs are byte-identical
+    /// 
+    /// the comparison itself is constant-time, but returning early reveals
+    /// whether the inputs were identical through total run time.
+    #[serde(default)]
+    pub fast_identical_check: bool,
+    /// maximum length of the operations list, checked after extraction
+    /// 
+    /// near-identical large inputs still produce one operation per byte;
+    /// this keeps formatters and serializers from being handed a huge script.
+    #[serde(default)]
+    pub max_operations: Option<usize>,
+    /// length-bounded trimming of a shared prefix and suffix before the
+    /// matrix is built; see `AffixTrim`
+    #[serde(default)]
+    pub affix_trim: AffixTrim,
+    /// largest edit matrix to allocate, at 4 bytes per cell (none = unlimited)
+    /// 
+    /// the matrix has `(m + 1) * (n + 1)` cells for padded lengths `m` and
+    /// `n`; larger diffs fail with `ComputationLimitExceeded` instead of
+    /// exhausting memory.
+    #[serde(default = "default_max_matrix_bytes")]
+    pub max_matrix_bytes: Option<usize>,
+    /// let the diagonal step replace one byte with another
+    /// 
+    /// when false the diagonal is only taken over equal symbols, so every
+    /// change becomes a delete plus an insert and scripts contain no
+    /// `Substitute`, like the classic longest-common-subsequence `diff`.
+    /// the edit distance then counts both halves of each change.
+    #[serde(default = "default_allow_substitution")]
+    pub allow_substitution: bool,
+}
+
+/// default `max_matrix_bytes`: 512mb
+/// 
+/// room for two inputs padded to 8kb, whose 8193 x 8193 matrix is just
+/// over 256mb.
+pub const DEFAULT_MAX_MATRIX_BYTES: usize = 512 * 1024 * 1024;
+
+/// serde default for `SecurityConfig::max_matrix_bytes`
+fn default_max_matrix_bytes() -> Option<usize> {
+    Some(DEFAULT_MAX_MATRIX_BYTES)
+}
+
+/// serde default for `SecurityConfig::allow_substitution`
+fn default_allow_substitution() -> bool {
+    true
+}
+
+/// whether a shared prefix and suffix are trimmed before building the matrix
+/// 
+/// trimming to the actual common prefix would make the matrix size, and so
+/// the run time, reveal how far the inputs agree. `LengthBounded(k)` instead
+/// compares exactly `k` bytes at each end in constant time and trims a window
+/// only if all `k` bytes matched. whether each window was trimmed is still
+/// visible through timing: one bit per window, leaked by design. byte
+/// granularity only; token diffs ignore this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AffixTrim {
+    /// always build the full matrix
+    #[default]
+    None,
+    /// trim a matching `k`-byte prefix and/or suffix window when both inputs
+    /// are at least `2 * k` bytes long
+    LengthBounded(usize),
+}
+
+/// how the padded region beyond the real inputs is filled
+/// 
+/// whatever the strategy, both inputs get the same byte at each padded
+/// position so the extra region diffs to all keeps. the default is
+/// `OutOfBand`, which reserves no byte, so the validating presets still
+/// accept any binary input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PaddingStrategy {
+    /// fill with a fixed byte, such as `0xFF`
+    /// 
+    /// with `validate_inputs` on, inputs containing the byte are rejected.
+    Fixed(u8),
+    /// fill with cryptographically random bytes, drawn fresh for every call
+    Random,
+    /// caller guarantees the padding is never read as content
+    /// 
+    /// the edit script is always extracted from the original lengths, so the
+    /// padded region never reaches the output; the fill value (zero) is
+    /// irrelevant under that assumption.
+    #[default]
+    OutOfBand,
 }
 
 impl Default for SecurityConfig {
@@ -176,6 +921,13 @@ impl Default for SecurityConfig {
             padding_size: None, // auto-determine based on inputs
             validate_inputs: true,
             max_edit_distance: None, // no limit by default
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
 }
@@ -189,6 +941,13 @@ impl SecurityConfig {
             padding_size: Some(4 * 1024), // fixed padding
             validate_inputs: true,
             max_edit_distance: Some(1024), // bounded computation
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Strict,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
 
@@ -200,6 +959,13 @@ impl SecurityConfig {
             padding_size: None,
             validate_inputs: true,
             max_edit_distance: None,
+            padding_byte: PaddingStrategy::default(),
+            timing_protection: TimingProtection::Moderate,
+            fast_identical_check: false,
+            max_operations: None,
+            affix_trim: AffixTrim::None,
+            max_matrix_bytes: Some(DEFAULT_MAX_MATRIX_BYTES),
+            allow_substitution: true,
         }
     }
 
@@ -237,14 +1003,24 @@ impl SecurityConfig {
 pub enum DiffError {
     /// input size exceeds configured limits
     InputTooLarge { size: usize, limit: usize },
+    /// an input is longer than the configured `padding_size` it should be padded to
+    PaddingTooSmall { input_size: usize, padding_size: usize },
     /// invalid input format or content
     InvalidInput(String),
     /// edit script is malformed or inconsistent
     InvalidScript(String),
+    /// input passed to `apply_to` is not the length the script was built for
+    InputLengthMismatch { expected: usize, got: usize },
+    /// edit script consumes more bytes than the input has
+    ScriptOverrunsInput,
+    /// edit script finishes with input bytes left over
+    ScriptUnderconsumesInput { remaining: usize },
     /// computation exceeded configured limits
     ComputationLimitExceeded(String),
     /// internal algorithm error (should not occur in normal operation)
     AlgorithmError(String),
+    /// requested operation would weaken the configured timing protection
+    TimingProtectionViolation(String),
 }
 
 impl std::fmt::Display for DiffError {
@@ -253,12 +1029,27 @@ impl std::fmt::Display for DiffError {
             DiffError::InputTooLarge { size, limit } => {
                 write!(f, "input size {} exceeds limit {}", size, limit)
             }
+            DiffError::PaddingTooSmall { input_size, padding_size } => {
+                write!(f, "input size {} exceeds padding size {}", input_size, padding_size)
+            }
             DiffError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
             DiffError::InvalidScript(msg) => write!(f, "invalid script: {}", msg),
+            DiffError::InputLengthMismatch { expected, got } => {
+                write!(f, "invalid input: input length mismatch: expected {}, got {}", expected, got)
+            }
+            DiffError::ScriptOverrunsInput => {
+                write!(f, "invalid script: script extends beyond input")
+            }
+            DiffError::ScriptUnderconsumesInput { remaining } => {
+                write!(f, "invalid script: script does not consume entire input: {} bytes remaining", remaining)
+            }
             DiffError::ComputationLimitExceeded(msg) => {
                 write!(f, "computation limit exceeded: {}", msg)
             }
             DiffError::AlgorithmError(msg) => write!(f, "algorithm error: {}", msg),
+            DiffError::TimingProtectionViolation(msg) => {
+                write!(f, "timing protection violation: {}", msg)
+            }
         }
     }
 }
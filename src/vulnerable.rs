@@ -196,7 +196,7 @@ impl VulnerableDiff {
                     if diff.abs() < 32 {
                         // "similar" bytes get different treatment
                         // artificial timing variation based on content
-                        for _ in 0..((diff.abs() as usize) % 3) {
+                        for _ in 0..(diff.unsigned_abs() as usize % 3) {
                             // busywork that depends on byte values
                             std::hint::black_box(diff * diff);
                         }
@@ -256,15 +256,11 @@ impl VulnerableDiff {
                 
                 // analyze surrounding context - timing depends on content
                 for offset in 1..=3 {
-                    if i >= offset && j >= offset {
-                        if a[i - offset] == b[j - offset] {
-                            similarity_score += 1;
-                        }
+                    if i >= offset && j >= offset && a[i - offset] == b[j - offset] {
+                        similarity_score += 1;
                     }
-                    if i + offset < a.len() && j + offset < b.len() {
-                        if a[i + offset] == b[j + offset] {
-                            similarity_score += 1;
-                        }
+                    if i + offset < a.len() && j + offset < b.len() && a[i + offset] == b[j + offset] {
+                        similarity_score += 1;
                     }
                 }
                 
@@ -0,0 +1,62 @@
+//! browser bindings via wasm-bindgen
+//!
+//! exposes a single `wasm_diff` entry point wrapping `DiffBuilder`. the
+//! manifest doesn't list `cdylib` (it would break `no_std` builds), so ask
+//! for it on the command line and run wasm-bindgen on the result:
+//!
+//! ```text
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/ctdiff.wasm
+//! ```
+//!
+//! then call it from javascript with `Uint8Array` inputs:
+//!
+//! ```text
+//! import init, { wasm_diff } from "./pkg/ctdiff.js";
+//! await init();
+//! const html = wasm_diff(left, right, "balanced", "html");
+//! ```
+//!
+//! the timing and attack modules are not built for wasm32 since the target
+//! has no usable clock; the diff itself never reads the clock.
+
+use crate::{
+    builder::DiffBuilder,
+    error::{Error, Result},
+    formats::OutputFormat,
+    security::SecurityLevel,
+};
+use wasm_bindgen::prelude::*;
+
+/// diffs two byte arrays and returns the formatted output
+///
+/// `security_level` is `maximum`, `balanced` or `fast`; `format` accepts any
+/// name understood by `OutputFormat`. errors are returned as strings.
+#[wasm_bindgen]
+pub fn wasm_diff(left: &[u8], right: &[u8], security_level: &str, format: &str) -> std::result::Result<String, JsValue> {
+    diff_to_string(left, right, security_level, format)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// wrapper logic behind `wasm_diff`, callable from native code and tests
+pub fn diff_to_string(left: &[u8], right: &[u8], security_level: &str, format: &str) -> Result<String> {
+    let level = parse_security_level(security_level)?;
+    let format: OutputFormat = format.parse()?;
+
+    let diff = DiffBuilder::new()
+        .security_level(level)
+        .output_format(format)
+        .build()?;
+
+    diff.compare(left, right)?.format()
+}
+
+/// parses a security level name as used by the cli
+fn parse_security_level(level: &str) -> Result<SecurityLevel> {
+    match level.to_lowercase().as_str() {
+        "maximum" => Ok(SecurityLevel::Maximum),
+        "balanced" => Ok(SecurityLevel::Balanced),
+        "fast" => Ok(SecurityLevel::Fast),
+        _ => Err(Error::configuration(format!("unknown security level: {}", level))),
+    }
+}
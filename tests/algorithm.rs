@@ -1,5 +1,6 @@
 use ctdiff::{ConstantTimeDiff, constant_time_diff};
-use ctdiff::types::{SecurityConfig, DiffResult, DiffOperation, DiffError};
+use ctdiff::types::{AffixTrim, SecurityConfig, DiffResult, DiffOperation, DiffError, PaddingStrategy};
+use ctdiff::security::TimingProtection;
 use std::time::Instant;
 
 // helper function to create no-padding config for cleaner tests
@@ -10,6 +11,13 @@ fn no_padding_config() -> SecurityConfig {
         padding_size: None,
         validate_inputs: true,
         max_edit_distance: None,
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     }
 }
 
@@ -149,6 +157,7 @@ fn test_deletion_in_middle() {
             println!("Op: {:?}, pos_a={}, pos_b={}", op, pos_a, pos_b);
             match op {
                 DiffOperation::Keep => { pos_a += 1; pos_b += 1; }
+                DiffOperation::KeepRun(n) => { pos_a += *n as usize; pos_b += *n as usize; }
                 DiffOperation::Insert(_) => { pos_b += 1; modifications += 1; }
                 DiffOperation::Delete => { pos_a += 1; modifications += 1; }
                 DiffOperation::Substitute(_) => { pos_a += 1; pos_b += 1; modifications += 1; }
@@ -222,6 +231,13 @@ fn test_security_configurations() {
         padding_size: Some(16), // small fixed padding
         validate_inputs: true,
         max_edit_distance: Some(1024),
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     let differ2 = ConstantTimeDiff::new(secure_config);
     let result2 = differ2.diff(a, b).unwrap();
@@ -243,6 +259,13 @@ fn test_input_size_limits() {
         padding_size: None,
         validate_inputs: true,
         max_edit_distance: None,
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     
     let differ = ConstantTimeDiff::new(config);
@@ -265,6 +288,13 @@ fn test_edit_distance_limits() {
         padding_size: None,
         validate_inputs: true,
         max_edit_distance: Some(15), // allow small inputs
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     
     let differ = ConstantTimeDiff::new(config);
@@ -280,6 +310,13 @@ fn test_edit_distance_limits() {
         padding_size: None,
         validate_inputs: true,
         max_edit_distance: Some(5), // very restrictive
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     let differ_restrictive = ConstantTimeDiff::new(config_restrictive);
     let a = vec![b'a'; 10];
@@ -297,6 +334,13 @@ fn test_padding_functionality() {
         padding_size: Some(16),  // smaller padding
         validate_inputs: true,
         max_edit_distance: None,
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     
     let differ = ConstantTimeDiff::new(config);
@@ -310,6 +354,336 @@ fn test_padding_functionality() {
     assert_eq!(reconstructed, b"def");
 }
 
+#[test]
+fn test_padding_too_small() {
+    let config = SecurityConfig {
+        max_input_size: 100,
+        pad_inputs: true,
+        padding_size: Some(8),
+        validate_inputs: true,
+        max_edit_distance: None,
+        padding_byte: PaddingStrategy::default(),
+        timing_protection: TimingProtection::Moderate,
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
+    };
+    
+    let differ = ConstantTimeDiff::new(config);
+    // within max_input_size, so the padding config is to blame
+    let result = differ.diff(&[b'x'; 20], b"small");
+    assert_eq!(result.unwrap_err(), DiffError::PaddingTooSmall { input_size: 20, padding_size: 8 });
+    assert!(differ.diff(b"abc", b"def").is_ok());
+}
+
+#[test]
+fn test_padding_with_0xff_content() {
+    // content that looks like the default padding byte, right up to the boundary
+    let cases: [(&[u8], &[u8]); 4] = [
+        (b"ab\xff\xff", b"ab\xff"),
+        (b"\xff\xff\xff", b"\xff\xff\xff\xff\xff"),
+        (b"x\xffy", b"\xffy\xff"),
+        (b"", b"\xff"),
+    ];
+    let strategies = [
+        PaddingStrategy::Fixed(0xFF),
+        PaddingStrategy::Fixed(0x00),
+        PaddingStrategy::Random,
+        PaddingStrategy::OutOfBand,
+    ];
+    let unpadded = ConstantTimeDiff::new(no_padding_config());
+    
+    for strategy in strategies {
+        let config = SecurityConfig {
+            pad_inputs: true,
+            padding_size: Some(16),
+            padding_byte: strategy,
+            timing_protection: TimingProtection::Moderate,
+            // validation would reject content matching the padding byte
+            validate_inputs: false,
+            ..no_padding_config()
+        };
+        let differ = ConstantTimeDiff::new(config);
+        
+        for (a, b) in cases {
+            let expected = unpadded.diff(a, b).unwrap().edit_distance;
+            let result = differ.diff(a, b).unwrap();
+            
+            assert_eq!(result.edit_distance, expected, "{:?} on {:?} -> {:?}", strategy, a, b);
+            assert_eq!(result.original_len_a, a.len());
+            assert_eq!(result.original_len_b, b.len());
+            assert!(result.is_valid());
+            assert_eq!(result.apply_to(a).unwrap(), b);
+        }
+    }
+}
+
+#[test]
+fn test_validate_inputs_rejects_padding_byte() {
+    let padded = SecurityConfig {
+        pad_inputs: true,
+        padding_size: Some(16),
+        padding_byte: PaddingStrategy::Fixed(0xFF),
+        ..no_padding_config()
+    };
+    let differ = ConstantTimeDiff::new(padded.clone());
+    
+    // the scan finds the byte wherever it is, on either side
+    for (a, b) in [(&b"\xffabc"[..], &b"abc"[..]), (b"abc", b"ab\xff"), (b"\xff", b"\xff")] {
+        assert!(matches!(differ.diff(a, b), Err(DiffError::InvalidInput(_))), "{:?} -> {:?}", a, b);
+    }
+    assert!(differ.diff(b"abc", b"abd").is_ok());
+    
+    // a different fixed byte is what gets reserved
+    let hash_padded = ConstantTimeDiff::new(SecurityConfig { padding_byte: PaddingStrategy::Fixed(b'#'), ..padded.clone() });
+    assert!(hash_padded.diff(b"\xff", b"x").is_ok());
+    assert!(matches!(hash_padded.diff(b"#", b"x"), Err(DiffError::InvalidInput(_))));
+    
+    // nothing is reserved without validation, without padding, or with
+    // padding that can't be mistaken for content, which the presets use
+    let unvalidated = SecurityConfig { validate_inputs: false, ..padded.clone() };
+    let random = SecurityConfig { padding_byte: PaddingStrategy::Random, ..padded.clone() };
+    let presets = [SecurityConfig::default(), SecurityConfig::maximum_security(), SecurityConfig::balanced()];
+    for config in [unvalidated, no_padding_config(), random].into_iter().chain(presets) {
+        let result = ConstantTimeDiff::new(config).diff(b"a\xffb", b"ab").unwrap();
+        assert_eq!(result.apply_to(b"a\xffb").unwrap(), b"ab");
+    }
+}
+
+#[test]
+fn test_affix_trim_shrinks_matrix_on_full_window_match() {
+    let trimming = SecurityConfig {
+        max_input_size: 4096,
+        affix_trim: AffixTrim::LengthBounded(32),
+        ..no_padding_config()
+    };
+    // progress is reported once per matrix row, so calls count the rows
+    let rows = |config: &SecurityConfig, a: &[u8], b: &[u8]| {
+        let mut calls = 0;
+        let result = ConstantTimeDiff::new(config.clone())
+            .diff_with_progress(a, b, |_| calls += 1)
+            .unwrap();
+        assert_eq!(result.apply_to(a).unwrap(), b);
+        (calls, result)
+    };
+    
+    let prefix = [b'p'; 100];
+    let suffix = [b's'; 100];
+    let a = [&prefix[..], b"old", &suffix[..]].concat();
+    let b = [&prefix[..], b"newer", &suffix[..]].concat();
+    
+    let untrimmed_config = SecurityConfig { affix_trim: AffixTrim::None, ..trimming.clone() };
+    let (full_rows, full) = rows(&untrimmed_config, &a, &b);
+    let (trimmed_rows, trimmed) = rows(&trimming, &a, &b);
+    // both 32-byte windows go, taking 64 rows with them
+    assert_eq!(trimmed_rows, full_rows - 64);
+    assert_eq!(trimmed.edit_distance, full.edit_distance);
+    assert_eq!(trimmed.original_len_a, a.len());
+    
+    // one differing byte inside a window keeps that whole window
+    let mut b_prefix_differs = b.clone();
+    b_prefix_differs[31] = b'x';
+    assert_eq!(rows(&trimming, &a, &b_prefix_differs).0, full_rows - 32);
+    let mut b_both_differ = b_prefix_differs.clone();
+    let last = b_both_differ.len() - 32;
+    b_both_differ[last] = b'x';
+    assert_eq!(rows(&trimming, &a, &b_both_differ).0, full_rows);
+    
+    // a difference just past the window doesn't stop the trim
+    let mut b_past_window = b.clone();
+    b_past_window[32] = b'x';
+    assert_eq!(rows(&trimming, &a, &b_past_window).0, full_rows - 64);
+    
+    // inputs shorter than two windows are never trimmed
+    let (short_rows, _) = rows(&trimming, &prefix[..40], &prefix[..50]);
+    assert_eq!(short_rows, rows(&untrimmed_config, &prefix[..40], &prefix[..50]).0);
+}
+
+#[test]
+fn test_max_matrix_bytes() {
+    // a 10x10 diff needs an 11x11 matrix of u32
+    let matrix_bytes = 11 * 11 * 4;
+    let with_limit = |limit| ConstantTimeDiff::new(SecurityConfig {
+        max_matrix_bytes: Some(limit),
+        ..no_padding_config()
+    });
+    let (a, b) = (b"abcdefghij", b"abcxefghiy");
+    
+    let result = with_limit(matrix_bytes).diff(a, b).unwrap();
+    assert_eq!(result.apply_to(a).unwrap(), b);
+    
+    match with_limit(matrix_bytes - 1).diff(a, b) {
+        Err(DiffError::ComputationLimitExceeded(message)) => {
+            assert!(message.contains(&matrix_bytes.to_string()), "{}", message);
+        }
+        other => panic!("expected a matrix limit error, got {:?}", other),
+    }
+    
+    // padding counts toward the matrix
+    let padded = ConstantTimeDiff::new(SecurityConfig {
+        pad_inputs: true,
+        padding_size: Some(16),
+        validate_inputs: false,
+        max_matrix_bytes: Some(matrix_bytes),
+        ..no_padding_config()
+    });
+    assert!(matches!(padded.diff(a, b), Err(DiffError::ComputationLimitExceeded(_))));
+    
+    // the default limit turns a huge matrix into an error, not an abort
+    let large = ConstantTimeDiff::new(SecurityConfig {
+        max_input_size: 1 << 20,
+        max_matrix_bytes: Some(ctdiff::security::DEFAULT_MAX_MATRIX_BYTES),
+        ..no_padding_config()
+    });
+    let big = vec![b'a'; 1 << 15];
+    assert!(matches!(large.diff(&big, &big), Err(DiffError::ComputationLimitExceeded(_))));
+}
+
+#[test]
+fn test_diff_lines_reconstructs_right() {
+    use ctdiff::LineOperation;
+    
+    let differ = ConstantTimeDiff::new(SecurityConfig { max_input_size: 4096, ..no_padding_config() });
+    let left = b"fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n\n// end\n";
+    let right = b"use std::io;\nfn main() {\n    let x = 2;\n    println!(\"{}\", x);\n}\n\n// end";
+    
+    let result = differ.diff_lines(left, right).unwrap();
+    assert_eq!(result.original_lines_a, 6);
+    assert_eq!(result.original_lines_b, 7);
+    assert_eq!(result.apply_to(left).unwrap(), right);
+    
+    // one line inserted, one replaced, and the final line lost its newline
+    assert_eq!(result.operations, vec![
+        LineOperation::Insert(b"use std::io;\n".to_vec()),
+        LineOperation::Keep,
+        LineOperation::Replace(b"    let x = 2;\n".to_vec()),
+        LineOperation::Keep,
+        LineOperation::Keep,
+        LineOperation::Keep,
+        LineOperation::Replace(b"// end".to_vec()),
+    ]);
+    assert_eq!(result.edit_distance, 3);
+    
+    // identical and empty inputs
+    let same = differ.diff_lines(left, left).unwrap();
+    assert_eq!(same.edit_distance, 0);
+    assert_eq!(same.apply_to(left).unwrap(), left);
+    assert_eq!(differ.diff_lines(b"", right).unwrap().apply_to(b"").unwrap(), right);
+    
+    // the script only applies to input with the same line count
+    assert!(result.apply_to(b"one line\n").is_err());
+}
+
+#[test]
+fn test_diff_tokens_substitutes_whole_tokens() {
+    use ctdiff::TokenOperation;
+    
+    let differ = ConstantTimeDiff::new(no_padding_config());
+    let tokens = |text: &str| -> Vec<String> { text.split(' ').map(String::from).collect() };
+    let left = tokens("let total = price * quantity ;");
+    let right = tokens("pub let total = price * count ;");
+    
+    let result = differ.diff_tokens(&left, &right).unwrap();
+    assert_eq!(result.original_len_a, 7);
+    assert_eq!(result.original_len_b, 8);
+    assert_eq!(result.apply_to(&left, &right).unwrap(), right);
+    
+    // the changed identifier is one substitution, pointing at both tokens
+    // across the shift from the inserted one
+    let changes: Vec<_> = result.operations.iter()
+        .filter(|op| op.is_modification())
+        .collect();
+    assert_eq!(changes, vec![
+        &TokenOperation::Insert { right: 0 },
+        &TokenOperation::Substitute { left: 5, right: 6 },
+    ]);
+    assert_eq!(left[5], "quantity");
+    assert_eq!(right[6], "count");
+    assert_eq!(result.edit_distance, 2);
+    
+    // every other left token is kept at its own index
+    let kept: Vec<(usize, usize)> = result.operations.iter()
+        .filter_map(|op| match *op {
+            TokenOperation::Keep { left, right } => Some((left, right)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(kept.len(), 6);
+    assert!(kept.iter().all(|&(l, r)| left[l] == right[r]));
+    
+    // tokens that merely share a prefix are different tokens
+    let result = differ.diff_tokens(&["ab", "c"], &["abc"]).unwrap();
+    assert_eq!(result.edit_distance, 2);
+}
+
+#[test]
+fn test_diff_with_deadline() {
+    use std::time::Duration;
+    
+    let config = SecurityConfig {
+        max_input_size: 4096,
+        ..no_padding_config()
+    };
+    let differ = ConstantTimeDiff::new(config);
+    let a = vec![b'a'; 2048];
+    let b = vec![b'b'; 2048];
+    
+    // already expired: the first check fails
+    let result = differ.diff_with_deadline(&a, &b, Instant::now());
+    assert!(matches!(result, Err(DiffError::ComputationLimitExceeded(_))));
+    
+    let deadline = Instant::now() + Duration::from_secs(600);
+    let result = differ.diff_with_deadline(b"kitten", b"sitting", deadline).unwrap();
+    assert_eq!(result.edit_distance, 3);
+    assert_eq!(result, differ.diff(b"kitten", b"sitting").unwrap());
+    
+    // strict timing protection refuses to trade timing for a deadline
+    let strict = ConstantTimeDiff::new(SecurityConfig {
+        timing_protection: TimingProtection::Strict,
+        ..no_padding_config()
+    });
+    assert!(matches!(
+        strict.diff_with_deadline(b"a", b"b", deadline),
+        Err(DiffError::TimingProtectionViolation(_))
+    ));
+}
+
+#[test]
+fn test_fast_identical_check() {
+    let differ = ConstantTimeDiff::new(SecurityConfig {
+        max_input_size: 4096,
+        fast_identical_check: true,
+        ..no_padding_config()
+    });
+    let input = vec![b'x'; 2048];
+    
+    // an expired deadline would stop the matrix fill, so success means the
+    // matrix was never built
+    let result = differ.diff_with_deadline(&input, &input, Instant::now()).unwrap();
+    assert!(result.is_valid());
+    assert_eq!(result.edit_distance, 0);
+    assert_eq!(result.operations.len(), input.len());
+    assert!(result.operations.iter().all(|op| matches!(op, DiffOperation::Keep)));
+    assert_eq!(result.apply_to(&input).unwrap(), input);
+    
+    // different inputs still go through the matrix
+    let mut other = input.clone();
+    other[1000] = b'y';
+    assert!(matches!(
+        differ.diff_with_deadline(&input, &other, Instant::now()),
+        Err(DiffError::ComputationLimitExceeded(_))
+    ));
+    
+    // off by default, including for maximum security
+    let slow = ConstantTimeDiff::new(SecurityConfig { max_input_size: 4096, ..no_padding_config() });
+    assert!(slow.diff_with_deadline(&input, &input, Instant::now()).is_err());
+    assert!(!SecurityConfig::maximum_security().fast_identical_check);
+    assert!(ctdiff::security::SecurityConfig::fast(None).fast_identical_check);
+    assert!(!ctdiff::security::SecurityConfig::maximum_security(None).fast_identical_check);
+}
+
 #[test]
 fn test_timing_consistency_basic() {
     // basic structural test that same inputs take similar time
@@ -353,12 +727,83 @@ fn test_diff_result_validation() {
     assert!(!invalid_result.is_valid());
 }
 
+#[test]
+fn test_compact_identical_diff() {
+    let differ = ConstantTimeDiff::new(no_padding_config());
+    let input = vec![b'x'; 1000];
+    
+    let mut result = differ.diff(&input, &input).unwrap();
+    assert_eq!(result.operations.len(), 1000);
+    assert!(!result.is_compacted());
+    
+    result.compact();
+    assert!(result.is_compacted());
+    assert_eq!(result.operations, vec![DiffOperation::KeepRun(1000)]);
+    assert_eq!(result.edit_distance, 0);
+    assert!(result.is_valid());
+    assert_eq!(result.apply_to(&input).unwrap(), input);
+}
+
+#[test]
+fn test_compact_preserves_modifications() {
+    let differ = ConstantTimeDiff::new(no_padding_config());
+    let a = b"the quick brown fox jumps";
+    let b = b"the quick red fox leaps";
+    
+    let mut result = differ.diff(a, b).unwrap();
+    let original = result.clone();
+    result.compact();
+    
+    assert!(result.operations.len() < original.operations.len());
+    assert_eq!(result.edit_distance, original.edit_distance);
+    assert!(result.is_valid());
+    assert_eq!(result.apply_to(a).unwrap(), b);
+}
+
+#[test]
+fn test_try_from_operations() {
+    let ops = vec![
+        DiffOperation::Keep,
+        DiffOperation::Substitute(b'x'),
+        DiffOperation::Delete,
+        DiffOperation::Insert(b'y'),
+        DiffOperation::KeepRun(2),
+    ];
+    
+    let result = DiffResult::try_from_operations(ops.clone(), 5, 5).unwrap();
+    assert_eq!(result.edit_distance, 3);
+    assert!(result.is_valid());
+    assert_eq!(result.apply_to(b"abcde").unwrap(), b"axyde");
+    
+    // positions sum to (5, 5), not the claimed lengths
+    assert_eq!(
+        DiffResult::try_from_operations(ops.clone(), 4, 5),
+        Err(DiffError::ScriptOverrunsInput)
+    );
+    assert_eq!(
+        DiffResult::try_from_operations(ops.clone(), 7, 5),
+        Err(DiffError::ScriptUnderconsumesInput { remaining: 2 })
+    );
+    assert!(matches!(
+        DiffResult::try_from_operations(ops, 5, 6),
+        Err(DiffError::InvalidScript(_))
+    ));
+}
+
 #[test]
 fn test_error_handling() {
     // test apply with wrong input length
     let result = constant_time_diff(b"abc", b"def").unwrap();
     let apply_result = result.apply_to(b"wrong length input");
-    assert!(matches!(apply_result, Err(DiffError::InvalidInput(_))));
+    assert_eq!(apply_result, Err(DiffError::InputLengthMismatch { expected: 3, got: 18 }));
+    
+    // script that reads past the end of its input
+    let overrun = DiffResult::new(vec![DiffOperation::Keep, DiffOperation::Delete], 1, 1, 1);
+    assert_eq!(overrun.apply_to(b"a"), Err(DiffError::ScriptOverrunsInput));
+    
+    // script that stops before the input is used up
+    let underrun = DiffResult::new(vec![DiffOperation::Keep], 0, 3, 1);
+    assert_eq!(underrun.apply_to(b"abc"), Err(DiffError::ScriptUnderconsumesInput { remaining: 2 }));
 }
 
 #[test]
@@ -390,4 +835,432 @@ fn test_known_diff_examples() {
     let result = differ.diff(b"bat", b"ba").unwrap();
     assert_eq!(result.edit_distance, 1);
     assert_eq!(result.apply_to(b"bat").unwrap(), b"ba");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_max_operations_limit() {
+    let input = vec![b'z'; 4096];
+    let config = SecurityConfig { max_input_size: 8192, ..no_padding_config() };
+    
+    // one operation per byte is fine without a limit
+    let result = ConstantTimeDiff::new(config.clone()).diff(&input, &input).unwrap();
+    assert_eq!(result.operations.len(), input.len());
+    
+    let limited = ConstantTimeDiff::new(SecurityConfig { max_operations: Some(100), ..config });
+    match limited.diff(&input, &input) {
+        Err(DiffError::ComputationLimitExceeded(message)) => assert!(message.contains("4096")),
+        other => panic!("expected operation limit error, got {:?}", other),
+    }
+    assert!(limited.diff(b"small", b"smell").is_ok());
+}
+
+#[test]
+fn test_security_explanation() {
+    use ctdiff::SecurityLevel;
+    
+    let maximum = SecurityLevel::Maximum.to_config(None).explain();
+    assert!(maximum.timing_guarantee.starts_with("strong"));
+    assert!(maximum.dos_protection.starts_with("strong"));
+    assert!(maximum.recommended_for.contains("secrets"));
+    
+    let fast = SecurityLevel::Fast.to_config(None).explain();
+    assert!(!fast.timing_guarantee.contains("strong:"));
+    assert!(fast.timing_guarantee.contains("no strong guarantee"));
+    assert!(fast.memory_behavior.contains("real input sizes"));
+    
+    let insecure = ctdiff::security::SecurityConfig::insecure().explain();
+    assert!(insecure.timing_guarantee.starts_with("none"));
+    assert!(insecure.dos_protection.starts_with("none"));
+}
+
+#[test]
+fn test_invert_round_trip() {
+    let differ = ConstantTimeDiff::new(no_padding_config());
+    let cases: [(&[u8], &[u8]); 6] = [
+        (b"kitten", b"sitting"),
+        (b"abc", b""),
+        (b"", b"abc"),
+        (b"hello world", b"hello world"),
+        (b"line one\nline two\n", b"line 1\nline two\nline three\n"),
+        (b"\x00\xff\x10", b"\xff\x00"),
+    ];
+    
+    for (a, b) in cases {
+        let mut result = differ.diff(a, b).unwrap();
+        let inverted = result.invert(a).unwrap();
+        assert!(inverted.is_valid());
+        assert_eq!(inverted.edit_distance, result.edit_distance);
+        assert_eq!(inverted.apply_to(b).unwrap(), a);
+        
+        // compacted scripts invert the same way
+        result.compact();
+        assert_eq!(result.invert(a).unwrap().apply_to(b).unwrap(), a);
+    }
+    
+    let result = differ.diff(b"abc", b"abd").unwrap();
+    assert!(matches!(result.invert(b"ab"), Err(DiffError::InputLengthMismatch { expected: 3, got: 2 })));
+}
+
+#[test]
+fn test_compare_scripts() {
+    let differ = ConstantTimeDiff::new(SecurityConfig::default());
+    let first = differ.diff(b"kitten", b"sitting").unwrap();
+    let second = differ.diff(b"kitten", b"sitting").unwrap();
+    
+    let same = first.compare_scripts(&second);
+    assert!(same.identical);
+    assert_eq!(same.first_divergence, None);
+    assert_eq!(same.edit_distance_delta, 0);
+    
+    // change one operation partway through the script
+    let mut modified = first.clone();
+    let index = modified.operations.iter().position(|op| *op == DiffOperation::Keep).unwrap();
+    modified.operations[index] = DiffOperation::Substitute(b'?');
+    modified.edit_distance += 1;
+    
+    let diverged = first.compare_scripts(&modified);
+    assert!(!diverged.identical);
+    assert_eq!(diverged.first_divergence, Some(index));
+    assert_eq!(diverged.edit_distance_delta, 1);
+    assert_eq!(modified.compare_scripts(&first).edit_distance_delta, -1);
+    
+    // a truncated script diverges where it ends
+    let mut truncated = first.clone();
+    truncated.operations.pop();
+    assert_eq!(first.compare_scripts(&truncated).first_divergence, Some(first.operations.len() - 1));
+}
+
+#[test]
+fn test_ct_eq_agrees_with_eq() {
+    let differ = ConstantTimeDiff::new(SecurityConfig::default());
+    let first = differ.diff(b"kitten", b"sitting").unwrap();
+    
+    let mut substituted = first.clone();
+    substituted.operations[0] = DiffOperation::Substitute(b'?');
+    let mut truncated = first.clone();
+    truncated.operations.pop();
+    let mut compacted = first.clone();
+    compacted.compact();
+    let mut other_distance = first.clone();
+    other_distance.edit_distance += 1;
+    
+    let candidates = [
+        first.clone(),
+        differ.diff(b"kitten", b"sitting").unwrap(),
+        differ.diff(b"kitten", b"mitten").unwrap(),
+        substituted,
+        truncated,
+        compacted,
+        other_distance,
+    ];
+    
+    // ct_eq checks every operation of the longer script before answering,
+    // so only its result (not its timing) is observable here
+    for a in &candidates {
+        for b in &candidates {
+            assert_eq!(bool::from(a.ct_eq(b)), a == b, "{:?} vs {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn test_diff_with_progress() {
+    let differ = ConstantTimeDiff::new(SecurityConfig::default());
+    let a = vec![b'a'; 50];
+    let b = vec![b'b'; 60];
+    
+    let mut reported = Vec::new();
+    let result = differ.diff_with_progress(&a, &b, |fraction| reported.push(fraction)).unwrap();
+    assert_eq!(result, differ.diff(&a, &b).unwrap());
+    
+    assert!(reported.len() > 1);
+    assert!(reported.windows(2).all(|pair| pair[0] < pair[1]), "not increasing: {:?}", reported);
+    assert!(reported.iter().all(|&fraction| (0.0..=1.0).contains(&fraction)));
+    assert_eq!(reported.last(), Some(&1.0));
+    
+    // the cadence depends on lengths only, not on content
+    let mut similar = Vec::new();
+    differ.diff_with_progress(&a, &[b'a'; 60], |fraction| similar.push(fraction)).unwrap();
+    assert_eq!(similar, reported);
+}
+
+#[test]
+fn test_fingerprint() {
+    let differ = ConstantTimeDiff::new(SecurityConfig::default());
+    let first = differ.diff(b"kitten", b"sitting").unwrap();
+    let second = differ.diff(b"kitten", b"sitting").unwrap();
+    assert_eq!(first.fingerprint(), second.fingerprint());
+    
+    // stable across runs: pinned value for the empty script
+    assert_eq!(DiffResult::new(Vec::new(), 0, 0, 0).fingerprint(), 0x0c82_1078_4d8a_f5a5);
+    
+    let mut modified = first.clone();
+    let index = modified.operations.iter().position(|op| *op == DiffOperation::Keep).unwrap();
+    modified.operations[index] = DiffOperation::Delete;
+    assert_ne!(first.fingerprint(), modified.fingerprint());
+    
+    let mut substituted = first.clone();
+    substituted.operations[index] = DiffOperation::Substitute(b'x');
+    assert_ne!(substituted.fingerprint(), modified.fingerprint());
+    assert_ne!(first.fingerprint(), differ.diff(b"kitten", b"mitten").unwrap().fingerprint());
+}
+
+#[test]
+fn test_scratch_pool_matches_fresh_allocation() {
+    use ctdiff::Granularity;
+    
+    let fresh = ConstantTimeDiff::new(SecurityConfig::default());
+    let pooled = ConstantTimeDiff::with_scratch_pool(SecurityConfig::default());
+    
+    // sizes go up and down so the pooled buffer is both grown and reused
+    // with stale cells from a larger matrix
+    let pairs: Vec<(Vec<u8>, Vec<u8>)> = [5usize, 300, 40, 0, 700, 12]
+        .iter()
+        .map(|&len| {
+            let left: Vec<u8> = (0..len).map(|i| b"ab\ncd e"[i % 7]).collect();
+            let right: Vec<u8> = (0..len + len / 3).map(|i| b"a b\ncde"[i % 7]).collect();
+            (left, right)
+        })
+        .collect();
+    
+    for (left, right) in &pairs {
+        assert_eq!(pooled.diff(left, right).unwrap(), fresh.diff(left, right).unwrap());
+        assert_eq!(
+            pooled.diff_with_granularity(left, right, Granularity::Word).unwrap(),
+            fresh.diff_with_granularity(left, right, Granularity::Word).unwrap(),
+        );
+        assert_eq!(pooled.diff_lines(left, right).unwrap(), fresh.diff_lines(left, right).unwrap());
+        let left_words: Vec<&[u8]> = left.split(|&b| b == b' ').collect();
+        let right_words: Vec<&[u8]> = right.split(|&b| b == b' ').collect();
+        assert_eq!(pooled.diff_tokens(&left_words, &right_words).unwrap(), fresh.diff_tokens(&left_words, &right_words).unwrap());
+    }
+    
+    // a clone gets its own buffer and still agrees
+    let (left, right) = &pairs[1];
+    assert_eq!(pooled.clone().diff(left, right).unwrap(), fresh.diff(left, right).unwrap());
+}
+
+#[test]
+fn test_change_regions() {
+    use ctdiff::ChangeRegion;
+    
+    let differ = ConstantTimeDiff::new(no_padding_config());
+    
+    let result = differ.diff(b"hello", b"hallo").unwrap();
+    assert_eq!(result.change_regions(b"hello").unwrap(), vec![ChangeRegion {
+        left_offset: 1,
+        left_bytes: b"e".to_vec(),
+        right_bytes: b"a".to_vec(),
+    }]);
+    
+    // a replaced word, a deletion and an appended tail, each its own region
+    let left = b"the cat sat, quietly";
+    let right = b"the dog sat quietly!!";
+    let mut result = differ.diff(left, right).unwrap();
+    let regions = result.change_regions(left).unwrap();
+    assert_eq!(regions.iter().map(|r| r.left_offset).collect::<Vec<_>>(), vec![4, 11, 20]);
+    assert_eq!(regions[0].left_bytes.len(), regions[0].right_bytes.len());
+    assert_eq!((&regions[1].left_bytes[..], &regions[1].right_bytes[..]), (&b","[..], &b""[..]));
+    assert_eq!((&regions[2].left_bytes[..], &regions[2].right_bytes[..]), (&b""[..], &b"!!"[..]));
+    
+    // splicing the regions into the left input rebuilds the right one
+    let mut rebuilt = Vec::new();
+    let mut pos = 0;
+    for region in &regions {
+        rebuilt.extend_from_slice(&left[pos..region.left_offset]);
+        rebuilt.extend_from_slice(&region.right_bytes);
+        pos = region.left_offset + region.left_bytes.len();
+    }
+    rebuilt.extend_from_slice(&left[pos..]);
+    assert_eq!(rebuilt, right);
+    
+    // compacting the script doesn't change the regions
+    result.compact();
+    assert_eq!(result.change_regions(left).unwrap(), regions);
+    assert!(matches!(result.change_regions(b"short"), Err(DiffError::InputLengthMismatch { .. })));
+    assert!(differ.diff(left, left).unwrap().change_regions(left).unwrap().is_empty());
+}
+
+#[test]
+fn test_substitution_disabled() {
+    let config = SecurityConfig {
+        allow_substitution: false,
+        ..no_padding_config()
+    };
+    let differ = ConstantTimeDiff::new(config);
+    
+    // the changed byte is a delete plus an insert rather than one substitute
+    let result = differ.diff(b"cat", b"bat").unwrap();
+    assert!(result.is_valid());
+    assert_eq!(result.edit_distance, 2);
+    assert!(!result.operations.iter().any(|op| matches!(op, DiffOperation::Substitute(_))));
+    assert_eq!(result.operations.iter().filter(|op| matches!(op, DiffOperation::Delete)).count(), 1);
+    assert_eq!(result.operations.iter().filter(|op| matches!(op, DiffOperation::Insert(b'b'))).count(), 1);
+    assert_eq!(result.apply_to(b"cat").unwrap(), b"bat");
+    
+    let substituting = ConstantTimeDiff::new(no_padding_config()).diff(b"cat", b"bat").unwrap();
+    assert_eq!(substituting.edit_distance, 1);
+    
+    // longer inputs keep the common subsequence and still round-trip
+    let a = b"the quick brown fox";
+    let b = b"the quack brawn fix!";
+    let result = differ.diff(a, b).unwrap();
+    assert!(!result.operations.iter().any(|op| matches!(op, DiffOperation::Substitute(_))));
+    assert_eq!(result.edit_distance, 7);
+    assert_eq!(result.apply_to(a).unwrap(), b);
+}
+
+#[test]
+fn test_map_offsets() {
+    // "abcdefgh" -> "aXcfgYYh": b substituted, d and e deleted, YY inserted
+    let ops = vec![
+        DiffOperation::Keep,
+        DiffOperation::Substitute(b'X'),
+        DiffOperation::Keep,
+        DiffOperation::Delete,
+        DiffOperation::Delete,
+        DiffOperation::KeepRun(2),
+        DiffOperation::Insert(b'Y'),
+        DiffOperation::Insert(b'Y'),
+        DiffOperation::Keep,
+    ];
+    let result = DiffResult::try_from_operations(ops, 8, 8).unwrap();
+    assert_eq!(result.apply_to(b"abcdefgh").unwrap(), b"aXcfgYYh");
+    
+    let left_to_right: Vec<_> = (0..=9).map(|offset| result.map_offset_left_to_right(offset)).collect();
+    assert_eq!(left_to_right, vec![
+        Some(0), Some(1), Some(2),
+        // d and e were deleted and land on f
+        Some(3), Some(3),
+        Some(3), Some(4),
+        // h moves past the insertion
+        Some(7),
+        Some(8), None,
+    ]);
+    
+    let right_to_left: Vec<_> = (0..=9).map(|offset| result.map_offset_right_to_left(offset)).collect();
+    assert_eq!(right_to_left, vec![
+        Some(0), Some(1), Some(2), Some(5), Some(6),
+        // the inserted YY lands on h
+        Some(7), Some(7),
+        Some(7),
+        Some(8), None,
+    ]);
+    
+    // a diff computed by the algorithm maps kept bytes to equal bytes
+    let differ = ConstantTimeDiff::new(no_padding_config());
+    let left = b"fn main() { run(); }";
+    let right = b"fn main() {\n    run(true);\n}";
+    let result = differ.diff(left, right).unwrap();
+    for offset in [0, 3, 7] {
+        assert_eq!(right[result.map_offset_left_to_right(offset).unwrap()], left[offset]);
+    }
+    assert_eq!(result.map_offset_left_to_right(left.len()), Some(right.len()));
+}
+
+#[test]
+fn test_diff_error_converts_losslessly() {
+    use ctdiff::Error;
+    
+    let variants = vec![
+        DiffError::InputTooLarge { size: 10, limit: 5 },
+        DiffError::PaddingTooSmall { input_size: 10, padding_size: 8 },
+        DiffError::InvalidInput("bad byte".to_string()),
+        DiffError::InvalidScript("short".to_string()),
+        DiffError::InputLengthMismatch { expected: 3, got: 4 },
+        DiffError::ScriptOverrunsInput,
+        DiffError::ScriptUnderconsumesInput { remaining: 2 },
+        DiffError::ComputationLimitExceeded("matrix".to_string()),
+        DiffError::AlgorithmError("no transition".to_string()),
+        DiffError::TimingProtectionViolation("deadline".to_string()),
+    ];
+    
+    for original in variants {
+        let error = Error::from(original.clone());
+        // each variant lands on its own error variant with its fields intact
+        let preserved = match (&original, &error) {
+            (DiffError::InputTooLarge { size: 10, limit: 5 }, Error::InputTooLarge { size: 10, limit: 5 }) => true,
+            (DiffError::PaddingTooSmall { .. }, Error::PaddingTooSmall { input_size: 10, padding_size: 8 }) => true,
+            (DiffError::InvalidInput(_), Error::InvalidInput { message }) => message == "bad byte",
+            (DiffError::InvalidScript(_), Error::InvalidScript { message }) => message == "short",
+            (DiffError::InputLengthMismatch { .. }, Error::InputLengthMismatch { expected: 3, got: 4 }) => true,
+            (DiffError::ScriptOverrunsInput, Error::ScriptOverrunsInput) => true,
+            (DiffError::ScriptUnderconsumesInput { .. }, Error::ScriptUnderconsumesInput { remaining: 2 }) => true,
+            (DiffError::ComputationLimitExceeded(_), Error::ResourceLimit { message }) => message == "matrix",
+            (DiffError::AlgorithmError(_), Error::Algorithm { message }) => message == "no transition",
+            (DiffError::TimingProtectionViolation(_), Error::Security { message }) => message == "deadline",
+            _ => false,
+        };
+        assert!(preserved, "{:?} became {:?}", original, error);
+        
+        let recoverable = matches!(original,
+            DiffError::PaddingTooSmall { .. } | DiffError::InvalidInput(_) | DiffError::InvalidScript(_) | DiffError::InputLengthMismatch { .. }
+            | DiffError::ScriptOverrunsInput | DiffError::ScriptUnderconsumesInput { .. });
+        assert_eq!(error.is_recoverable(), recoverable, "{:?}", original);
+        assert_eq!(error.is_security_error(), matches!(original, DiffError::TimingProtectionViolation(_)));
+    }
+    
+    // conversions through `?` map the same way
+    let convert = || -> ctdiff::Result<()> { Err(DiffError::InputTooLarge { size: 10, limit: 5 })? };
+    assert!(matches!(convert(), Err(Error::InputTooLarge { size: 10, limit: 5 })));
+}
+
+#[test]
+fn test_distance_matches_diff() {
+    let padded = SecurityConfig {
+        pad_inputs: true,
+        padding_size: Some(32),
+        ..no_padding_config()
+    };
+    let configs = vec![
+        no_padding_config(),
+        padded,
+        SecurityConfig::balanced(),
+        SecurityConfig { allow_substitution: false, ..no_padding_config() },
+        SecurityConfig { affix_trim: AffixTrim::LengthBounded(2), ..no_padding_config() },
+    ];
+    let pairs: Vec<(&[u8], &[u8])> = vec![
+        (b"", b""),
+        (b"", b"abc"),
+        (b"abc", b""),
+        (b"abc", b"abc"),
+        (b"abc", b"def"),
+        (b"kitten", b"sitting"),
+        (b"hello world", b"hello, World"),
+        (b"ab-middle-yz", b"ab-muddle-yz"),
+    ];
+    
+    for config in configs {
+        let differ = ConstantTimeDiff::new(config);
+        for (a, b) in &pairs {
+            let expected = differ.diff(a, b).unwrap().edit_distance;
+            assert_eq!(differ.distance(a, b).unwrap(), expected, "{:?} vs {:?} with {:?}", a, b, differ.config());
+        }
+    }
+    
+    // the same limits apply
+    let small = ConstantTimeDiff::new(SecurityConfig { max_input_size: 4, ..no_padding_config() });
+    assert!(matches!(small.distance(b"too long", b""), Err(DiffError::InputTooLarge { .. })));
+}
+
+#[test]
+fn test_diff_with_preview_bounds_distance() {
+    let configs = vec![
+        no_padding_config(),
+        SecurityConfig { pad_inputs: true, padding_size: Some(64), ..no_padding_config() },
+        SecurityConfig { affix_trim: AffixTrim::LengthBounded(4), ..no_padding_config() },
+    ];
+    let a = b"prefix: kitten sitting on the mat :suffix";
+    let b = b"prefix: sitting kitten on a hat :suffix";
+
+    for config in configs {
+        let differ = ConstantTimeDiff::new(config);
+        let mut bounds = Vec::new();
+        let result = differ.diff_with_preview(a, b, |_, bound| bounds.push(bound)).unwrap();
+
+        assert_eq!(*bounds.last().unwrap(), result.edit_distance);
+        assert!(bounds.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", bounds);
+        assert!(bounds.iter().all(|&bound| bound <= result.edit_distance));
+        assert_eq!(result, differ.diff(a, b).unwrap());
+    }
+}
@@ -0,0 +1,129 @@
+//! allocation accounting tests
+//!
+//! uses a counting global allocator to check that lightweight entry points
+//! skip the copies made by the full result types.
+
+use ctdiff::{DiffBuilder, SecurityLevel};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+/// allocations at least this large are counted by `large_allocations_by`
+const LARGE_ALLOCATION: usize = 4096;
+
+thread_local! {
+    // per-thread so tests running in parallel don't pollute each other
+    static ALLOCATED_BYTES: Cell<usize> = const { Cell::new(0) };
+    static LARGE_ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED_BYTES.try_with(|c| c.set(c.get() + layout.size()));
+        if layout.size() >= LARGE_ALLOCATION {
+            let _ = LARGE_ALLOCATIONS.try_with(|c| c.set(c.get() + 1));
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// bytes allocated on this thread while running `f`
+fn allocated_by<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATED_BYTES.with(|c| c.get());
+    let result = f();
+    let after = ALLOCATED_BYTES.with(|c| c.get());
+    (result, after - before)
+}
+
+/// allocations of at least `LARGE_ALLOCATION` bytes made on this thread while running `f`
+fn large_allocations_by<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = LARGE_ALLOCATIONS.with(|c| c.get());
+    let result = f();
+    let after = LARGE_ALLOCATIONS.with(|c| c.get());
+    (result, after - before)
+}
+
+#[test]
+fn test_distance_only_skips_input_copies() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let left = vec![b'a'; 512];
+    let mut right = left.clone();
+    right[100] = b'b';
+
+    let (full, full_bytes) = allocated_by(|| diff.compare(&left, &right).expect("diff failed"));
+    let (distance, distance_bytes) = allocated_by(|| diff.compare_distance_only(&left, &right).expect("diff failed"));
+
+    assert_eq!(distance, full.edit_distance());
+    // the full result copies both inputs; the distance-only path must not
+    assert!(
+        distance_bytes + left.len() + right.len() <= full_bytes,
+        "distance-only allocated {} bytes, full compare {}", distance_bytes, full_bytes
+    );
+}
+
+#[test]
+fn test_compare_many_matches_individual_distances() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let pairs: Vec<(&[u8], &[u8])> = vec![
+        (b"hello", b"hello"),
+        (b"hello", b"hallo"),
+        (b"kitten", b"sitting"),
+        (b"", b"abc"),
+    ];
+
+    let distances: Vec<usize> = diff.compare_many(pairs.iter().copied())
+        .collect::<Result<_, _>>()
+        .expect("compare_many failed");
+
+    let expected: Vec<usize> = pairs.iter()
+        .map(|(l, r)| diff.compare(l, r).expect("diff failed").edit_distance())
+        .collect();
+
+    assert_eq!(distances, expected);
+    assert_eq!(distances, vec![0, 1, 3, 3]);
+}
+
+#[test]
+fn test_reused_matrix_is_allocated_once() {
+    let run = |reuse_matrix: bool| {
+        let diff = DiffBuilder::new()
+            .security_level(SecurityLevel::Fast)
+            .reuse_matrix(reuse_matrix)
+            .build()
+            .expect("failed to build diff");
+        
+        // same-length pairs whose matrix is the only allocation over the threshold
+        large_allocations_by(|| {
+            (0..10_000)
+                .map(|i| {
+                    let left = format!("record {:05} status=ok region=eu-west", i);
+                    let right = format!("record {:05} status=ko region=eu-east", i);
+                    diff.compare_distance_only(left.as_bytes(), right.as_bytes()).expect("diff failed")
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+    
+    let (fresh_distances, fresh_allocations) = run(false);
+    let (pooled_distances, pooled_allocations) = run(true);
+    
+    assert_eq!(pooled_distances, fresh_distances);
+    assert!(fresh_allocations >= 10_000, "{} large allocations without reuse", fresh_allocations);
+    assert!(pooled_allocations <= 4, "{} large allocations with reuse", pooled_allocations);
+}
@@ -25,6 +25,7 @@ fn test_attack_scenario_generation() {
         AttackScenario::ChangeSize,
         AttackScenario::VersionControl,
         AttackScenario::CodeReview,
+        AttackScenario::LengthLeakage,
     ];
     
     for scenario in scenarios {
@@ -126,7 +127,7 @@ fn test_comprehensive_attack_demo() {
     let results = simulator.run_comprehensive_demo(3); // small iteration count for tests
     
     // should test all scenarios
-    assert_eq!(results.len(), 6); // number of AttackScenario variants
+    assert_eq!(results.len(), 7); // number of AttackScenario variants
     
     for result in &results {
         assert!(!result.scenario.is_empty());
@@ -206,6 +207,7 @@ fn test_attack_scenario_descriptions() {
         AttackScenario::ChangeSize,
         AttackScenario::VersionControl,
         AttackScenario::CodeReview,
+        AttackScenario::LengthLeakage,
     ];
     
     for scenario in scenarios {
@@ -295,9 +297,59 @@ fn test_attack_success_probability_calculation() {
     );
     
     // verify basic properties
+    assert!(!no_diff_comparison.significant_difference);
     assert!(diff_comparison.ratio < 1.0); // fast is faster
     assert!(diff_comparison.ratio < 0.5); // significantly faster
     
     // the attack simulator should calculate different success probabilities
     // for these scenarios (exact values depend on implementation details)
-}
\ No newline at end of file
+}
+/// checks that every opened tag is closed in order, treating `/>` as
+/// self-closing and skipping `<!...>` declarations
+fn assert_well_formed(html: &str) {
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = html;
+    
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..].find('>').expect("unterminated tag") + start;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        
+        if tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(open.pop(), Some(name.trim()), "mismatched closing tag </{}>", name);
+        } else if !tag.ends_with('/') {
+            let name = tag.split_whitespace().next().expect("empty tag");
+            open.push(name);
+        }
+        
+        // text between tags may only use entities, never bare markup characters
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        assert!(!text.contains('>'), "unescaped '>' in text: {:?}", text);
+        for (i, _) in text.match_indices('&') {
+            assert!(text[i..].find(';').is_some_and(|semi| semi < 8), "unescaped '&' in text: {:?}", text);
+        }
+    }
+    
+    assert!(open.is_empty(), "unclosed tags: {:?}", open);
+}
+
+#[test]
+fn test_html_report() {
+    let mut simulator = AttackSimulator::new();
+    let results = simulator.simulate_attack(AttackScenario::IdenticalVsDifferent, 5);
+    let html = results.to_html();
+    
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<svg"));
+    assert!(html.contains(AttackScenario::IdenticalVsDifferent.description()));
+    assert!(html.contains("class=\"badge"));
+    assert_well_formed(&html);
+    
+    // combined report keeps one section per scenario
+    let combined = ctdiff::attack::html_report(&[results.clone(), results]);
+    assert_eq!(combined.matches("<section>").count(), 2);
+    assert_well_formed(&combined);
+}
@@ -0,0 +1,33 @@
+//! smoke test for the benchmark workloads
+//!
+//! run with `cargo test --features bench --test bench`.
+
+#![cfg(feature = "bench")]
+
+use ctdiff::attack::AttackScenario;
+use ctdiff::bench::{bench_config, workloads, DiffMode};
+use ctdiff::ConstantTimeDiff;
+
+#[test]
+fn test_every_mode_runs_once_on_every_workload() {
+    let scenarios = [
+        AttackScenario::SimilarityGradient,
+        AttackScenario::VersionControl,
+        AttackScenario::CodeReview,
+    ];
+    let sizes = [16, 128];
+    let workloads = workloads(&scenarios, &sizes);
+    let differ = ConstantTimeDiff::new(bench_config(1024));
+
+    assert!(!workloads.is_empty());
+    for workload in &workloads {
+        assert!(workload.left.len().max(workload.right.len()) >= workload.size);
+        for mode in DiffMode::ALL {
+            let distance = mode.run(&differ, &workload.left, &workload.right)
+                .unwrap_or_else(|e| panic!("{} on {}: {}", mode.name(), workload.name, e));
+            if workload.left == workload.right {
+                assert_eq!(distance, 0, "{} on {}", mode.name(), workload.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,944 @@
+//! tests for the configured `Diff` api
+//!
+//! covers comparison entry points beyond the basic compare/format path
+
+use ctdiff::{DiffBuilder, DiffOperation, Error, Granularity, OutputFormat, SecurityLevel};
+
+#[test]
+fn test_compare_if_similar_skips_different_sizes() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let small = vec![b'a'; 10];
+    let large = vec![b'a'; 1000];
+
+    let result = diff.compare_if_similar(&small, &large, 0.5).expect("compare failed");
+    assert!(result.is_none());
+
+    // same length but no bytes in common
+    let result = diff.compare_if_similar(b"aaaaaaaa", b"bbbbbbbb", 0.5).expect("compare failed");
+    assert!(result.is_none());
+}
+
+#[test]
+fn test_compare_if_similar_returns_similar_result() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let left = b"the quick brown fox jumps over the lazy dog";
+    let right = b"the quick brown fox jumped over the lazy dog";
+
+    let result = diff.compare_if_similar(left, right, 0.9)
+        .expect("compare failed")
+        .expect("similar inputs were skipped");
+    let full = diff.compare(left, right).expect("compare failed");
+
+    assert_eq!(result.edit_distance(), full.edit_distance());
+    assert_eq!(result.operations(), full.operations());
+    assert!(result.similarity() >= 0.9);
+
+    // passes the cheap bounds but the real distance misses the threshold
+    let result = diff.compare_if_similar(b"abcd", b"dcba", 0.9).expect("compare failed");
+    assert!(result.is_none());
+}
+
+#[test]
+fn test_compare_if_similar_rejects_strict_timing() {
+    for level in [SecurityLevel::Maximum, SecurityLevel::Balanced] {
+        let diff = DiffBuilder::new()
+            .security_level(level)
+            .build()
+            .expect("failed to build diff");
+
+        let result = diff.compare_if_similar(b"hello", b"hello", 0.5);
+        assert!(matches!(result, Err(Error::Security { .. })), "{:?} allowed early exit", level);
+    }
+}
+
+/// lengths of the runs of consecutive modifications in a script
+fn modification_runs(operations: &[DiffOperation]) -> Vec<usize> {
+    let mut runs = Vec::new();
+    let mut current = 0;
+    for op in operations {
+        if op.is_modification() {
+            current += 1;
+        } else if current > 0 {
+            runs.push(current);
+            current = 0;
+        }
+    }
+    if current > 0 {
+        runs.push(current);
+    }
+    runs
+}
+
+#[test]
+fn test_word_granularity_replaces_whole_words() {
+    let left = "the quick brown fox sat on the mat";
+    let right = "the quack brown fox sat on the cat";
+
+    let by_byte = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(left, right)
+        .expect("compare failed");
+    assert_eq!(by_byte.edit_distance(), 2);
+    assert_eq!(by_byte.token_edit_distance(), 2);
+
+    let by_word = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .granularity(Granularity::Word)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(left, right)
+        .expect("compare failed");
+
+    // "quick" -> "quack" and "mat" -> "cat" change as whole words
+    assert_eq!(modification_runs(by_word.operations()), vec![5, 3]);
+    assert_eq!(by_word.edit_distance(), 8);
+    // but as two word edits
+    assert_eq!(by_word.token_edit_distance(), 2);
+
+    let script = ctdiff::types::DiffResult::try_from_operations(
+        by_word.operations().to_vec(),
+        left.len(),
+        right.len(),
+    ).expect("word script is inconsistent");
+    assert_eq!(script.apply_to(left.as_bytes()).unwrap(), right.as_bytes());
+}
+
+#[test]
+fn test_line_granularity_reassembles_lines() {
+    let left = b"first line\nsecond line\nthird line\n";
+    let right = b"first line\nsecond lime\nthird line\nfourth\n";
+
+    let result = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .granularity(Granularity::Line)
+        .build()
+        .expect("failed to build diff")
+        .compare(left, right)
+        .expect("compare failed");
+
+    // the changed line and the appended line
+    assert_eq!(modification_runs(result.operations()), vec![12, 7]);
+    assert_eq!(result.edit_distance(), 19);
+    assert_eq!(result.token_edit_distance(), 2);
+
+    let script = ctdiff::types::DiffResult::try_from_operations(
+        result.operations().to_vec(),
+        left.len(),
+        right.len(),
+    ).expect("line script is inconsistent");
+    assert_eq!(script.apply_to(left).unwrap(), right);
+
+    assert_eq!(
+        Granularity::Word.tokenize(b"hi, you"),
+        vec![&b"hi"[..], b",", b" ", b"you"]
+    );
+}
+
+#[test]
+fn test_compare_materialized_reconstructs_once() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let left = b"first line\nsecond line\n";
+    let right = b"first line\nsecond lane\nthird line\n";
+    let (result, reconstructed) = diff.compare_materialized(left, right).expect("compare failed");
+    assert_eq!(reconstructed, right);
+
+    // the result keeps the right side, so formats don't rebuild it
+    assert_eq!(result.right_data(), right);
+    for format in [OutputFormat::Unified, OutputFormat::Json, OutputFormat::Html, OutputFormat::Git, OutputFormat::Summary] {
+        result.format_as(format).expect("format failed");
+        result.format().expect("format failed");
+    }
+}
+
+#[test]
+fn test_rediff_reuses_previous_left() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let base = b"fn main() {\n    run();\n}\n";
+    let edited = b"fn main() {\n    run_all();\n}\n";
+    let original = diff.compare_named(base, edited, "base.rs", "buffer").expect("compare failed");
+
+    let unchanged = diff.rediff(&original, edited).expect("rediff failed");
+    assert_eq!(unchanged.operations(), original.operations());
+    assert_eq!(unchanged.edit_distance(), original.edit_distance());
+    assert_eq!(unchanged.format().unwrap(), original.format().unwrap());
+
+    let reverted = diff.rediff(&original, base).expect("rediff failed");
+    assert!(reverted.is_identical());
+    assert_eq!(reverted.left_name(), "base.rs");
+}
+
+#[test]
+fn test_ignore_trailing_whitespace() {
+    let strict = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    assert!(!strict.compare(b"a \n", b"a\n").unwrap().is_identical());
+
+    let lenient = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_trailing_whitespace(true)
+        .build()
+        .expect("failed to build diff");
+    assert!(lenient.compare(b"a \n", b"a\n").unwrap().is_identical());
+    assert!(lenient.compare(b"a\t \r\nb", b"a\r\nb").unwrap().is_identical());
+    // leading and interior whitespace still count
+    assert!(!lenient.compare(b" a\n", b"a\n").unwrap().is_identical());
+    assert!(!lenient.compare(b"a b\n", b"ab\n").unwrap().is_identical());
+
+    let ignore_all = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_all_whitespace(true)
+        .build()
+        .expect("failed to build diff");
+    assert!(ignore_all.compare(b" a b \n", b"ab\n").unwrap().is_identical());
+    // line breaks are not whitespace for this purpose
+    assert!(!ignore_all.compare(b"a\nb", b"ab").unwrap().is_identical());
+}
+
+#[test]
+fn test_expand_tabs_aligns_indentation() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .expand_tabs(4)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare(b"\tif x {\n", b"    if x {\n").unwrap();
+    assert!(result.is_identical());
+    // the result describes the normalized text
+    assert_eq!(result.left_data(), b"    if x {\n");
+
+    // tab stops depend on the column the tab starts at
+    assert!(diff.compare(b"ab\tc", b"ab  c").unwrap().is_identical());
+    assert!(!diff.compare(b"ab\tc", b"ab    c").unwrap().is_identical());
+}
+
+#[test]
+fn test_normalize_newlines() {
+    let crlf = b"one\r\ntwo\r\nthree\r\n";
+    let lf = b"one\ntwo\nthree\n";
+
+    let plain = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let result = plain.compare(crlf, lf).unwrap();
+    assert!(!result.is_identical());
+    assert_eq!(result.line_ending_changes(), None);
+
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .normalize_newlines(true)
+        .build()
+        .expect("failed to build diff");
+    let result = diff.compare(crlf, lf).unwrap();
+    assert!(result.is_identical());
+    assert_eq!(result.line_ending_changes(), Some(3));
+    // the result describes the normalized text
+    assert_eq!(result.left_data(), lf);
+
+    // old mac line endings are normalized too
+    assert_eq!(diff.compare(b"a\rb\r", b"").unwrap().left_data(), b"a\nb\n");
+    // content changes still show
+    assert!(!diff.compare(b"one\r\n", b"two\n").unwrap().is_identical());
+}
+
+#[test]
+fn test_ignore_case() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_case(true)
+        .build()
+        .expect("failed to build diff");
+    assert!(diff.compare(b"Hello", b"hello").unwrap().is_identical());
+    assert!(!diff.differs(b"HOST.Example.COM", b"host.example.com").unwrap());
+
+    // a content change alongside a case change still shows, lowercased
+    let result = diff.compare(b"Hello World", b"hello there").unwrap();
+    assert!(!result.is_identical());
+    assert_eq!(result.left_data(), b"hello world");
+    assert_eq!(result.right_data(), b"hello there");
+
+    // ascii only unless unicode_case is on
+    assert!(!diff.compare("ÉTÉ".as_bytes(), "été".as_bytes()).unwrap().is_identical());
+    let unicode = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_case(true)
+        .unicode_case(true)
+        .build()
+        .expect("failed to build diff");
+    assert!(unicode.compare("ÉTÉ".as_bytes(), "été".as_bytes()).unwrap().is_identical());
+    // invalid utf-8 passes through
+    assert!(!unicode.compare(b"A\xff", b"a\xfe").unwrap().is_identical());
+}
+
+#[test]
+fn test_show_original_case() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_case(true)
+        .show_original_case(true)
+        .build()
+        .expect("failed to build diff");
+    assert!(diff.compare(b"Hello", b"hello").unwrap().is_identical());
+
+    let result = diff.compare(b"Hello World", b"hello THERE").unwrap();
+    assert_eq!(result.left_data(), b"Hello World");
+    // changed bytes take the right side's casing, kept ones the left's
+    assert_eq!(result.right_data(), b"Hello THERE");
+    assert_eq!(result.edit_distance(), 5);
+}
+
+#[test]
+fn test_summary_notes_line_ending_changes() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .normalize_newlines(true)
+        .output_format(OutputFormat::Summary)
+        .build()
+        .expect("failed to build diff");
+
+    let output = diff.compare(b"a\r\nb\r\nc\n", b"a\nb\nd\n").unwrap().format().unwrap();
+    assert!(output.contains("2 lines differed only in line endings"), "{}", output);
+
+    let output = diff.compare(b"a\nb\n", b"a\nc\n").unwrap().format().unwrap();
+    assert!(!output.contains("line endings"), "{}", output);
+}
+
+#[test]
+fn test_code_review_preset() {
+    let diff = DiffBuilder::code_review().build().expect("failed to build diff");
+    let left = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+    let right = b"1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+    let output = diff.compare_named(left, right, "a.rs", "b.rs").unwrap().format().unwrap();
+
+    assert!(output.starts_with("diff --git a/a.rs b/b.rs"), "{}", output);
+    // three lines of context either side of the change
+    assert!(output.contains("@@ -2,7 +2,7 @@"), "{}", output);
+
+    // word diff is on for formats that render it
+    let html = DiffBuilder::code_review()
+        .output_format(OutputFormat::Html)
+        .build()
+        .expect("failed to build diff");
+    let output = html.compare(left, right).unwrap().format().unwrap();
+    assert!(output.contains("class=\"side-by-side\""));
+
+    // whitespace stays significant
+    assert!(!diff.compare(b"a \n", b"a\n").unwrap().is_identical());
+}
+
+#[test]
+fn test_config_change_preset() {
+    let diff = DiffBuilder::config_change().build().expect("failed to build diff");
+    let output = diff.compare(b"port = 80\n", b"port = 8080\n").unwrap().format().unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&output).expect("preset output is json");
+    assert!(json.is_object());
+
+    // balanced security keeps its default input limit
+    let too_big = vec![b'x'; 256 * 1024 + 1];
+    assert!(diff.compare(&too_big, b"x").is_err());
+}
+
+#[test]
+fn test_can_compare_matches_compare() {
+    use ctdiff::DiffError;
+    
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .max_file_size(1024)
+        .build()
+        .expect("failed to build diff");
+    assert_eq!(diff.max_input_size(), 1024);
+    
+    for (left_len, right_len) in [(0, 0), (10, 1024), (1024, 1024), (1025, 3), (3, 4096)] {
+        let preflight = diff.can_compare(left_len, right_len);
+        let actual = diff.compare(&vec![b'a'; left_len], &vec![b'b'; right_len]);
+        
+        match preflight {
+            Ok(()) => assert!(actual.is_ok(), "{} / {}", left_len, right_len),
+            Err(error) => {
+                assert_eq!(error, DiffError::InputTooLarge { size: left_len.max(right_len), limit: 1024 });
+                assert!(matches!(actual, Err(Error::InputTooLarge { size, limit: 1024 }) if size == left_len.max(right_len)));
+            }
+        }
+    }
+    
+    // the edit distance limit is checked from lengths too
+    // (balanced allows a quarter of its 256kb size limit)
+    let balanced = DiffBuilder::new()
+        .security_level(SecurityLevel::Balanced)
+        .build()
+        .expect("failed to build diff");
+    assert!(balanced.can_compare(100, 100).is_ok());
+    assert!(matches!(balanced.can_compare(40_000, 40_000), Err(DiffError::ComputationLimitExceeded(_))));
+    assert!(balanced.compare(&vec![b'a'; 40_000], &vec![b'b'; 40_000]).is_err());
+}
+
+#[test]
+fn test_score_matches_compare() {
+    let diff = DiffBuilder::new()
+        .ignore_trailing_whitespace(true)
+        .build()
+        .unwrap();
+    
+    let pairs: [(&[u8], &[u8]); 4] = [
+        (b"kitten", b"sitting"),
+        (b"same", b"same"),
+        (b"", b""),
+        (b"line one  \nline two\n", b"line one\nline 2\n"),
+    ];
+    for (left, right) in pairs {
+        let score = diff.score(left, right).unwrap();
+        let full = diff.compare(left, right).unwrap();
+        let stats = full.statistics();
+        
+        assert_eq!(score.edit_distance, full.edit_distance());
+        assert_eq!(score.similarity, full.similarity());
+        assert_eq!((score.left_size, score.right_size), (stats.left_size, stats.right_size));
+    }
+}
+
+#[test]
+fn test_grapheme_granularity_keeps_clusters_whole() {
+    let left = "hello 🌍!";
+    let right = "hello 🌎!";
+    
+    // bytewise, the two globes share their first three bytes and only the
+    // last byte is substituted, splitting the codepoint
+    let by_byte = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .unwrap()
+        .compare_text(left, right)
+        .unwrap();
+    assert_eq!(by_byte.edit_distance(), 1);
+    
+    let by_grapheme = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .granularity(Granularity::Grapheme)
+        .build()
+        .unwrap()
+        .compare_text(left, right)
+        .unwrap();
+    
+    // one substitution covering exactly the replaced cluster
+    assert_eq!(modification_runs(by_grapheme.operations()), vec!["🌎".len()]);
+    let substituted: Vec<u8> = by_grapheme.operations().iter()
+        .filter_map(|op| match op {
+            DiffOperation::Substitute(byte) => Some(*byte),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(substituted, "🌎".as_bytes());
+    assert_eq!(by_grapheme.right_data(), right.as_bytes());
+    
+    // a combining accent stays with its base letter
+    assert_eq!(
+        Granularity::Grapheme.tokenize("cafe\u{301}!".as_bytes()),
+        vec![&b"c"[..], b"a", b"f", "e\u{301}".as_bytes(), b"!"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compare_files_reads_fifo() {
+    use std::io::Write;
+    
+    let dir = tempfile::tempdir().unwrap();
+    let fifo = dir.path().join("left.fifo");
+    let right = dir.path().join("right.txt");
+    std::fs::write(&right, "streamed input\n").unwrap();
+    let status = std::process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(status.success());
+    
+    // the writer blocks until compare_files opens the other end
+    let writer = {
+        let fifo = fifo.clone();
+        std::thread::spawn(move || {
+            let mut pipe = std::fs::OpenOptions::new().write(true).open(fifo).unwrap();
+            pipe.write_all(b"streamed inpvt\n").unwrap();
+        })
+    };
+    
+    let result = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .unwrap()
+        .compare_files(&fifo, &right)
+        .unwrap();
+    writer.join().unwrap();
+    
+    assert_eq!(result.edit_distance(), 1);
+    assert_eq!(result.left_data(), b"streamed inpvt\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compare_files_stops_at_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let small = dir.path().join("small.txt");
+    std::fs::write(&small, "x").unwrap();
+    
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .max_file_size(1024)
+        .build()
+        .unwrap();
+    
+    // an endless stream trips the limit mid-read instead of exhausting memory
+    let error = diff.compare_files(std::path::Path::new("/dev/zero"), &small).unwrap_err();
+    assert!(
+        matches!(error, Error::InputTooLarge { size: 1025, limit: 1024 }),
+        "unexpected error: {:?}", error
+    );
+    
+    // and so does a regular file one byte over
+    let big = dir.path().join("big.txt");
+    std::fs::write(&big, vec![b'y'; 1025]).unwrap();
+    assert!(matches!(
+        diff.compare_files(&small, &big),
+        Err(Error::InputTooLarge { limit: 1024, .. })
+    ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_compare_files_async_matches_compare_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let left = dir.path().join("left.txt");
+    let right = dir.path().join("right.txt");
+    std::fs::write(&left, "Hello World\r\nsame\r\n").unwrap();
+    std::fs::write(&right, "hello THERE\nsame\n").unwrap();
+    
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_case(true)
+        .show_original_case(true)
+        .normalize_newlines(true)
+        .max_file_size(1024)
+        .build()
+        .unwrap();
+    
+    // original casing and line ending changes come through as they do sync
+    let sync = diff.compare_files(&left, &right).unwrap();
+    let named = diff.compare_files_async_named(&left, &right).await.unwrap();
+    assert_eq!(named.right_data(), b"Hello THERE\nsame\n");
+    assert_eq!(named.right_data(), sync.right_data());
+    assert_eq!(named.line_ending_changes(), Some(1));
+    assert_eq!(named.left_name(), sync.left_name());
+    
+    let unnamed = diff.compare_files_async(&left, &right).await.unwrap();
+    assert_eq!(unnamed.right_data(), sync.right_data());
+    assert_eq!(unnamed.left_name(), "left");
+    
+    // and an oversized file fails with the same error
+    let big = dir.path().join("big.txt");
+    std::fs::write(&big, vec![b'y'; 1025]).unwrap();
+    for error in [
+        diff.compare_files_async(&left, &big).await.unwrap_err(),
+        diff.compare_files_async_named(&left, &big).await.unwrap_err(),
+    ] {
+        assert!(matches!(error, Error::InputTooLarge { limit: 1024, .. }), "unexpected error: {:?}", error);
+    }
+}
+
+#[test]
+fn test_patch_round_trips_binary() {
+    let left: Vec<u8> = (0..=255u8).rev().chain(b"\xff\xfe\x00 invalid \xc3\x28 utf-8".iter().copied()).collect();
+    let mut right = left.clone();
+    right[10] = 0x80;
+    right.splice(100..104, [0xc0, 0xc1, 0xf5]);
+    right.extend_from_slice(b"\xed\xa0\x80");
+    
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let result = diff.compare(&left, &right).unwrap();
+    let patch = result.to_patch();
+    
+    assert_eq!(patch.left_len(), left.len());
+    assert_eq!(patch.right_len(), right.len());
+    assert_eq!(patch.apply(&left).unwrap(), right);
+    
+    // it survives serialization byte for byte
+    let stored = serde_json::to_string(&patch).unwrap();
+    let restored: ctdiff::Patch = serde_json::from_str(&stored).unwrap();
+    assert_eq!(restored.apply(&left).unwrap(), right);
+    
+    // the text formats can't carry these bytes, so they don't try
+    let unified = result.format_as(OutputFormat::Unified).unwrap();
+    assert!(unified.starts_with("Binary files "), "{}", unified);
+    
+    // a patch only applies to the input it was made from
+    assert!(patch.apply(&left[1..]).is_err());
+}
+
+#[test]
+fn test_patch_can_apply() {
+    use ctdiff::DiffError;
+    
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let left = b"the quick brown fox";
+    let patch = diff.compare(left, b"the quick red fox jumps").unwrap().to_patch();
+    
+    assert_eq!(patch.can_apply(left), Ok(()));
+    assert!(patch.apply(left).is_ok());
+    
+    let short = &left[..10];
+    assert_eq!(
+        patch.can_apply(short),
+        Err(DiffError::InputLengthMismatch { expected: left.len(), got: 10 })
+    );
+    assert!(matches!(
+        patch.apply(short),
+        Err(Error::InputLengthMismatch { expected: 19, got: 10 })
+    ));
+    
+    // a tampered patch fails the same way in both
+    let mut stored = serde_json::to_value(&patch).unwrap();
+    stored["right_len"] = 5.into();
+    let tampered: ctdiff::Patch = serde_json::from_value(stored).unwrap();
+    assert!(matches!(tampered.can_apply(left), Err(DiffError::InvalidScript(_))));
+    assert!(matches!(tampered.apply(left), Err(Error::InvalidScript { .. })));
+}
+
+#[test]
+fn test_compare_range() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .output_format(OutputFormat::Json)
+        .build()
+        .expect("failed to build diff");
+    
+    // same middle section at different offsets, different surroundings
+    let middle = b"shared header section\n";
+    let left = [&b"left prefix "[..], middle, b"left trailer"].concat();
+    let right = [&b"a much longer right prefix "[..], middle, b"!"].concat();
+    let left_start = 12;
+    let right_start = 27;
+    
+    let result = diff.compare_range(
+        &left,
+        &right,
+        left_start..left_start + middle.len(),
+        right_start..right_start + middle.len(),
+    ).unwrap();
+    assert!(result.is_identical());
+    assert_eq!(result.left_data(), middle);
+    
+    // offsets in the output count from the range start
+    let result = diff.compare_range(&left, &right, left_start..left.len(), right_start..right.len()).unwrap();
+    assert_eq!(result.edit_distance(), "left trailer".len());
+    let json: serde_json::Value = serde_json::from_str(&result.format().unwrap()).unwrap();
+    assert_eq!(json["metadata"]["left_size"], left.len() - left_start);
+    let first_change = json["operations"].as_array().unwrap().iter()
+        .position(|op| op["type"] != "keep")
+        .unwrap();
+    assert_eq!(first_change, middle.len());
+    
+    for (left_range, right_range) in [(0..left.len() + 1, 0..1), (0..1, right.len()..right.len() + 1)] {
+        match diff.compare_range(&left, &right, left_range, right_range) {
+            Err(Error::InvalidInput { message }) => assert!(message.contains("out of bounds"), "{}", message),
+            other => panic!("expected an out-of-bounds error, got {:?}", other.map(|r| r.edit_distance())),
+        }
+    }
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = diff.compare_range(&left, &right, 5..2, 0..1);
+    assert!(matches!(reversed, Err(Error::InvalidInput { .. })));
+}
+
+#[test]
+fn test_differs_agrees_with_compare() {
+    use ctdiff::SecurityConfig;
+    
+    let pairs: [(&[u8], &[u8]); 5] = [
+        (b"", b""),
+        (b"same bytes", b"same bytes"),
+        (b"same bytes", b"same bytez"),
+        (b"abc", b"abcd"),
+        (b"", b"x"),
+    ];
+    for level in [SecurityLevel::Maximum, SecurityLevel::Balanced, SecurityLevel::Fast] {
+        let diff = DiffBuilder::new()
+            .security_level(level)
+            .build()
+            .expect("failed to build diff");
+        for (left, right) in pairs {
+            let expected = !diff.compare(left, right).unwrap().is_identical();
+            assert_eq!(diff.differs(left, right).unwrap(), expected, "{:?}: {:?} / {:?}", level, left, right);
+        }
+        
+        // a right side that looks like the left plus padding still differs
+        assert!(diff.differs(b"x", b"x\xff").unwrap());
+    }
+    
+    // no matrix is built, so a matrix limit nothing fits under doesn't matter
+    let no_matrix = DiffBuilder::new()
+        .security_config(SecurityConfig { max_matrix_bytes: Some(1), ..SecurityConfig::balanced(None) })
+        .build()
+        .expect("failed to build diff");
+    assert!(matches!(
+        no_matrix.compare(b"left", b"right"),
+        Err(Error::ResourceLimit { .. })
+    ));
+    assert!(no_matrix.differs(b"left", b"right").unwrap());
+    assert!(!no_matrix.differs(b"left", b"left").unwrap());
+    
+    let normalized = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .ignore_trailing_whitespace(true)
+        .max_file_size(16)
+        .build()
+        .expect("failed to build diff");
+    assert!(!normalized.differs(b"text  \n", b"text\n").unwrap());
+    assert!(matches!(normalized.differs(&[b'a'; 17], b""), Err(Error::InputTooLarge { .. })));
+}
+
+#[test]
+fn test_version_constants() {
+    let parts: Vec<u32> = ctdiff::VERSION
+        .split(['-', '+']).next().unwrap()
+        .split('.')
+        .map(|part| part.parse().expect("version part isn't a number"))
+        .collect();
+    assert_eq!(parts.len(), 3, "{} isn't major.minor.patch", ctdiff::VERSION);
+    assert!(ctdiff::api_compatible(parts[0]));
+    assert!(!ctdiff::api_compatible(parts[0] + 1));
+
+    let result = DiffBuilder::new()
+        .output_format(OutputFormat::Json)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare(b"a", b"b")
+        .expect("diff failed");
+    let value: serde_json::Value = serde_json::from_str(&result.format().unwrap()).unwrap();
+    assert_eq!(value["metadata"]["format_version"], ctdiff::JSON_FORMAT_VERSION);
+}
+
+#[test]
+fn test_compare_batch_fixed() {
+    use ctdiff::SecurityConfig;
+
+    let pairs: [(&[u8], &[u8]); 4] = [
+        (b"a", b"b"),
+        (b"hello world", b"hello there world"),
+        (b"same", b"same"),
+        (b"", b"a record of twenty-six b"),
+    ];
+
+    // a 65x65 matrix is just over this limit; per-call padding of these
+    // short pairs stays far below it
+    let limited = SecurityConfig { max_matrix_bytes: Some(64 * 64 * 4), ..SecurityConfig::balanced(None) };
+    let diff = DiffBuilder::new()
+        .security_config(limited)
+        .build()
+        .expect("failed to build diff");
+
+    let results = diff.compare_batch_fixed(&pairs, 32).unwrap();
+    assert_eq!(results.len(), pairs.len());
+    for ((left, right), result) in pairs.iter().zip(&results) {
+        let single = diff.compare(left, right).unwrap();
+        assert_eq!(result.edit_distance(), single.edit_distance());
+        assert_eq!(result.operations(), single.operations());
+        assert_eq!(result.to_patch().apply(left).unwrap(), *right);
+    }
+
+    // every input, whatever its size, is padded to the batch size: each
+    // pair now needs the same 65x65 matrix and hits the limit
+    for pair in pairs {
+        assert!(diff.compare(pair.0, pair.1).is_ok());
+        match diff.compare_batch_fixed(&[pair], 64) {
+            Err(Error::ResourceLimit { message }) => {
+                assert!(message.contains("65x65 cells"), "{}", message);
+            }
+            other => panic!("expected the matrix limit, got {:?}", other.map(|r| r.len())),
+        }
+    }
+
+    // inputs longer than the fixed size fail the batch before any diffing
+    assert!(matches!(
+        diff.compare_batch_fixed(&pairs, 16),
+        Err(Error::PaddingTooSmall { input_size: 17, padding_size: 16 })
+    ));
+    assert!(diff.compare_batch_fixed(&[], 16).unwrap().is_empty());
+}
+
+#[test]
+fn test_custom_tokenizer() {
+    // one token per csv field, each keeping its trailing comma or newline
+    let fields = |input: &[u8]| {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for (i, &byte) in input.iter().enumerate() {
+            if byte == b',' || byte == b'\n' {
+                ranges.push(start..i + 1);
+                start = i + 1;
+            }
+        }
+        if start < input.len() {
+            ranges.push(start..input.len());
+        }
+        ranges
+    };
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .tokenizer(fields)
+        .build()
+        .expect("failed to build diff");
+
+    let left = b"alice,30,paris\nbob,25,rome\n";
+    let right = b"alice,31,paris\nbob,25,rome\n";
+    let result = diff.compare(left, right).unwrap();
+
+    // the changed field is replaced as one token, where a byte diff would
+    // keep its unchanged `3`
+    let regions = result.change_regions().unwrap();
+    assert_eq!(regions.len(), 1);
+    assert_eq!((&regions[0].left_bytes[..], &regions[0].right_bytes[..]), (&b"30,"[..], &b"31,"[..]));
+    assert_eq!(result.edit_distance(), 3);
+    assert_eq!(result.token_edit_distance(), 1);
+    assert_eq!(result.to_patch().apply(left).unwrap(), right);
+
+    // ranges that leave a gap are rejected
+    let gappy = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .tokenizer(|input| vec![0..1, 2..input.len()])
+        .build()
+        .expect("failed to build diff");
+    assert!(matches!(gappy.compare(b"ab", b"ac"), Err(Error::InvalidInput { .. })));
+}
+
+#[test]
+fn test_compare_prepared() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .granularity(Granularity::Line)
+        .normalize_newlines(true)
+        .build()
+        .expect("failed to build diff");
+
+    let base = b"fn main() {\n    println!(\"hi\");\n}\n";
+    let rights: [&[u8]; 4] = [
+        b"fn main() {\n    println!(\"hello\");\n}\n",
+        b"fn main() {\r\n    println!(\"hi\");\r\n}\r\n",
+        b"fn main() {\n}\n",
+        b"",
+    ];
+
+    // one prepared base, reused against every right
+    let prepared_base = diff.prepare(base);
+    assert_eq!(prepared_base.line_count(), 3);
+    for right in rights {
+        let direct = diff.compare(base, right).unwrap();
+        let prepared = diff.compare_prepared(&prepared_base, &diff.prepare(right)).unwrap();
+        assert_eq!(prepared.operations(), direct.operations());
+        assert_eq!(prepared.edit_distance(), direct.edit_distance());
+        assert_eq!(prepared.line_ending_changes(), direct.line_ending_changes());
+    }
+    let crlf = diff.compare_prepared(&prepared_base, &diff.prepare(rights[1])).unwrap();
+    assert_eq!(crlf.line_ending_changes(), Some(3));
+
+    // without line granularity the prepared bytes are compared as usual
+    let bytes = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let direct = bytes.compare(base, rights[0]).unwrap();
+    let prepared = bytes.compare_prepared(&bytes.prepare(base), &bytes.prepare(rights[0])).unwrap();
+    assert_eq!(prepared.operations(), direct.operations());
+}
+
+#[test]
+fn test_binary_delta() {
+    use ctdiff::{apply_binary_delta, AffixTrim, SecurityConfig};
+
+    // affix trimming keeps the 10KB diff quick
+    let diff = DiffBuilder::new()
+        .security_config(SecurityConfig { affix_trim: AffixTrim::LengthBounded(4096), ..SecurityConfig::fast(None) })
+        .build()
+        .expect("failed to build diff");
+
+    // one changed byte in 10KB encodes to a handful of bytes
+    let left: Vec<u8> = (0..10 * 1024).map(|i| (i * 31 % 251) as u8).collect();
+    let mut right = left.clone();
+    right[5000] ^= 0xff;
+    let result = diff.compare(&left, &right).unwrap();
+    let delta = result.to_binary_delta();
+    assert!(delta.len() < 64, "{} byte delta", delta.len());
+    assert!(delta.len() * 100 < serde_json::to_vec(result.operations()).unwrap().len());
+    assert_eq!(apply_binary_delta(&left, &delta).unwrap(), right);
+
+    // the wrong original, a truncated delta or garbage are rejected
+    assert!(matches!(
+        apply_binary_delta(&left[1..], &delta),
+        Err(Error::InputLengthMismatch { .. })
+    ));
+    assert!(apply_binary_delta(&left, &delta[..delta.len() - 2]).is_err());
+    assert!(apply_binary_delta(&left, b"not a delta").is_err());
+}
+
+proptest::proptest! {
+    #[test]
+    fn prop_binary_delta_round_trips(
+        left in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..64),
+        right in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..64)
+    ) {
+        let diff = DiffBuilder::new()
+            .security_level(SecurityLevel::Fast)
+            .build()
+            .expect("failed to build diff");
+        let delta = diff.compare(&left, &right).unwrap().to_binary_delta();
+        proptest::prop_assert_eq!(ctdiff::apply_binary_delta(&left, &delta).unwrap(), right);
+    }
+}
+
+#[test]
+fn test_compare_with_preview() {
+    let left = b"the quick brown fox jumps over the lazy dog";
+    let right = b"the quick red fox walked over the lazy cat";
+
+    for level in [SecurityLevel::Fast, SecurityLevel::Balanced] {
+        let diff = DiffBuilder::new()
+            .security_level(level)
+            .build()
+            .expect("failed to build diff");
+
+        let mut previews = Vec::new();
+        let result = diff.compare_with_preview(left, right, |fraction, similarity| previews.push((fraction, similarity))).unwrap();
+
+        // one preview per matrix row, then the exact similarity
+        assert!(previews.len() > left.len(), "{:?}", level);
+        assert_eq!(*previews.last().unwrap(), (1.0, result.similarity() as f32));
+        assert_eq!(result.operations(), diff.compare(left, right).unwrap().operations());
+
+        // the estimate starts optimistic and only comes down
+        assert!(previews.windows(2).all(|pair| pair[0].0 < pair[1].0 && pair[0].1 >= pair[1].1), "{:?}", level);
+        assert!(previews[0].1 > result.similarity() as f32);
+    }
+}
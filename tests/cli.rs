@@ -83,6 +83,59 @@ fn test_colored_output() {
         .stdout(predicate::str::contains("+++"));
 }
 
+#[test]
+fn test_color_choice_when_piped() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    
+    fs::write(&file1, "line1\nline2\n").unwrap();
+    fs::write(&file2, "line1\nchanged\n").unwrap();
+    
+    // stdout is a pipe here, so auto must not color but always must
+    for new_format in [None, Some("git")] {
+        let mut cmd = Command::cargo_bin("ctdiff").unwrap();
+        cmd.env_remove("NO_COLOR").env_remove("CLICOLOR_FORCE");
+        cmd.arg("--color=auto");
+        if let Some(format) = new_format {
+            cmd.arg("--new-format").arg(format);
+        }
+        cmd.arg(&file1).arg(&file2);
+        cmd.assert()
+            .code(1)
+            .stdout(predicate::str::contains("\x1b[").not());
+        
+        let mut cmd = Command::cargo_bin("ctdiff").unwrap();
+        cmd.env_remove("NO_COLOR").env_remove("CLICOLOR_FORCE");
+        cmd.arg("--color=always");
+        if let Some(format) = new_format {
+            cmd.arg("--new-format").arg(format);
+        }
+        cmd.arg(&file1).arg(&file2);
+        cmd.assert()
+            .code(1)
+            .stdout(predicate::str::contains("\x1b["));
+    }
+    
+    // bare --color means always, even when piped
+    Command::cargo_bin("ctdiff").unwrap()
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .arg("--color")
+        .arg(&file1)
+        .arg(&file2)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("\x1b["));
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg("--color=sometimes")
+        .arg(&file1)
+        .arg(&file2)
+        .assert()
+        .failure();
+}
+
 #[test]
 fn test_quiet_mode() {
     let temp_dir = TempDir::new().unwrap();
@@ -210,10 +263,46 @@ fn test_max_size_limit() {
     cmd.arg("--max-size").arg("1").arg(&file1).arg(&file2);
     
     cmd.assert()
-        .code(2) // error exit code
+        .code(3) // security limit exit code
         .stderr(predicate::str::contains("exceeds limit"));
 }
 
+#[test]
+fn test_degrade_on_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    
+    // just over maximum's 4kb limit, well under balanced's
+    let content = "y".repeat(4100);
+    fs::write(&file1, &content).unwrap();
+    fs::write(&file2, format!("{}z", content)).unwrap();
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .args(["--security-level", "maximum"])
+        .arg(&file1).arg(&file2)
+        .assert()
+        .code(3);
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .args(["--security-level", "maximum", "--degrade-on-limit", "--show-timing"])
+        .arg(&file1).arg(&file2)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("warning: input too large for Maximum security, retrying at Balanced"))
+        .stderr(predicate::str::contains("constant-time guarantee: moderate"));
+    
+    // inputs that fit don't degrade
+    fs::write(&file2, "small").unwrap();
+    fs::write(&file1, "smaller").unwrap();
+    Command::cargo_bin("ctdiff").unwrap()
+        .args(["--security-level", "maximum", "--degrade-on-limit"])
+        .arg(&file1).arg(&file2)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("warning").not());
+}
+
 #[test]
 fn test_force_flag() {
     let temp_dir = TempDir::new().unwrap();
@@ -255,16 +344,17 @@ fn test_binary_files() {
     let file1 = temp_dir.path().join("binary1.bin");
     let file2 = temp_dir.path().join("binary2.bin");
     
-    fs::write(&file1, &[0x00, 0x01, 0x02, 0xFF]).unwrap();
-    fs::write(&file2, &[0x00, 0x01, 0x03, 0xFF]).unwrap();
+    fs::write(&file1, [0x00, 0x01, 0x02, 0xFF]).unwrap();
+    fs::write(&file2, [0x00, 0x01, 0x03, 0xFF]).unwrap();
     
     let mut cmd = Command::cargo_bin("ctdiff").unwrap();
     cmd.arg(&file1).arg(&file2);
     
     cmd.assert()
         .code(1) // files are different
-        .stdout(predicate::str::contains("---"))
-        .stdout(predicate::str::contains("+++"));
+        .stdout(predicate::str::contains("Binary files"))
+        .stdout(predicate::str::contains("differ"))
+        .stdout(predicate::str::contains("@@").not());
 }
 
 #[test]
@@ -282,4 +372,300 @@ fn test_side_by_side_format() {
     cmd.assert()
         .code(1)
         .stdout(predicate::str::contains("|")); // side-by-side separator
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_all_equal_reports_differing_replica() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("replica1.txt");
+    let file2 = temp_dir.path().join("replica2.txt");
+    let file3 = temp_dir.path().join("replica3.txt");
+    
+    fs::write(&file1, "replica content\n").unwrap();
+    fs::write(&file2, "replica content\n").unwrap();
+    fs::write(&file3, "replica c0ntent\n").unwrap();
+    
+    let mut cmd = Command::cargo_bin("ctdiff").unwrap();
+    cmd.arg("--all-equal").arg(&file1).arg(&file2).arg(&file3);
+    
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("file 2"))
+        .stderr(predicate::str::contains("replica3.txt"))
+        .stderr(predicate::str::contains("file 1 ").not());
+    
+    // all identical replicas succeed
+    let mut cmd = Command::cargo_bin("ctdiff").unwrap();
+    cmd.arg("--all-equal").arg(&file1).arg(&file2).arg(&file1);
+    
+    cmd.assert()
+        .code(0)
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_json_schema_flag() {
+    let output = Command::cargo_bin("ctdiff").unwrap()
+        .arg("--json-schema")
+        .output()
+        .unwrap();
+    
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).expect("schema is not json");
+    assert_eq!(schema, ctdiff::formats::json::schema());
+}
+
+#[test]
+fn test_explain_security_flag() {
+    let output = Command::cargo_bin("ctdiff").unwrap()
+        .args(["--explain-security", "--security-level", "maximum"])
+        .output()
+        .unwrap();
+    
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("Timing guarantee: strong"));
+    assert!(text.contains("Recommended for:"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_inputs_match_uncompressed() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    
+    let temp_dir = TempDir::new().unwrap();
+    let left = "alpha\nbeta\ngamma\n";
+    let right = "alpha\nBETA\ngamma\ndelta\n";
+    
+    let gzip = |name: &str, content: &str| {
+        let path = temp_dir.path().join(name);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        fs::write(&path, encoder.finish().unwrap()).unwrap();
+        path
+    };
+    let plain = |name: &str, content: &str| {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    };
+    
+    // drops the header lines, which carry the file names
+    let body = |file1: &std::path::Path, file2: &std::path::Path| {
+        let output = Command::cargo_bin("ctdiff").unwrap().arg(file1).arg(file2).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        String::from_utf8(output.stdout).unwrap()
+            .lines()
+            .filter(|line| !line.starts_with("---") && !line.starts_with("+++"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    
+    let expected = body(&plain("a.txt", left), &plain("b.txt", right));
+    assert!(expected.iter().any(|line| line == "+BETA"));
+    assert_eq!(body(&gzip("a.txt.gz", left), &gzip("b.txt.gz", right)), expected);
+    
+    // a decompressed size over --max-size is refused unless forced
+    let big = gzip("big.gz", &"x".repeat(4096));
+    Command::cargo_bin("ctdiff").unwrap()
+        .args(["--max-size", "1"]).arg(&big).arg(&big)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds limit"));
+}
+
+#[test]
+fn test_score_subcommand() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    
+    fs::write(&file1, "kitten").unwrap();
+    fs::write(&file2, "sitting").unwrap();
+    
+    let output = Command::cargo_bin("ctdiff").unwrap()
+        .arg("score").arg(&file1).arg(&file2)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["edit_distance"], 3);
+    assert_eq!(report["left_size"], 6);
+    assert_eq!(report["right_size"], 7);
+    assert!((report["similarity"].as_f64().unwrap() - 4.0 / 7.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_exit_codes() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    let large = temp_dir.path().join("large.txt");
+    
+    fs::write(&file1, "hello world\n").unwrap();
+    fs::write(&file2, "hello universe\n").unwrap();
+    // over the balanced level's 256kb recommendation
+    fs::write(&large, "x".repeat(300 * 1024)).unwrap();
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg(&file1).arg(&file2)
+        .assert()
+        .code(1);
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg(&large).arg(&file1)
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("--force"));
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg(&file1).arg(temp_dir.path().join("missing.txt"))
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_exit_status_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    
+    fs::write(&file1, "hello world\n").unwrap();
+    fs::write(&file2, "hello universe\n").unwrap();
+    
+    let status = |file2: &std::path::Path| {
+        let output = Command::cargo_bin("ctdiff").unwrap()
+            .arg("--exit-status-json").arg(&file1).arg(file2)
+            .output()
+            .unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(status["exit_code"], output.status.code().unwrap());
+        status
+    };
+    
+    let different = status(&file2);
+    assert_eq!(different["identical"], false);
+    assert_eq!(different["exit_code"], 1);
+    assert!(different["edit_distance"].as_u64().unwrap() > 0);
+    
+    let identical = status(&file1);
+    assert_eq!(identical, serde_json::json!({ "identical": true, "edit_distance": 0, "exit_code": 0 }));
+}
+
+#[test]
+fn test_also_write_extra_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    let json_path = temp_dir.path().join("diff.json");
+    let summary_path = temp_dir.path().join("summary.txt");
+    
+    fs::write(&file1, "hello world\n").unwrap();
+    fs::write(&file2, "hello universe\n").unwrap();
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg("--also-json").arg(&json_path)
+        .arg("--also-summary").arg(&summary_path)
+        .arg(&file1).arg(&file2)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("-hello world"))
+        .stdout(predicate::str::contains("+hello universe"));
+    
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(json["metadata"]["left_name"], file1.display().to_string());
+    assert_eq!(json["metadata"]["right_size"], 15);
+    assert_eq!(json["statistics"]["identical"], false);
+    assert!(json["statistics"]["edit_distance"].as_u64().unwrap() > 0);
+    assert!(json["operations"].as_array().is_some_and(|ops| !ops.is_empty()));
+    
+    let summary = fs::read_to_string(&summary_path).unwrap();
+    assert!(summary.contains("Edit Distance:"), "{}", summary);
+    
+    // the extra files are written even when stdout is quiet
+    let html_path = temp_dir.path().join("diff.html");
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg("--quiet")
+        .arg("--also-html").arg(&html_path)
+        .arg(&file1).arg(&file2)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty());
+    assert!(fs::read_to_string(&html_path).unwrap().contains("<html"));
+}
+
+#[test]
+fn test_format_selects_library_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    
+    fs::write(&file1, "line1\nline2\n").unwrap();
+    fs::write(&file2, "line1\nchanged\n").unwrap();
+    
+    let run = |format: &str| {
+        let output = Command::cargo_bin("ctdiff").unwrap()
+            .arg("--format").arg(format)
+            .arg(&file1)
+            .arg(&file2)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+    
+    let json: serde_json::Value = serde_json::from_str(&run("json")).expect("--format json isn't json");
+    assert_eq!(json["metadata"]["left_name"], file1.display().to_string());
+    assert!(json["statistics"]["edit_distance"].as_u64().unwrap() > 0);
+    
+    assert!(run("html").starts_with("<!DOCTYPE html>"));
+    assert!(run("git").starts_with("diff --git "));
+    assert!(run("summary").contains("Edit Distance:"));
+    
+    // the cli's own formats are unchanged
+    assert!(run("minimal").len() < run("unified").len());
+}
+
+#[test]
+fn test_stat() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("file1.txt");
+    let file2 = temp_dir.path().join("file2.txt");
+    
+    fs::write(&file1, "hello world\n").unwrap();
+    fs::write(&file2, "hello, World\n").unwrap();
+    
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg("--stat")
+        .arg(&file1)
+        .arg(&file2)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("file2.txt | 3 ++-"))
+        .stdout(predicate::str::contains("1 file changed, 2 insertions(+), 1 deletion(-)"))
+        .stdout(predicate::str::contains("@@").not());
+    
+    // identical files print no stat
+    Command::cargo_bin("ctdiff").unwrap()
+        .arg("--stat")
+        .arg(&file1)
+        .arg(&file1)
+        .assert()
+        .code(0)
+        .stdout(predicate::str::is_empty());
+    
+    // library formats render their own output, so --stat can't replace them
+    for format in ["json", "html", "git", "summary"] {
+        Command::cargo_bin("ctdiff").unwrap()
+            .arg("--stat")
+            .arg("--format")
+            .arg(format)
+            .arg(&file1)
+            .arg(&file2)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}
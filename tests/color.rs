@@ -0,0 +1,66 @@
+//! color handling across text formats
+//!
+//! kept in its own test binary because it changes process-wide environment
+//! variables (`CLICOLOR_FORCE`, `NO_COLOR`) that other tests would race on
+
+use ctdiff::{ColorChoice, DiffBuilder, OutputFormat, SecurityLevel};
+
+const ESC: char = '\x1b';
+
+fn format_with_color(format: OutputFormat) -> String {
+    format_with_choice(format, ColorChoice::Always)
+}
+
+fn format_with_choice(format: OutputFormat, choice: ColorChoice) -> String {
+    DiffBuilder::new()
+        .output_format(format)
+        .security_level(SecurityLevel::Fast)
+        .color_choice(choice)
+        .build()
+        .expect("failed to build diff")
+        .compare_text("line 1\nline 2\nline 3\n", "line 1\nchanged\nline 3\n")
+        .expect("diff failed")
+        .format()
+        .expect("format failed")
+}
+
+#[test]
+fn test_color_and_no_color() {
+    std::env::remove_var("CLICOLOR_FORCE");
+    std::env::remove_var("NO_COLOR");
+
+    // test stdout is captured, not a terminal: auto stays plain, always colors
+    assert!(!ColorChoice::Auto.resolve(false));
+    assert!(ColorChoice::Auto.resolve(true));
+    assert!(ColorChoice::Always.resolve(false));
+    assert!(!ColorChoice::Never.resolve(true));
+    assert!(!format_with_choice(OutputFormat::Unified, ColorChoice::Auto).contains(ESC));
+    assert!(format_with_choice(OutputFormat::Unified, ColorChoice::Always).contains(ESC));
+
+    std::env::set_var("CLICOLOR_FORCE", "1");
+    assert!(ColorChoice::Auto.resolve(false));
+    std::env::set_var("CLICOLOR_FORCE", "0");
+    assert!(!ColorChoice::Auto.resolve(false));
+    std::env::remove_var("CLICOLOR_FORCE");
+
+    let git = format_with_color(OutputFormat::Git);
+    assert!(git.contains(ESC), "colored git output has no ansi escapes");
+    assert!(git.lines().any(|l| l.starts_with(ESC) && l.contains("-line 2")));
+    assert!(git.lines().any(|l| l.starts_with(ESC) && l.contains("+changed")));
+    assert!(format_with_color(OutputFormat::Summary).contains(ESC));
+
+    std::env::set_var("NO_COLOR", "1");
+    assert!(!ColorChoice::Auto.resolve(true));
+    // NO_COLOR wins over CLICOLOR_FORCE
+    std::env::set_var("CLICOLOR_FORCE", "1");
+    assert!(!ColorChoice::Auto.resolve(false));
+    std::env::remove_var("CLICOLOR_FORCE");
+    for format in [OutputFormat::Git, OutputFormat::Summary, OutputFormat::Unified] {
+        let output = format_with_color(format.clone());
+        assert!(!output.contains(ESC), "{} output colored despite NO_COLOR", format);
+    }
+
+    // an empty NO_COLOR doesn't count
+    std::env::set_var("NO_COLOR", "");
+    assert!(format_with_color(OutputFormat::Git).contains(ESC));
+}
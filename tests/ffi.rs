@@ -0,0 +1,81 @@
+//! tests for the c interface, called directly from rust
+
+#![cfg(feature = "capi")]
+
+use ctdiff::constant_time_diff;
+use ctdiff::ffi::*;
+use std::ptr;
+
+#[test]
+fn test_compare_matches_constant_time_diff() {
+    let pairs: [(&[u8], &[u8]); 4] = [
+        (b"kitten", b"sitting"),
+        (b"hello", b"hello"),
+        (b"", b"abc"),
+        (b"abc", b""),
+    ];
+
+    for (a, b) in pairs {
+        let mut distance = usize::MAX;
+        let code = unsafe { ctdiff_compare(a.as_ptr(), a.len(), b.as_ptr(), b.len(), &mut distance) };
+
+        assert_eq!(code, CTDIFF_OK);
+        assert_eq!(distance, constant_time_diff(a, b).unwrap().edit_distance);
+    }
+}
+
+#[test]
+fn test_compare_with_level() {
+    let (a, b) = (b"kitten".as_slice(), b"sitting".as_slice());
+    let expected = constant_time_diff(a, b).unwrap().edit_distance;
+
+    for level in [CTDIFF_LEVEL_MAXIMUM, CTDIFF_LEVEL_BALANCED, CTDIFF_LEVEL_FAST] {
+        let mut distance = 0;
+        let code = unsafe { ctdiff_compare_with_level(a.as_ptr(), a.len(), b.as_ptr(), b.len(), level, &mut distance) };
+        assert_eq!(code, CTDIFF_OK);
+        assert_eq!(distance, expected);
+    }
+
+    let mut distance = 0;
+    let code = unsafe { ctdiff_compare_with_level(a.as_ptr(), a.len(), b.as_ptr(), b.len(), 42, &mut distance) };
+    assert_eq!(code, CTDIFF_ERR_INVALID_LEVEL);
+
+    // maximum security caps inputs at 4kb
+    let large = vec![0u8; 8 * 1024];
+    let code = unsafe {
+        ctdiff_compare_with_level(large.as_ptr(), large.len(), large.as_ptr(), large.len(), CTDIFF_LEVEL_MAXIMUM, &mut distance)
+    };
+    assert_eq!(code, CTDIFF_ERR_INPUT_TOO_LARGE);
+}
+
+#[test]
+fn test_null_pointers() {
+    let mut distance = 7;
+
+    // null with zero length is an empty input
+    let code = unsafe { ctdiff_compare(ptr::null(), 0, b"ab".as_ptr(), 2, &mut distance) };
+    assert_eq!(code, CTDIFF_OK);
+    assert_eq!(distance, 2);
+
+    let code = unsafe { ctdiff_compare(ptr::null(), 3, b"ab".as_ptr(), 2, &mut distance) };
+    assert_eq!(code, CTDIFF_ERR_NULL_POINTER);
+
+    let code = unsafe { ctdiff_compare(b"a".as_ptr(), 1, b"b".as_ptr(), 1, ptr::null_mut()) };
+    assert_eq!(code, CTDIFF_ERR_NULL_POINTER);
+
+    unsafe { ctdiff_free_result(ptr::null_mut()) };
+    assert_eq!(unsafe { ctdiff_result_edit_distance(ptr::null()) }, 0);
+}
+
+#[test]
+fn test_result_handle() {
+    let (a, b) = (b"kitten".as_slice(), b"sitting".as_slice());
+    let mut result = ptr::null_mut();
+
+    let code = unsafe { ctdiff_diff(a.as_ptr(), a.len(), b.as_ptr(), b.len(), &mut result) };
+    assert_eq!(code, CTDIFF_OK);
+    assert!(!result.is_null());
+
+    assert_eq!(unsafe { ctdiff_result_edit_distance(result) }, 3);
+    unsafe { ctdiff_free_result(result) };
+}
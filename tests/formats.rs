@@ -100,6 +100,102 @@ fn test_git_format_structure() {
     assert!(output.contains("+changed"));
 }
 
+#[test]
+fn test_git_format_series_applies_with_git() {
+    use ctdiff::formats::{git, FormatOptions};
+    use ctdiff::{ConstantTimeDiff, types::SecurityConfig};
+    use std::process::Command;
+
+    // git is only needed to check the result, so go without it if missing
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("git not found, skipping");
+        return;
+    }
+
+    let differ = ConstantTimeDiff::new(SecurityConfig::default());
+    let pairs = [
+        ("src/a.txt", "line 1\nline 2\n", "line 1\nchanged\n"),
+        ("src/b.txt", "alpha\nbeta\n", "alpha\nbeta\ngamma\n"),
+        ("new.txt", "", "created\nfile\n"),
+        ("gone.txt", "deleted\nfile\n", ""),
+        ("tail.txt", "p\nq", "p\nr"),
+        ("terminated.txt", "x\ny", "x\ny\n"),
+        ("same.txt", "unchanged\n", "unchanged\n"),
+    ];
+
+    let files: Vec<_> = pairs.iter()
+        .map(|(name, left, right)| {
+            let result = differ.diff(left.as_bytes(), right.as_bytes()).expect("diff failed");
+            (name.to_string(), name.to_string(), left.as_bytes().to_vec(), right.as_bytes().to_vec(), result)
+        })
+        .collect();
+
+    let output = git::format_series(&files, 3, &FormatOptions::default()).expect("format failed");
+    assert_eq!(output.matches("diff --git ").count(), pairs.len() - 1, "{}", output);
+    assert!(output.contains("--- /dev/null\n+++ b/new.txt\n"), "{}", output);
+    assert!(output.contains("--- a/gone.txt\n+++ /dev/null\n"), "{}", output);
+    assert!(output.contains("-q\n\\ No newline at end of file\n+r\n\\ No newline at end of file\n"), "{}", output);
+
+    // check the series against git itself, on a tree holding the left sides
+    let dir = tempfile::tempdir().unwrap();
+    for (name, left, _) in pairs {
+        if !left.is_empty() {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, left).unwrap();
+        }
+    }
+    std::fs::write(dir.path().join("series.patch"), &output).unwrap();
+
+    let git_apply = |check: bool| {
+        let mut command = Command::new("git");
+        command.current_dir(dir.path())
+            .env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap())
+            .arg("apply");
+        if check {
+            command.arg("--check");
+        }
+        let result = command.arg("series.patch").output().unwrap();
+        assert!(result.status.success(), "git apply failed: {}\n{}", String::from_utf8_lossy(&result.stderr), output);
+    };
+    git_apply(true);
+    git_apply(false);
+
+    for (name, _, right) in pairs {
+        let path = dir.path().join(name);
+        if right.is_empty() {
+            assert!(!path.exists(), "{} was not deleted", name);
+        } else {
+            assert_eq!(std::fs::read_to_string(path).unwrap(), right, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn test_git_format_file_modes() {
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Git)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    // every byte substituted: edit distance equals length but nothing was deleted
+    let rewritten = diff.compare_text("abcd\n", "wxyz\n").expect("diff failed");
+    let rewritten = rewritten.format().expect("format failed");
+    assert!(!rewritten.contains("deleted file mode"));
+    assert!(!rewritten.contains("new file mode"));
+
+    let emptied = diff.compare_text("line 1\nline 2\n", "").expect("diff failed");
+    let emptied = emptied.format().expect("format failed");
+    assert!(emptied.contains("deleted file mode 100644"));
+    assert!(!emptied.contains("new file mode"));
+
+    let created = diff.compare_text("", "line 1\nline 2\n").expect("diff failed");
+    let created = created.format().expect("format failed");
+    assert!(created.contains("new file mode 100644"));
+    assert!(!created.contains("deleted file mode"));
+}
+
 #[test]
 fn test_summary_format_content() {
     let diff = DiffBuilder::new()
@@ -119,6 +215,111 @@ fn test_summary_format_content() {
     assert!(output.contains("Operations:"));
 }
 
+#[test]
+fn test_summary_cost_breakdown() {
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Summary)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_text("hello world", "help wordy!!")
+        .expect("diff failed");
+
+    // unit costs: every modification adds exactly one to the distance
+    assert_eq!(result.statistics().weighted_cost, result.edit_distance());
+
+    let output = result.format().expect("format failed");
+    let breakdown: Vec<&str> = output.lines()
+        .skip_while(|line| *line != "Cost breakdown:")
+        .skip(1)
+        .take(3)
+        .collect();
+    assert_eq!(breakdown.len(), 3);
+
+    let percent_sum: f64 = breakdown.iter()
+        .map(|line| {
+            let start = line.find('(').expect("missing percentage") + 1;
+            let end = line.find("%)").expect("missing percentage");
+            line[start..end].parse::<f64>().expect("bad percentage")
+        })
+        .sum();
+    assert!((percent_sum - 100.0).abs() < 0.2, "percentages sum to {}", percent_sum);
+}
+
+#[test]
+fn test_side_by_side_format_separator() {
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::SideBySide)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_text("line 1\nline 2", "line 1\nchanged")
+        .expect("diff failed");
+
+    let output = result.format().expect("format failed");
+
+    // every row has a left and right column
+    assert!(output.contains('|'));
+    assert!(output.lines().any(|l| l.contains("line 2") && l.contains("| changed")));
+}
+
+#[test]
+fn test_operations_format_lists_each_operation() {
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Operations)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_text("abc", "axcd")
+        .expect("diff failed");
+
+    let output = result.format().expect("format failed");
+
+    assert!(output.contains(&format!("operations: {}", result.operations().len())));
+    for (i, op) in result.operations().iter().enumerate() {
+        let expected = match op {
+            ctdiff::DiffOperation::Keep => format!("{:4}: keep", i),
+            ctdiff::DiffOperation::KeepRun(n) => format!("{:4}: keep {}", i, n),
+            ctdiff::DiffOperation::Insert(b) => format!("{:4}: insert '{}'", i, char::from(*b)),
+            ctdiff::DiffOperation::Delete => format!("{:4}: delete", i),
+            ctdiff::DiffOperation::Substitute(b) => format!("{:4}: substitute '{}'", i, char::from(*b)),
+        };
+        assert!(output.contains(&expected), "missing {:?} in {}", expected, output);
+    }
+}
+
+#[test]
+fn test_compacted_operation_totals_match_expanded() {
+    use ctdiff::formats::{html, json, operations, summary, FormatOptions};
+
+    let left = b"the quick brown fox";
+    let right = b"the quick brawn fox!";
+    let expanded = ctdiff::constant_time_diff(left, right).expect("diff failed");
+    let mut compacted = expanded.clone();
+    compacted.compact();
+    assert!(compacted.operations.len() < expanded.operations.len());
+    assert_eq!(compacted.expanded_operation_count(), expanded.operations.len());
+
+    let options = FormatOptions::default();
+    let total = expanded.operations.len();
+    for result in [&expanded, &compacted] {
+        let html = html::format("a", "b", left, right, result, 3, &options).unwrap();
+        assert!(html.contains(&format!("<label>Operations:</label> {}</div>", total)), "{}", html);
+
+        let listing = operations::format("a", "b", left, right, result, false, &options).unwrap();
+        assert!(listing.contains(&format!("operations: {}\n", total)), "{}", listing);
+
+        let text = summary::format("a", "b", left, right, result, false, &options).unwrap();
+        assert!(text.contains(&format!("Total: {}\n", total)), "{}", text);
+
+        let value: Value = serde_json::from_str(&json::format("a", "b", left, right, result, &options).unwrap()).unwrap();
+        assert_eq!(value["statistics"]["operations"]["total"], total);
+    }
+}
+
 #[test]
 fn test_identical_files_all_formats() {
     let formats = vec![
@@ -127,6 +328,8 @@ fn test_identical_files_all_formats() {
         OutputFormat::Html,
         OutputFormat::Git,
         OutputFormat::Summary,
+        OutputFormat::SideBySide,
+        OutputFormat::Operations,
     ];
 
     let content = "identical content";
@@ -335,6 +538,68 @@ fn test_context_lines_unified_format() {
     }
 }
 
+#[test]
+fn test_unified_zero_context_has_no_context_lines() {
+    use ctdiff::formats::FormatOptions;
+
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Unified)
+        .format_options(FormatOptions { show_line_numbers: false, ..FormatOptions::default() })
+        .context_lines(0)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_text("line1\nline2\nline3\nline4\nline5", "line1\nline2\nchanged\nline4\nline5")
+        .expect("diff failed");
+    let output = result.format().expect("format failed");
+
+    let body: Vec<&str> = output.lines()
+        .filter(|l| !l.starts_with("---") && !l.starts_with("+++") && !l.starts_with("@@"))
+        .collect();
+    assert_eq!(body, vec!["-line3", "+changed"]);
+}
+
+#[test]
+fn test_unified_far_apart_changes_split_hunks() {
+    use ctdiff::formats::FormatOptions;
+
+    let left: Vec<String> = (1..=20).map(|i| format!("line{}", i)).collect();
+    let mut right = left.clone();
+    right[1] = "first change".to_string();
+    right[17] = "second change".to_string();
+
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Unified)
+        .format_options(FormatOptions { show_line_numbers: false, ..FormatOptions::default() })
+        .context_lines(2)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_text(&left.join("\n"), &right.join("\n"))
+        .expect("diff failed");
+    let output = result.format().expect("format failed");
+
+    assert_eq!(output.lines().filter(|l| l.starts_with("@@")).count(), 2);
+    // trailing context stops after exactly two equal lines
+    assert!(output.contains(" line4\n"));
+    assert!(!output.contains(" line5\n"));
+
+    // with enough context the gap is covered and the hunks merge
+    let merged = DiffBuilder::new()
+        .output_format(OutputFormat::Unified)
+        .context_lines(8)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(&left.join("\n"), &right.join("\n"))
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    assert_eq!(merged.lines().filter(|l| l.starts_with("@@")).count(), 1);
+}
+
 #[test]
 fn test_color_output_unified() {
     let diff_colored = DiffBuilder::new()
@@ -360,8 +625,8 @@ fn test_color_output_unified() {
     let output_plain = result_plain.format().expect("format failed");
 
     // both should work (color support depends on environment)
-    assert!(output_colored.len() > 0);
-    assert!(output_plain.len() > 0);
+    assert!(!output_colored.is_empty());
+    assert!(!output_plain.is_empty());
 }
 
 #[test]
@@ -370,8 +635,8 @@ fn test_file_comparison_integration() {
     use tempfile::NamedTempFile;
 
     // create temporary files
-    let mut file1 = NamedTempFile::new().expect("failed to create temp file");
-    let mut file2 = NamedTempFile::new().expect("failed to create temp file");
+    let file1 = NamedTempFile::new().expect("failed to create temp file");
+    let file2 = NamedTempFile::new().expect("failed to create temp file");
 
     let content1 = "file content line 1\nfile content line 2\n";
     let content2 = "file content line 1\nmodified line 2\n";
@@ -402,6 +667,77 @@ fn test_file_comparison_integration() {
     }
 }
 
+#[test]
+fn test_compare_files_uses_paths_as_names() {
+    use std::fs;
+    use tempfile::NamedTempFile;
+
+    let file1 = NamedTempFile::new().expect("failed to create temp file");
+    let file2 = NamedTempFile::new().expect("failed to create temp file");
+    fs::write(file1.path(), "line 1\nline 2\n").expect("failed to write file1");
+    fs::write(file2.path(), "line 1\nline two\n").expect("failed to write file2");
+
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Unified)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let output = diff.compare_files(file1.path(), file2.path())
+        .expect("file comparison failed")
+        .format()
+        .expect("format failed");
+    assert!(output.contains(&format!("--- {}", file1.path().display())));
+    assert!(output.contains(&format!("+++ {}", file2.path().display())));
+
+    let named = diff.compare_named(b"a", b"b", "old.txt", "new.txt")
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    assert!(named.contains("--- old.txt"));
+    assert!(named.contains("+++ new.txt"));
+}
+
+#[test]
+fn test_result_serde_round_trip() {
+    let formats = vec![
+        OutputFormat::Unified,
+        OutputFormat::Json,
+        OutputFormat::Html,
+        OutputFormat::Git,
+        OutputFormat::Summary,
+        OutputFormat::SideBySide,
+        OutputFormat::Operations,
+    ];
+
+    for format in formats {
+        let diff = DiffBuilder::new()
+            .output_format(format.clone())
+            .security_level(SecurityLevel::Fast)
+            .context_lines(1)
+            // keep json output reproducible across the round trip
+            .format_options(ctdiff::formats::FormatOptions { include_timestamp: false, ..Default::default() })
+            .build()
+            .expect("failed to build diff");
+
+        let result = diff.compare(b"line 1\nline 2\n\xff\x00\n", b"line 1\nchanged\n\xff\x00\n")
+            .expect("diff failed");
+
+        let cached = serde_json::to_string(&result).expect("serialize failed");
+        let json: Value = serde_json::from_str(&cached).expect("invalid json");
+        // buffers are stored as base64 strings, not byte arrays
+        assert!(json["left_data"].is_string());
+
+        let reloaded: ctdiff::DiffResult = serde_json::from_str(&cached).expect("deserialize failed");
+        assert_eq!(reloaded.edit_distance(), result.edit_distance());
+        assert_eq!(
+            reloaded.format().expect("format failed"),
+            result.format().expect("format failed"),
+            "{} output changed after round trip", format
+        );
+    }
+}
+
 #[test]
 fn test_round_trip_consistency() {
     // test that our diff operations are consistent
@@ -431,7 +767,7 @@ fn test_error_conditions() {
     // test various error conditions
     
     // security config validation
-    use ctdiff::security::{SecurityConfig, TimingProtection};
+    use ctdiff::security::{AffixTrim, SecurityConfig, TimingProtection, PaddingStrategy};
     
     let invalid_config = SecurityConfig {
         max_input_size: 10,  // very small
@@ -439,8 +775,14 @@ fn test_error_conditions() {
         padding_size: Some(1000), // larger than input limit
         validate_inputs: true,
         max_edit_distance: Some(5),
+        padding_byte: PaddingStrategy::default(),
         memory_protection: false, // disable memory protection with timing protection
         timing_protection: TimingProtection::Strict, // this should trigger warning
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
     };
     
     // this should trigger a warning but still be "valid" - let's just verify it runs
@@ -459,6 +801,18 @@ fn test_format_specific_options() {
         max_line_width: Some(80),
         show_line_numbers: true,
         word_diff: false,
+        show_moves: false,
+        json_schema_version: None,
+        include_timestamp: true,
+        deterministic: false,
+        json_omit_keeps: false,
+        max_hunks: None,
+        show_entropy: false,
+        git_line_numbers: false,
+        html_diffstat: false,
+        json_include_reconstructed: false,
+        rewrite_threshold: None,
+        hunk_context_pattern: None,
     };
     
     let diff = DiffBuilder::new()
@@ -484,8 +838,8 @@ async fn test_async_file_operations() {
     use tempfile::NamedTempFile;
     use std::fs;
 
-    let mut file1 = NamedTempFile::new().expect("failed to create temp file");
-    let mut file2 = NamedTempFile::new().expect("failed to create temp file");
+    let file1 = NamedTempFile::new().expect("failed to create temp file");
+    let file2 = NamedTempFile::new().expect("failed to create temp file");
 
     fs::write(file1.path(), "async test content").expect("failed to write file1");
     fs::write(file2.path(), "async modified content").expect("failed to write file2");
@@ -503,4 +857,806 @@ async fn test_async_file_operations() {
     assert!(result.edit_distance() > 0);
     
     let _output = result.format().expect("format failed");
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_write_to_async_matches_format() {
+    use tempfile::NamedTempFile;
+    use std::fs;
+
+    let file1 = NamedTempFile::new().expect("failed to create temp file");
+    let file2 = NamedTempFile::new().expect("failed to create temp file");
+
+    fs::write(file1.path(), "line 1\nline 2\nline 3\n").expect("failed to write file1");
+    fs::write(file2.path(), "line 1\nchanged\nline 3\n").expect("failed to write file2");
+
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Html)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_files_async_named(file1.path(), file2.path()).await
+        .expect("async file comparison failed");
+
+    let mut buffer: Vec<u8> = Vec::new();
+    result.write_to_async(&mut buffer).await.expect("async write failed");
+
+    let expected = result.format().expect("format failed");
+    assert_eq!(buffer, expected.as_bytes());
+    // the streamed output carries the real paths, not left/right placeholders
+    assert!(expected.contains(&file1.path().display().to_string()));
+}
+
+/// true if `needle` appears in `haystack` in order, not necessarily contiguously
+fn is_subsequence(needle: &[u8], haystack: &[u8]) -> bool {
+    let mut remaining = haystack.iter();
+    needle.iter().all(|byte| remaining.any(|b| b == byte))
+}
+
+#[test]
+fn test_longest_common_subsequence() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+
+    // the minimal script substitutes twice, so it keeps 3 bytes, one fewer
+    // than the longest common subsequence
+    let result = diff.compare(b"ABCBDAB", b"BDCAB").expect("diff failed");
+    let common = result.longest_common_subsequence();
+    assert_eq!(common.len(), result.lcs_length());
+    assert_eq!(result.lcs_length(), 3);
+    assert!(is_subsequence(&common, b"ABCBDAB"));
+    assert!(is_subsequence(&common, b"BDCAB"));
+
+    // without substitutions the script keeps a longest common subsequence
+    let no_substitution = DiffBuilder::new()
+        .security_config(ctdiff::SecurityConfig {
+            allow_substitution: false,
+            ..ctdiff::SecurityConfig::fast(None)
+        })
+        .build()
+        .expect("failed to build diff");
+    let result = no_substitution.compare(b"ABCBDAB", b"BDCAB").expect("diff failed");
+    assert_eq!(result.lcs_length(), 4);
+    assert!(is_subsequence(&result.longest_common_subsequence(), b"BDCAB"));
+
+    let result = diff.compare(b"identical input", b"identical input").expect("diff failed");
+    assert_eq!(result.longest_common_subsequence(), b"identical input");
+    assert_eq!(result.lcs_length(), 15);
+}
+
+#[test]
+fn test_detect_moved_line() {
+    let left = "moved to the bottom\nfirst line\nsecond line\nthird line\n";
+    let right = "first line\nsecond line\nthird line\nmoved to the bottom\n";
+
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let result = diff.compare_text(left, right).expect("diff failed");
+
+    let moves = result.detect_moves();
+    assert_eq!(moves.len(), 1, "expected one move, got {:?}", moves);
+    let block = moves[0];
+    assert_eq!(block.from_offset, 0);
+    assert!(block.len >= "moved to the bottom".len());
+    assert_eq!(
+        &left.as_bytes()[block.from_offset..block.from_offset + block.len],
+        &right.as_bytes()[block.to_offset..block.to_offset + block.len],
+    );
+
+    // an in-place edit isn't a move
+    let result = diff.compare_text("first line\nsecond line\n", "first line\nsecond lime\n")
+        .expect("diff failed");
+    assert!(result.detect_moves().is_empty());
+
+    let summary = DiffBuilder::new()
+        .output_format(OutputFormat::Summary)
+        .format_options(ctdiff::formats::FormatOptions { show_moves: true, ..Default::default() })
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(left, right)
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    assert!(summary.contains("1 block moved."), "summary missing moves: {}", summary);
+}
+
+/// checks `value` against the subset of json schema that `json::schema` uses
+fn validate_against_schema(value: &serde_json::Value, schema: &serde_json::Value, path: &str) -> Result<(), String> {
+    use serde_json::Value;
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            other => return Err(format!("{}: unsupported schema type {}", path, other)),
+        };
+        if !matches {
+            return Err(format!("{}: expected {}, got {}", path, expected, value));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!("{}: {} not in {:?}", path, value, allowed));
+        }
+    }
+    if let (Some(min), Some(n)) = (schema.get("minimum").and_then(Value::as_f64), value.as_f64()) {
+        if n < min {
+            return Err(format!("{}: {} below minimum {}", path, n, min));
+        }
+    }
+    if let (Some(max), Some(n)) = (schema.get("maximum").and_then(Value::as_f64), value.as_f64()) {
+        if n > max {
+            return Err(format!("{}: {} above maximum {}", path, n, max));
+        }
+    }
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for required in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+            let key = required.as_str().unwrap();
+            if !object.contains_key(key) {
+                return Err(format!("{}: missing required {}", path, key));
+            }
+        }
+        for (key, child) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(child_schema) => validate_against_schema(child, child_schema, &format!("{}.{}", path, key))?,
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    return Err(format!("{}: unexpected property {}", path, key));
+                }
+                None => {}
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            validate_against_schema(item, items, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_json_output_matches_schema() {
+    use ctdiff::formats::{json, FormatOptions};
+
+    let schema = json::schema();
+    assert_eq!(json::SCHEMA_VERSION, "1.0");
+
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Json)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let result = diff.compare_text("line one\nline two\n", "line one\nline 2\nline three\n")
+        .expect("diff failed");
+    let value: serde_json::Value = serde_json::from_str(&result.format().unwrap()).unwrap();
+    validate_against_schema(&value, &schema, "$").unwrap();
+    assert_eq!(value["metadata"]["format_version"], json::SCHEMA_VERSION);
+
+    // the validator does reject documents that break the schema
+    let mut broken = value.clone();
+    broken["operations"][0]["type"] = "teleport".into();
+    assert!(validate_against_schema(&broken, &schema, "$").is_err());
+
+    // pinning a supported version works, an unknown one is an error
+    let pinned = |version: &str| DiffBuilder::new()
+        .output_format(OutputFormat::Json)
+        .format_options(FormatOptions { json_schema_version: Some(version.to_string()), ..Default::default() })
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text("a", "b")
+        .expect("diff failed")
+        .format();
+    let output: serde_json::Value = serde_json::from_str(&pinned("1.0").unwrap()).unwrap();
+    assert_eq!(output["metadata"]["format_version"], "1.0");
+    assert!(matches!(pinned("2.0"), Err(ctdiff::Error::Configuration { .. })));
+}
+
+#[test]
+fn test_json_timestamp_is_rfc3339() {
+    use ctdiff::formats::FormatOptions;
+
+    let json_for = |include_timestamp: bool| -> Value {
+        let output = DiffBuilder::new()
+            .output_format(OutputFormat::Json)
+            .format_options(FormatOptions { include_timestamp, ..Default::default() })
+            .security_level(SecurityLevel::Fast)
+            .build()
+            .expect("failed to build diff")
+            .compare_text("old", "new")
+            .expect("diff failed")
+            .format()
+            .expect("format failed");
+        serde_json::from_str(&output).expect("invalid json")
+    };
+
+    let with = json_for(true);
+    let timestamp = with["metadata"]["timestamp"].as_str().expect("timestamp missing");
+    chrono::DateTime::parse_from_rfc3339(timestamp).expect("timestamp is not rfc 3339");
+    assert!(timestamp.ends_with('Z'));
+
+    let without = json_for(false);
+    assert!(without["metadata"].get("timestamp").is_none());
+}
+
+#[test]
+fn test_deterministic_output() {
+    use ctdiff::formats::FormatOptions;
+
+    let format_twice = |format: OutputFormat, deterministic: bool| {
+        let result = DiffBuilder::new()
+            .output_format(format)
+            .format_options(FormatOptions { deterministic, ..Default::default() })
+            .color(true)
+            .security_level(SecurityLevel::Fast)
+            .build()
+            .expect("failed to build diff")
+            .compare_text("line 1\nline 2\n", "line 1\nline two\n")
+            .expect("diff failed");
+        let first = result.format().expect("format failed");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        (first, result.format().expect("format failed"))
+    };
+
+    for format in [OutputFormat::Json, OutputFormat::Html, OutputFormat::Git, OutputFormat::Unified] {
+        let (first, second) = format_twice(format.clone(), true);
+        assert_eq!(first, second, "{} output is not reproducible", format);
+        assert!(!first.contains('\x1b'), "{} output is colored", format);
+    }
+
+    let (first, second) = format_twice(OutputFormat::Json, false);
+    let first: Value = serde_json::from_str(&first).unwrap();
+    let second: Value = serde_json::from_str(&second).unwrap();
+    assert_ne!(first["metadata"]["timestamp"], second["metadata"]["timestamp"]);
+}
+
+#[test]
+fn test_json_omit_keeps() {
+    use ctdiff::formats::FormatOptions;
+
+    let left = vec![b'a'; 1000];
+    let mut right = left.clone();
+    right[500] = b'b';
+
+    let json_for = |json_omit_keeps: bool| -> Value {
+        let output = DiffBuilder::new()
+            .output_format(OutputFormat::Json)
+            .format_options(FormatOptions { json_omit_keeps, ..Default::default() })
+            .security_level(SecurityLevel::Fast)
+            .build()
+            .expect("failed to build diff")
+            .compare(&left, &right)
+            .expect("diff failed")
+            .format()
+            .expect("format failed");
+        serde_json::from_str(&output).expect("invalid json")
+    };
+
+    let full = json_for(false);
+    let compact = json_for(true);
+    let full_ops = full["operations"].as_array().unwrap();
+    let compact_ops = compact["operations"].as_array().unwrap();
+
+    // keep, substitute, keep
+    assert_eq!(compact_ops.len(), 3);
+    assert!(compact_ops.len() * 100 < full_ops.len());
+    assert_eq!(compact_ops[0]["type"], "keep_run");
+    assert_eq!(compact_ops[0]["length"], 500);
+    assert_eq!(compact_ops[1]["type"], "substitute");
+    assert_eq!(compact_ops[2]["position"], 501);
+    assert_eq!(compact_ops[2]["length"], 499);
+
+    assert_eq!(compact["statistics"]["operations"]["keeps"], 999);
+    assert_eq!(compact["statistics"], full["statistics"]);
+    validate_against_schema(&compact, &ctdiff::formats::json::schema(), "$").unwrap();
+}
+
+#[test]
+fn test_hunks_api() {
+    use ctdiff::formats::{HunkLine, LineKind};
+    
+    let left: Vec<String> = (1..=10).map(|i| format!("line{}", i)).collect();
+    let mut right = left.clone();
+    right.remove(1);
+    right.insert(7, "new".to_string());
+    
+    let result = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .context_lines(1)
+        .format_options(ctdiff::formats::FormatOptions { show_line_numbers: false, ..Default::default() })
+        .build()
+        .expect("failed to build diff")
+        .compare_text(&left.join("\n"), &right.join("\n"))
+        .expect("diff failed");
+    
+    let hunks = result.hunks(1);
+    assert_eq!(hunks.len(), 2);
+    
+    // deleting line 2 shifts later right-side line numbers up by one
+    let first = &hunks[0];
+    assert_eq!((first.old_start, first.old_count, first.new_start, first.new_count), (1, 3, 1, 2));
+    assert_eq!(first.lines, vec![
+        HunkLine { kind: LineKind::Context, content: "line1".to_string() },
+        HunkLine { kind: LineKind::Delete, content: "line2".to_string() },
+        HunkLine { kind: LineKind::Context, content: "line3".to_string() },
+    ]);
+    
+    let second = &hunks[1];
+    assert_eq!((second.old_start, second.old_count, second.new_start, second.new_count), (8, 2, 7, 3));
+    assert_eq!(second.lines[1], HunkLine { kind: LineKind::Insert, content: "new".to_string() });
+    
+    // the text formats print the same headers at the configured context
+    let unified = result.format_as(OutputFormat::Unified).expect("format failed");
+    let git = result.format_as(OutputFormat::Git).expect("format failed");
+    for output in [unified, git] {
+        let headers: Vec<&str> = output.lines().filter(|l| l.starts_with("@@")).collect();
+        assert_eq!(headers, vec!["@@ -1,3 +1,2 @@", "@@ -8,2 +7,3 @@"]);
+    }
+    
+    // wide context merges both changes into one hunk
+    assert_eq!(result.hunks(3).len(), 1);
+    assert!(DiffBuilder::new().build().unwrap().compare_text("same", "same").unwrap().hunks(3).is_empty());
+}
+
+#[test]
+fn test_max_line_width_multibyte() {
+    use ctdiff::formats::FormatOptions;
+    
+    // 3-byte characters; the old byte-based cut at width - 3 = 5 landed mid-character
+    let left = "日本語".repeat(10);
+    let right = format!("{}!", left);
+    
+    let output = DiffBuilder::new()
+        .output_format(OutputFormat::Unified)
+        .format_options(FormatOptions {
+            max_line_width: Some(8),
+            show_line_numbers: false,
+            ..FormatOptions::default()
+        })
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(&left, &right)
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    
+    let body: Vec<&str> = output.lines()
+        .filter(|l| l.starts_with('-') || l.starts_with('+'))
+        .filter(|l| !l.starts_with("---") && !l.starts_with("+++"))
+        .collect();
+    // eight characters including the ellipsis
+    assert_eq!(body, vec!["-日本語日本語日…", "+日本語日本語日…"]);
+    
+    // short lines are left alone
+    let short = DiffBuilder::new()
+        .format_options(FormatOptions { max_line_width: Some(8), show_line_numbers: false, ..FormatOptions::default() })
+        .build()
+        .unwrap()
+        .compare_text("日本", "日本語")
+        .unwrap()
+        .format()
+        .unwrap();
+    assert!(short.contains("+日本語\n"));
+}
+
+#[test]
+fn test_max_hunks_caps_output() {
+    // a change every tenth line gives five separate hunks at one line of context
+    let left: Vec<String> = (1..=50).map(|i| format!("line{}", i)).collect();
+    let mut right = left.clone();
+    for i in (4..50).step_by(10) {
+        right[i] = format!("changed{}", i);
+    }
+    let (left, right) = (left.join("\n"), right.join("\n"));
+    
+    let diff_with = |max_hunks: Option<usize>| DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .context_lines(1)
+        .format_options(ctdiff::formats::FormatOptions { max_hunks, ..Default::default() })
+        .build()
+        .expect("failed to build diff")
+        .compare_text(&left, &right)
+        .expect("diff failed");
+    
+    let capped = diff_with(Some(2));
+    for format in [OutputFormat::Unified, OutputFormat::Git] {
+        let output = capped.format_as(format).expect("format failed");
+        assert_eq!(output.lines().filter(|l| l.starts_with("@@")).count(), 2, "{}", output);
+        assert!(output.ends_with("… and 3 more hunks omitted\n"), "{}", output);
+    }
+    
+    // a cap above the hunk count changes nothing
+    let uncapped = diff_with(None);
+    let roomy = diff_with(Some(5));
+    for format in [OutputFormat::Unified, OutputFormat::Git] {
+        let output = roomy.format_as(format.clone()).expect("format failed");
+        assert_eq!(output, uncapped.format_as(format).expect("format failed"));
+        assert_eq!(output.lines().filter(|l| l.starts_with("@@")).count(), 5);
+        assert!(!output.contains("omitted"));
+    }
+}
+
+#[test]
+fn test_shannon_entropy() {
+    use ctdiff::formats::summary::shannon_entropy;
+    
+    assert_eq!(shannon_entropy(&[]), 0.0);
+    assert_eq!(shannon_entropy(&[0u8; 4096]), 0.0);
+    // every byte value equally often is the maximum
+    let uniform: Vec<u8> = (0..=255u8).cycle().take(256 * 16).collect();
+    assert!((shannon_entropy(&uniform) - 8.0).abs() < 1e-9);
+    
+    // pseudo-random bytes come close to it
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let random: Vec<u8> = (0..65536)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 56) as u8
+        })
+        .collect();
+    let entropy = shannon_entropy(&random);
+    assert!(entropy > 7.99 && entropy <= 8.0, "{}", entropy);
+    
+    let summary = |data: &[u8], show_entropy: bool| DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .output_format(OutputFormat::Summary)
+        .format_options(ctdiff::formats::FormatOptions { show_entropy, ..Default::default() })
+        .build()
+        .expect("failed to build diff")
+        .compare(b"plain text", data)
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    
+    let output = summary(&random[..512], true);
+    assert!(output.contains("Left: "), "{}", output);
+    assert_eq!(output.matches("likely encrypted or compressed").count(), 1, "{}", output);
+    assert!(!summary(&random[..512], false).contains("bits/byte"));
+}
+
+#[test]
+fn test_invalid_utf8_reported_as_binary() {
+    let compare = |left: &[u8], right: &[u8]| DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_named(left, right, "old.bin", "new.bin")
+        .expect("diff failed");
+    
+    // an invalid byte mid-line, and a multibyte character cut short at the end
+    for (left, right) in [
+        (&b"header\nvalue \xff\nfooter\n"[..], &b"header\nvalue \xfe\nfooter\n"[..]),
+        (&b"caf\xc3\xa9"[..], &b"caf\xc3"[..]),
+    ] {
+        let result = compare(left, right);
+        
+        let unified = result.format_as(OutputFormat::Unified).expect("format failed");
+        assert_eq!(unified, "Binary files old.bin and new.bin differ\n");
+        
+        let git = result.format_as(OutputFormat::Git).expect("format failed");
+        assert!(git.starts_with("diff --git a/old.bin b/new.bin\n"), "{}", git);
+        assert!(git.ends_with("Binary files a/old.bin and b/new.bin differ\n"), "{}", git);
+        assert!(!git.contains("@@") && !git.contains('\u{fffd}'), "{}", git);
+        
+        let json: Value = serde_json::from_str(&result.format_as(OutputFormat::Json).expect("format failed"))
+            .expect("invalid json");
+        assert_eq!(json["metadata"]["contains_binary"], true);
+        
+        let summary = result.format_as(OutputFormat::Summary).expect("format failed");
+        assert!(summary.contains("Contains Binary: true"), "{}", summary);
+    }
+    
+    // valid utf-8 keeps its text diff and is flagged as text
+    let text = compare("naïve\n".as_bytes(), "naive\n".as_bytes());
+    assert!(text.format_as(OutputFormat::Unified).expect("format failed").contains("+naive"));
+    let json: Value = serde_json::from_str(&text.format_as(OutputFormat::Json).expect("format failed"))
+        .expect("invalid json");
+    assert_eq!(json["metadata"]["contains_binary"], false);
+    assert!(!text.format_as(OutputFormat::Summary).expect("format failed").contains("Contains Binary"));
+    
+    // identical binary inputs have nothing to report
+    let same = compare(b"\xff\xfe", b"\xff\xfe");
+    assert!(!same.format_as(OutputFormat::Unified).expect("format failed").contains("Binary"));
+}
+
+#[test]
+fn test_git_hunk_starts_at_line_one() {
+    use ctdiff::formats::FormatOptions;
+    
+    let left = "first\nsecond\nthird\nfourth\nfifth\n";
+    let right = "FIRST\nsecond\nthird\nfourth\nfifth\n";
+    let compare = |git_line_numbers: bool| DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .output_format(OutputFormat::Git)
+        .context_lines(3)
+        .format_options(FormatOptions { git_line_numbers, ..Default::default() })
+        .build()
+        .expect("failed to build diff")
+        .compare_text(left, right)
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    
+    // no leading context exists before line 1, so the hunk starts there
+    let plain = compare(false);
+    assert!(plain.contains("@@ -1,4 +1,4 @@\n-first\n+FIRST\n second\n"), "{}", plain);
+    
+    let numbered = compare(true);
+    assert!(numbered.contains("@@ -1,4 +1,4 @@\n   1 -first\n   1 +FIRST\n   2  second\n"), "{}", numbered);
+    assert!(numbered.contains("   4  fourth\n"), "{}", numbered);
+}
+
+#[test]
+fn test_html_diffstat() {
+    use ctdiff::formats::{html, FormatOptions};
+
+    let render = |left: &str, right: &str, html_diffstat: bool| DiffBuilder::new()
+        .output_format(OutputFormat::Html)
+        .format_options(FormatOptions { html_diffstat, ..Default::default() })
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(left, right)
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+    let blocks = |output: &str| (
+        output.matches("class=\"diffstat-insert\"").count(),
+        output.matches("class=\"diffstat-delete\"").count(),
+    );
+
+    // mostly insertions: more green blocks than red, within the cap
+    let output = render("short line\n", "short line\nand a good deal of new text after it\n", true);
+    let (inserts, deletes) = blocks(&output);
+    assert!(inserts > deletes, "{} inserts, {} deletes", inserts, deletes);
+    assert!(inserts + deletes <= html::DIFFSTAT_BLOCKS);
+
+    // a small diff gets one block per change
+    assert_eq!(blocks(&render("abc", "abXc", true)), (1, 0));
+
+    // the cap holds however large the diff, and both sides stay visible
+    let left = "x".repeat(500);
+    let right = format!("{}{}", "y".repeat(3), "x".repeat(400));
+    let (inserts, deletes) = blocks(&render(&left, &right, true));
+    assert_eq!(inserts + deletes, html::DIFFSTAT_BLOCKS);
+    assert!(inserts >= 1 && deletes > inserts);
+
+    // off by default
+    assert_eq!(blocks(&render("abc", "abXc", false)), (0, 0));
+}
+
+#[test]
+fn test_summary_classifies_change() {
+    use ctdiff::ChangeKind;
+
+    let diff = DiffBuilder::new()
+        .output_format(OutputFormat::Summary)
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff");
+    let classify = |left: &str, right: &str| diff.compare_text(left, right).expect("diff failed").classify();
+
+    assert_eq!(classify("log line\n", "log line\n"), ChangeKind::Identical);
+    assert_eq!(classify("log line\n", "log line\nanother line\n"), ChangeKind::PureInsertion);
+    assert_eq!(classify("log line\nanother line\n", "log line\n"), ChangeKind::PureDeletion);
+    assert_eq!(classify("ab", "ba"), ChangeKind::PureSubstitution);
+    assert_eq!(classify("hello", "jello world"), ChangeKind::Mixed);
+
+    let output = diff.compare_text("log line\n", "log line\nanother line\n").unwrap().format().unwrap();
+    assert!(output.contains("Change Kind: pure insertion\n"), "{}", output);
+}
+
+#[test]
+fn test_json_include_reconstructed() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use ctdiff::formats::FormatOptions;
+
+    let left = b"header\n\x00\xff\xfe binary\n";
+    let right = b"header\n\x00\x01\xfe binary tail\n";
+    let render = |json_include_reconstructed: bool| -> Value {
+        let output = DiffBuilder::new()
+            .output_format(OutputFormat::Json)
+            .format_options(FormatOptions { json_include_reconstructed, ..Default::default() })
+            .security_level(SecurityLevel::Fast)
+            .build()
+            .expect("failed to build diff")
+            .compare(left, right)
+            .expect("diff failed")
+            .format()
+            .expect("format failed");
+        serde_json::from_str(&output).unwrap()
+    };
+
+    let value = render(true);
+    let encoded = value["reconstructed_right"].as_str().expect("missing reconstructed_right");
+    assert_eq!(STANDARD.decode(encoded).unwrap(), right);
+    assert_eq!(value["metadata"]["contains_binary"], true);
+
+    assert!(render(false).get("reconstructed_right").is_none());
+}
+
+#[test]
+fn test_rewrite_threshold() {
+    use ctdiff::formats::FormatOptions;
+
+    let left = format!("{}\nkeep\n{}\n", "a".repeat(40), "b".repeat(40));
+    let right = format!("{}\nkeep\n{}\n", "c".repeat(40), "d".repeat(40));
+    let compare = |format: OutputFormat, rewrite_threshold: Option<f64>| DiffBuilder::new()
+        .output_format(format)
+        .format_options(FormatOptions { rewrite_threshold, show_line_numbers: false, ..Default::default() })
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(&left, &right)
+        .expect("diff failed");
+
+    let result = compare(OutputFormat::Unified, Some(0.3));
+    assert!(result.similarity() < 0.1, "similarity {}", result.similarity());
+
+    // the matching line is interleaved as context by default
+    let minimal = compare(OutputFormat::Unified, None).format().unwrap();
+    assert!(minimal.contains("\n keep\n"), "{}", minimal);
+
+    // under the threshold every left line goes, then every right line comes
+    let rewritten = result.format().unwrap();
+    let body: Vec<&str> = rewritten.lines().skip_while(|line| !line.starts_with("@@")).skip(1).collect();
+    let expected_a = format!("-{}", "a".repeat(40));
+    let expected_b = format!("-{}", "b".repeat(40));
+    let expected_c = format!("+{}", "c".repeat(40));
+    let expected_d = format!("+{}", "d".repeat(40));
+    assert_eq!(body, vec![
+        expected_a.as_str(), "-keep", expected_b.as_str(),
+        expected_c.as_str(), "+keep", expected_d.as_str(),
+    ]);
+    assert!(rewritten.contains("@@ -1,3 +1,3 @@"), "{}", rewritten);
+
+    // only rendering changes, not the result
+    assert_eq!(result.operations(), compare(OutputFormat::Unified, None).operations());
+    let git = compare(OutputFormat::Git, Some(0.3)).format().unwrap();
+    assert!(git.contains("-keep\n") && git.contains("+keep\n"), "{}", git);
+    assert!(!compare(OutputFormat::Git, Some(0.05)).format().unwrap().contains("-keep\n"));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_hunk_context_pattern() {
+    use ctdiff::formats::{unified, FormatOptions};
+
+    let left = "import os\n\ndef load(path):\n    with open(path) as f:\n        data = f.read()\n    return data\n";
+    let right = "import os\n\ndef load(path):\n    with open(path) as f:\n        data = f.read().strip()\n    return data\n";
+    let unified = |hunk_context_pattern: Option<&str>| DiffBuilder::new()
+        .output_format(OutputFormat::Unified)
+        .format_options(FormatOptions {
+            hunk_context_pattern: hunk_context_pattern.map(str::to_string),
+            ..Default::default()
+        })
+        .security_level(SecurityLevel::Fast)
+        .context_lines(1)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(left, right)
+        .expect("diff failed")
+        .format();
+
+    let output = unified(Some(unified::DEFAULT_HUNK_CONTEXT_PATTERN)).unwrap();
+    assert!(output.contains("@@ -4,3 +4,3 @@ def load(path):\n"), "{}", output);
+
+    let rust = "fn parse() {\n    let x = 1;\n    let y = 2;\n}\n";
+    let output = DiffBuilder::new()
+        .format_options(FormatOptions { hunk_context_pattern: Some(r"^\s*fn ".to_string()), ..Default::default() })
+        .security_level(SecurityLevel::Fast)
+        .context_lines(0)
+        .build()
+        .expect("failed to build diff")
+        .compare_text(rust, &rust.replace("y = 2", "y = 3"))
+        .expect("diff failed")
+        .format()
+        .unwrap();
+    assert!(output.contains("@@ -3,1 +3,1 @@ fn parse() {\n"), "{}", output);
+
+    // off by default, and a bad pattern is a configuration error
+    assert!(unified(None).unwrap().contains("@@ -4,3 +4,3 @@\n"));
+    assert!(matches!(unified(Some("(")), Err(ctdiff::Error::Configuration { .. })));
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn test_hunk_context_pattern_needs_regex_feature() {
+    use ctdiff::formats::FormatOptions;
+
+    let result = DiffBuilder::new()
+        .format_options(FormatOptions { hunk_context_pattern: Some(r"^\S".to_string()), ..Default::default() })
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .expect("failed to build diff")
+        .compare_text("a\n  b\n", "a\n  c\n")
+        .expect("diff failed");
+    assert!(matches!(result.format(), Err(ctdiff::Error::Configuration { .. })));
+}
+
+#[test]
+fn test_format_stream_matches_format() {
+    use ctdiff::formats::FormatOptions;
+    use std::io::Write;
+
+    /// records the size of every write it receives
+    #[derive(Default)]
+    struct ChunkedWriter {
+        data: Vec<u8>,
+        largest_write: usize,
+    }
+
+    impl Write for ChunkedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.largest_write = self.largest_write.max(buf.len());
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let left: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+    let right = left.replace("line 3\n", "line three\n").replace("line 30\n", "");
+    let formats = [
+        OutputFormat::Unified,
+        OutputFormat::Json,
+        OutputFormat::Html,
+        OutputFormat::Git,
+        OutputFormat::Summary,
+        OutputFormat::SideBySide,
+        OutputFormat::Operations,
+    ];
+    let options = [
+        FormatOptions::default(),
+        FormatOptions::minimal(),
+        FormatOptions::verbose(),
+        FormatOptions { json_omit_keeps: true, json_pretty: false, ..Default::default() },
+    ]
+    // no timestamp, which would differ between the two renders
+    .map(|options| FormatOptions { include_timestamp: false, ..options });
+
+    for format in formats {
+        for options in &options {
+            let result = DiffBuilder::new()
+                .output_format(format.clone())
+                .format_options(options.clone())
+                .security_level(SecurityLevel::Fast)
+                .build()
+                .expect("failed to build diff")
+                .compare(left.as_bytes(), right.as_bytes())
+                .expect("diff failed");
+            let formatted = result.format().expect("format failed");
+
+            let mut writer = ChunkedWriter::default();
+            result.format_stream(&mut writer).expect("format_stream failed");
+            assert_eq!(String::from_utf8(writer.data).unwrap(), formatted, "{} with {:?}", format, options);
+
+            // the streamed formats never hand over the whole output at once
+            if matches!(format, OutputFormat::Unified | OutputFormat::Git | OutputFormat::Json) {
+                assert!(writer.largest_write < formatted.len(), "{} with {:?}", format, options);
+            }
+
+            let mut written = Vec::new();
+            result.write_to(&mut written).expect("write_to failed");
+            assert_eq!(written, formatted.as_bytes());
+        }
+    }
+}
@@ -64,7 +64,7 @@ fn test_ct_copy_if_correctness() {
     assert_eq!(dst, [5, 6, 7, 8]);
     
     // don't copy when condition is false
-    let original = dst.clone();
+    let original = dst;
     ct_copy_if(&mut dst, &[9, 10, 11, 12], Choice::from(0));
     assert_eq!(dst, original);
 }
@@ -97,6 +97,25 @@ fn test_ct_memcmp_correctness() {
     assert!(ct_memcmp(b"abcd", b"abc") > 0);
 }
 
+#[test]
+fn test_ct_cmp_correctness() {
+    use std::cmp::Ordering;
+    
+    assert_eq!(ct_cmp(b"hello", b"hello"), Ordering::Equal);
+    assert_eq!(ct_cmp(b"", b""), Ordering::Equal);
+    assert_eq!(ct_cmp(b"abc", b"abd"), Ordering::Less);
+    assert_eq!(ct_cmp(b"b", b"a"), Ordering::Greater);
+    assert_eq!(ct_cmp(b"abc", b"abcd"), Ordering::Less);
+    assert_eq!(ct_cmp(b"abcd", b"abc"), Ordering::Greater);
+    // byte differences beat length
+    assert_eq!(ct_cmp(b"b", b"abcd"), Ordering::Greater);
+    
+    // usable as a sort key
+    let mut keys: Vec<&[u8]> = vec![b"pear", b"apple", b"", b"app"];
+    keys.sort_by(|a, b| ct_cmp(a, b));
+    assert_eq!(keys, vec![&b""[..], b"app", b"apple", b"pear"]);
+}
+
 // property-based tests to verify behavior across wide input space
 proptest! {
     #[test]
@@ -126,6 +145,20 @@ proptest! {
         prop_assert!(max_val == a || max_val == b);
     }
     
+    #[test]
+    fn prop_ct_min_slice_matches_std(data in prop::collection::vec(any::<u32>(), 1..100)) {
+        prop_assert_eq!(ct_min_slice(&data), *data.iter().min().unwrap());
+    }
+    
+    #[test]
+    fn prop_ct_argmin_slice_returns_first_min(
+        // a small value range makes ties common
+        data in prop::collection::vec(0u32..4, 1..100)
+    ) {
+        let min = *data.iter().min().unwrap();
+        prop_assert_eq!(ct_argmin_slice(&data), data.iter().position(|&value| value == min).unwrap());
+    }
+    
     #[test]
     fn prop_ct_lookup_bounds(
         data in prop::collection::vec(any::<u8>(), 1..100),
@@ -153,6 +186,26 @@ proptest! {
         };
         prop_assert_eq!(ct_result.signum(), expected_sign);
     }
+    
+    #[test]
+    fn prop_ct_cmp_matches_std(
+        a in prop::collection::vec(any::<u8>(), 0..50),
+        b in prop::collection::vec(any::<u8>(), 0..50)
+    ) {
+        prop_assert_eq!(ct_cmp(&a, &b), a.cmp(&b));
+    }
+    
+    #[test]
+    fn prop_ct_cmp_matches_std_on_shared_prefix(
+        prefix in prop::collection::vec(any::<u8>(), 0..20),
+        a_tail in prop::collection::vec(any::<u8>(), 0..5),
+        b_tail in prop::collection::vec(any::<u8>(), 0..5)
+    ) {
+        // random vectors rarely share a prefix; force the length tiebreak
+        let a = [prefix.as_slice(), &a_tail].concat();
+        let b = [prefix.as_slice(), &b_tail].concat();
+        prop_assert_eq!(ct_cmp(&a, &b), a.cmp(&b));
+    }
 }
 
 // timing attack resistance tests (structural verification)
@@ -192,4 +245,9 @@ fn test_large_inputs() {
     
     // test ct_memcmp with large inputs
     assert!(ct_memcmp(&a, &b) < 0);
-}
\ No newline at end of file
+}
+#[test]
+#[should_panic(expected = "empty slice")]
+fn test_ct_min_slice_panics_on_empty() {
+    ct_min_slice(&[]);
+}
@@ -0,0 +1,59 @@
+//! tests for the non-constant-time similarity heuristics
+
+use ctdiff::similarity::ngram_jaccard;
+
+#[test]
+fn test_ngram_jaccard_identical_inputs() {
+    let text = b"the quick brown fox";
+    assert_eq!(ngram_jaccard(text, text, 3), 1.0);
+    assert_eq!(ngram_jaccard(b"", b"", 3), 1.0);
+}
+
+#[test]
+fn test_ngram_jaccard_disjoint_inputs() {
+    assert_eq!(ngram_jaccard(b"aaaaaa", b"bbbbbb", 2), 0.0);
+    assert_eq!(ngram_jaccard(b"abc", b"", 2), 0.0);
+}
+
+#[test]
+fn test_ngram_jaccard_partial_overlap() {
+    // {ab, bc, cd} and {ab, bc, ce} share 2 of 4 distinct bigrams
+    assert_eq!(ngram_jaccard(b"abcd", b"abce", 2), 0.5);
+
+    // {abc, bcd, cde} and {bcd, cde, def} share 2 of 4 trigrams
+    assert_eq!(ngram_jaccard(b"abcde", b"bcdef", 3), 0.5);
+
+    // {ab, bc} and {ab, bx, xc} share 1 of 4
+    assert_eq!(ngram_jaccard(b"abc", b"abxc", 2), 0.25);
+}
+
+#[test]
+fn test_pairwise_matrix() {
+    use ctdiff::similarity::{pairwise_matrix, SimilarityMetric};
+    use ctdiff::{DiffBuilder, SecurityLevel};
+
+    let docs: [&[u8]; 3] = [b"abcd", b"abce", b"abxd"];
+
+    // one substitution out of four bytes, except between the last two
+    let by_distance = pairwise_matrix(&docs, SimilarityMetric::EditDistance).unwrap();
+    assert_eq!(by_distance, vec![
+        vec![1.0, 0.75, 0.75],
+        vec![0.75, 1.0, 0.5],
+        vec![0.75, 0.5, 1.0],
+    ]);
+
+    // the first two share two of four bigrams, the others only "ab" of five
+    let by_bigrams = pairwise_matrix(&docs, SimilarityMetric::NgramJaccard(2)).unwrap();
+    assert_eq!(by_bigrams, vec![
+        vec![1.0, 0.5, 0.2],
+        vec![0.5, 1.0, 0.2],
+        vec![0.2, 0.2, 1.0],
+    ]);
+
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .build()
+        .unwrap();
+    assert_eq!(diff.pairwise_similarity(&docs).unwrap(), by_distance);
+    assert!(diff.pairwise_similarity(&[]).unwrap().is_empty());
+}
@@ -0,0 +1,106 @@
+//! tests for the optional tracing instrumentation
+//!
+//! a small recording subscriber captures span names and every recorded field
+//! so the tests can check what the algorithm reports and what it leaves out
+
+#![cfg(feature = "tracing")]
+
+use ctdiff::types::SecurityConfig;
+use ctdiff::{AffixTrim, ConstantTimeDiff, PaddingStrategy};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[derive(Default)]
+struct Recorded {
+    spans: Vec<String>,
+    fields: Vec<(String, String)>,
+}
+
+struct Recorder(Arc<Mutex<Recorded>>);
+
+struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut recorded = self.0.lock().unwrap();
+        recorded.spans.push(span.metadata().name().to_string());
+        span.record(&mut FieldVisitor(&mut recorded.fields));
+        Id::from_u64(recorded.spans.len() as u64)
+    }
+
+    fn record(&self, _span: &Id, values: &Record<'_>) {
+        values.record(&mut FieldVisitor(&mut self.0.lock().unwrap().fields));
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        event.record(&mut FieldVisitor(&mut self.0.lock().unwrap().fields));
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// runs `f` with the recorder installed and returns what it captured
+fn record<F: FnOnce()>(f: F) -> Recorded {
+    let recorded = Arc::new(Mutex::new(Recorded::default()));
+    tracing::subscriber::with_default(Recorder(recorded.clone()), f);
+    Arc::try_unwrap(recorded).ok().unwrap().into_inner().unwrap()
+}
+
+#[test]
+fn test_spans_record_lengths_not_content() {
+    let config = SecurityConfig {
+        max_input_size: 1024,
+        pad_inputs: true,
+        padding_size: Some(64),
+        validate_inputs: true,
+        max_edit_distance: None,
+        padding_byte: PaddingStrategy::Fixed(b'#'),
+        timing_protection: Default::default(),
+        fast_identical_check: false,
+        max_operations: None,
+        affix_trim: AffixTrim::None,
+        max_matrix_bytes: None,
+        allow_substitution: true,
+    };
+    let left = b"password=hunter2";
+    let right = b"password=swordfish";
+
+    let recorded = record(|| {
+        ConstantTimeDiff::new(config).diff(left, right).expect("diff failed");
+    });
+
+    for name in ["pad_inputs", "compute_edit_matrix", "extract_edit_script_with_limits"] {
+        assert!(recorded.spans.iter().any(|span| span == name), "missing span {}", name);
+    }
+
+    let field = |name: &str| recorded.fields.iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.clone());
+    assert_eq!(field("len_a").as_deref(), Some("16"));
+    assert_eq!(field("len_b").as_deref(), Some("18"));
+    assert_eq!(field("pad_size").as_deref(), Some("64"));
+    assert!(field("edit_distance").is_some());
+
+    // neither the inputs nor any of their distinctive bytes show up
+    for (name, value) in &recorded.fields {
+        for secret in ["hunter2", "swordfish", "password", "104, 117"] {
+            assert!(!value.contains(secret), "field {} leaks {:?}: {}", name, secret, value);
+        }
+    }
+}
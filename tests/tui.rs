@@ -0,0 +1,46 @@
+//! smoke tests for the interactive viewer's view model
+//!
+//! only the view model is exercised; the terminal is never put in raw mode
+
+#![cfg(feature = "tui")]
+
+use ctdiff::tui::{DiffView, LineKind};
+use ctdiff::{DiffBuilder, SecurityLevel};
+
+#[test]
+fn test_view_model_from_diff() {
+    let diff = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .color(true)
+        .build()
+        .expect("failed to build diff");
+
+    let result = diff.compare_text("line 1\nline 2\nline 3", "line 1\nchanged\nline 3")
+        .expect("diff failed");
+    let mut view = DiffView::new(&result).expect("failed to build view");
+
+    let kinds: Vec<LineKind> = view.lines().iter().map(|l| l.kind).collect();
+    assert_eq!(kinds, vec![
+        LineKind::Header,
+        LineKind::Header,
+        LineKind::Hunk,
+        LineKind::Context,
+        LineKind::Delete,
+        LineKind::Insert,
+        LineKind::Context,
+    ]);
+    // the viewer styles lines itself, so no ansi escapes leak through
+    assert!(view.lines().iter().all(|l| !l.text.contains('\x1b')));
+    assert_eq!(view.lines()[4].text, "-line 2");
+
+    let status = view.status_line();
+    assert!(status.contains(&format!("edit distance: {}", result.edit_distance())));
+    assert!(status.contains("similarity:"));
+
+    view.scroll_down(3);
+    assert_eq!(view.scroll(), 3);
+    view.scroll_down(100);
+    assert_eq!(view.scroll(), view.lines().len() - 1);
+    view.scroll_up(100);
+    assert_eq!(view.scroll(), 0);
+}
@@ -0,0 +1,41 @@
+//! native tests for the wasm wrapper logic
+//!
+//! `wasm_diff` itself only runs on wasm32, so these exercise the shared
+//! `diff_to_string` path it delegates to
+
+#![cfg(feature = "wasm")]
+
+use ctdiff::wasm::diff_to_string;
+use ctdiff::{DiffBuilder, OutputFormat, SecurityLevel};
+
+#[test]
+fn test_wrapper_matches_builder_output() {
+    let output = diff_to_string(b"hello\nworld\n", b"hello\nrust\n", "fast", "unified")
+        .expect("wrapper failed");
+
+    let expected = DiffBuilder::new()
+        .security_level(SecurityLevel::Fast)
+        .output_format(OutputFormat::Unified)
+        .build()
+        .expect("failed to build diff")
+        .compare(b"hello\nworld\n", b"hello\nrust\n")
+        .expect("diff failed")
+        .format()
+        .expect("format failed");
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_wrapper_reports_errors_as_strings() {
+    let err = diff_to_string(b"a", b"b", "paranoid", "json").unwrap_err();
+    assert!(err.to_string().contains("unknown security level"));
+
+    let err = diff_to_string(b"a", b"b", "fast", "pdf").unwrap_err();
+    assert!(!err.to_string().is_empty());
+
+    // maximum security rejects inputs over its 4kb limit
+    let large = vec![b'x'; 8 * 1024];
+    let err = diff_to_string(&large, &large, "maximum", "summary").unwrap_err();
+    assert!(err.to_string().contains("exceeds limit"));
+}